@@ -0,0 +1,530 @@
+use clap::{ColorChoice, ValueEnum};
+use color_eyre::Help;
+use eyre::{Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Cli;
+use crate::options::{CliClassWrapping, CliTailwindVersion};
+use crate::reporter::Reporter;
+
+/// The config file names looked up in each directory, in order of precedence.
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["rustywind.toml", ".rustywindrc.json"];
+
+/// The contents of a `rustywind.toml` or `.rustywindrc.json` config file, or of the file
+/// given with `--config-file`.
+///
+/// Every command line option can be set, with the same name in camelCase. Paths are
+/// relative to the directory of the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ConfigFile {
+    pub sort_order: Option<Vec<String>>,
+    pub output_css_file: Option<String>,
+    pub vite_css: Option<String>,
+    pub skip_ssl_verification: Option<bool>,
+    pub pattern_fallback: Option<bool>,
+    #[serde(default)]
+    pub write: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub check_formatted: bool,
+    #[serde(default)]
+    pub report_unknown: bool,
+    #[serde(default)]
+    pub diff: bool,
+    pub diff_context: Option<usize>,
+    pub color: Option<String>,
+    pub reporter: Option<String>,
+    pub allow_unknown: Option<Vec<String>>,
+    pub allow_duplicates: Option<bool>,
+    pub ignored_files: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "string_or_list")]
+    pub custom_regex: Option<Vec<String>>,
+    pub attributes: Option<Vec<String>>,
+    pub functions: Option<Vec<String>>,
+    pub preserve_whitespace: Option<bool>,
+    pub class_wrapping: Option<String>,
+    pub tailwind_prefix: Option<String>,
+    pub utility_extensions: Option<String>,
+    pub plugins: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub tailwind_version: Option<String>,
+    pub quiet: Option<bool>,
+    /// Extraction patterns per file type.
+    pub extract: Option<Vec<ExtractionRuleConfig>>,
+}
+
+/// An entry of `extract`: the files it applies to and the patterns that find their classes.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ExtractionRuleConfig {
+    pub files: Vec<String>,
+    pub patterns: Vec<ExtractionPatternConfig>,
+}
+
+/// A regex, the default one when not set, and how the classes it finds are wrapped.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ExtractionPatternConfig {
+    pub regex: Option<String>,
+    pub class_wrapping: Option<String>,
+}
+
+/// The options that can only be set in a config file.
+#[derive(Debug, Default)]
+pub struct ConfigFileOptions {
+    pub sort_order: Option<Vec<String>>,
+    pub extract: Vec<ExtractionRuleConfig>,
+}
+
+impl ConfigFile {
+    /// Reads a TOML (`.toml` extension) or JSON config file.
+    pub fn load(config_file: &Path) -> Result<Self> {
+        let name = config_file.display();
+
+        let file_contents = fs::read_to_string(config_file)
+            .wrap_err_with(|| format!("Error reading the config file {name}"))
+            .with_suggestion(|| format!("Make sure the file {name} exists"))?;
+
+        let config: ConfigFile = if config_file.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&file_contents)
+                .wrap_err_with(|| format!("Error while parsing the config file {name}"))
+        } else {
+            serde_json::from_str(&file_contents)
+                .wrap_err_with(|| format!("Error while parsing the config file {name}"))
+        }
+        .with_suggestion(|| {
+            format!("Make sure the {name} is valid toml or json, with the expected format")
+        })?;
+
+        config
+            .validate()
+            .wrap_err_with(|| format!("Invalid config file {name}"))?;
+
+        Ok(config)
+    }
+
+    /// Finds the nearest config file in `start` or its ancestors.
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start.ancestors().find_map(|directory| {
+            CONFIG_FILE_NAMES
+                .iter()
+                .map(|name| directory.join(name))
+                .find(|config_file| config_file.is_file())
+        })
+    }
+
+    fn validate(&self) -> Result<()> {
+        let sort_order_sources = [
+            ("sortOrder", self.sort_order.is_some()),
+            ("outputCssFile", self.output_css_file.is_some()),
+            ("viteCss", self.vite_css.is_some()),
+        ];
+        let modes = [
+            ("write", self.write),
+            ("dryRun", self.dry_run),
+            ("checkFormatted", self.check_formatted),
+            ("reportUnknown", self.report_unknown),
+            ("diff", self.diff),
+        ];
+
+        for options in [&sort_order_sources[..], &modes[..]] {
+            let set: Vec<&str> = options
+                .iter()
+                .filter(|(_name, is_set)| *is_set)
+                .map(|(name, _is_set)| *name)
+                .collect();
+
+            if set.len() > 1 {
+                eyre::bail!("`{}` can't be used together", set.join("` and `"));
+            }
+        }
+
+        let regex_option = [
+            ("attributes", self.attributes.is_some()),
+            ("functions", self.functions.is_some()),
+        ]
+        .into_iter()
+        .find_map(|(option, is_set)| is_set.then_some(option));
+
+        if let (Some(option), Some(_)) = (regex_option, &self.custom_regex) {
+            eyre::bail!("`customRegex` and `{option}` can't be used together");
+        }
+
+        let class_wrappings = self
+            .extract
+            .iter()
+            .flatten()
+            .flat_map(|rule| &rule.patterns)
+            .map(|pattern| &pattern.class_wrapping);
+
+        for class_wrapping in [&self.class_wrapping]
+            .into_iter()
+            .chain(class_wrappings)
+            .flatten()
+        {
+            CliClassWrapping::from_str(class_wrapping, false)
+                .map_err(|error| eyre::eyre!("invalid `classWrapping`: {error}"))?;
+        }
+
+        if let Some(reporter) = &self.reporter {
+            Reporter::from_str(reporter, false)
+                .map_err(|error| eyre::eyre!("invalid `reporter`: {error}"))?;
+        }
+
+        if let Some(color) = &self.color {
+            ColorChoice::from_str(color, false)
+                .map_err(|error| eyre::eyre!("invalid `color`: {error}"))?;
+        }
+
+        if let Some(tailwind_version) = &self.tailwind_version {
+            CliTailwindVersion::from_str(tailwind_version, false)
+                .map_err(|error| eyre::eyre!("invalid `tailwindVersion`: {error}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Fills in the options that were not set on the command line, and returns the options
+    /// that can only be set in a config file. The sort order is left out when the command
+    /// line sets another source for it, and likewise for the custom regexes, attributes and
+    /// functions.
+    pub fn apply_to(self, cli: &mut Cli, config_dir: &Path) -> ConfigFileOptions {
+        let resolve = |path: String| config_dir.join(path).to_string_lossy().into_owned();
+
+        let has_sort_order_source = cli.output_css_file.is_some() || cli.vite_css.is_some();
+        let sort_order = if has_sort_order_source {
+            None
+        } else {
+            cli.output_css_file = self.output_css_file.map(resolve);
+            cli.vite_css = self.vite_css;
+            self.sort_order
+        };
+
+        let has_mode =
+            cli.write || cli.dry_run || cli.check_formatted || cli.report_unknown || cli.diff;
+        if !has_mode && !cli.stdin {
+            cli.write = self.write;
+            cli.dry_run = self.dry_run;
+            cli.check_formatted = self.check_formatted;
+            cli.report_unknown = self.report_unknown;
+            cli.diff = self.diff;
+        }
+
+        cli.diff_context = cli.diff_context.or(self.diff_context);
+        if !cli.stdin && !cli.dry_run && !cli.report_unknown && !cli.diff {
+            cli.reporter = cli.reporter.or_else(|| {
+                let reporter = self.reporter?;
+                Reporter::from_str(&reporter, false).ok()
+            });
+        }
+        cli.color = cli.color.or_else(|| {
+            let color = self.color?;
+            ColorChoice::from_str(&color, false).ok()
+        });
+
+        merge_flag(
+            &mut cli.skip_ssl_verification,
+            &mut cli.no_skip_ssl_verification,
+            self.skip_ssl_verification,
+        );
+        merge_flag(
+            &mut cli.pattern_fallback,
+            &mut cli.no_pattern_fallback,
+            self.pattern_fallback,
+        );
+        merge_flag(
+            &mut cli.allow_duplicates,
+            &mut cli.no_allow_duplicates,
+            self.allow_duplicates,
+        );
+        merge_flag(
+            &mut cli.preserve_whitespace,
+            &mut cli.no_preserve_whitespace,
+            self.preserve_whitespace,
+        );
+        merge_flag(&mut cli.quiet, &mut cli.no_quiet, self.quiet);
+
+        cli.allow_unknown = cli.allow_unknown.take().or(self.allow_unknown);
+        cli.ignored_files = cli
+            .ignored_files
+            .take()
+            .or_else(|| Some(self.ignored_files?.into_iter().map(resolve).collect()));
+        if cli.custom_regex.is_none() && cli.attributes.is_none() && cli.functions.is_none() {
+            cli.custom_regex = self.custom_regex;
+            cli.attributes = self.attributes;
+            cli.functions = self.functions;
+        }
+        cli.class_wrapping = cli.class_wrapping.or_else(|| {
+            let class_wrapping = self.class_wrapping?;
+            CliClassWrapping::from_str(&class_wrapping, false).ok()
+        });
+        cli.tailwind_prefix = cli.tailwind_prefix.take().or(self.tailwind_prefix);
+        cli.utility_extensions = cli
+            .utility_extensions
+            .take()
+            .or_else(|| self.utility_extensions.map(resolve));
+        cli.plugins = cli.plugins.take().or(self.plugins);
+        cli.tailwind_version = cli.tailwind_version.or_else(|| {
+            let tailwind_version = self.tailwind_version?;
+            CliTailwindVersion::from_str(&tailwind_version, false).ok()
+        });
+
+        ConfigFileOptions {
+            sort_order,
+            extract: self.extract.unwrap_or_default(),
+        }
+    }
+}
+
+/// Sets a flag from the config file unless the command line sets it or its `--no-*` flag.
+/// A config file setting it to `false` sets the `--no-*` flag, so the prettier config can't
+/// turn it on.
+fn merge_flag(flag: &mut bool, no_flag: &mut bool, configured: Option<bool>) {
+    if *flag || *no_flag {
+        return;
+    }
+
+    match configured {
+        Some(true) => *flag = true,
+        Some(false) => *no_flag = true,
+        None => {}
+    }
+}
+
+/// Accepts `tailwindVersion = 3` as well as `tailwindVersion = "3"`.
+fn string_or_number<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    Ok(
+        Option::<StringOrNumber>::deserialize(deserializer)?.map(|value| match value {
+            StringOrNumber::String(value) => value,
+            StringOrNumber::Number(value) => value.to_string(),
+        }),
+    )
+}
+
+/// Accepts `customRegex = "..."` as well as a list of regexes.
+fn string_or_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(
+        Option::<StringOrList>::deserialize(deserializer)?.map(|value| match value {
+            StringOrList::String(value) => vec![value],
+            StringOrList::List(values) => values,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use clap::Parser;
+    use rustywind_core::tailwind_version::TailwindVersion;
+
+    fn parse_toml(contents: &str) -> Result<ConfigFile> {
+        let config: ConfigFile = toml::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_config_file_sets_options_not_given_on_the_command_line() {
+        let config = parse_toml(
+            r#"
+            tailwindPrefix = "tw"
+            tailwindVersion = 3
+            classWrapping = "comma-single-quotes"
+            allowDuplicates = true
+            write = true
+            ignoredFiles = ["ignored.html"]
+            sortOrder = ["btn"]
+            "#,
+        )
+        .unwrap();
+
+        let mut cli = Cli::parse_from(["rustywind", "--tailwind-prefix", "app", "."]);
+        let config_options = config.apply_to(&mut cli, Path::new("project"));
+
+        assert_eq!(config_options.sort_order, Some(vec!["btn".to_string()]));
+        assert_eq!(cli.tailwind_prefix.as_deref(), Some("app"));
+        assert_eq!(
+            cli.tailwind_version.map(TailwindVersion::from),
+            Some(TailwindVersion::V3)
+        );
+        assert!(cli.class_wrapping.is_some());
+        assert!(cli.allow_duplicates);
+        assert!(cli.write);
+        assert_eq!(
+            cli.ignored_files,
+            Some(vec![
+                Path::new("project")
+                    .join("ignored.html")
+                    .to_string_lossy()
+                    .into_owned()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_command_line_overrides_sort_order_and_mode() {
+        let config = parse_toml(
+            r#"
+            sortOrder = ["btn"]
+            write = true
+            "#,
+        )
+        .unwrap();
+
+        let mut cli =
+            Cli::parse_from(["rustywind", "--output-css-file", "a.css", "--dry-run", "."]);
+        let config_options = config.apply_to(&mut cli, Path::new(""));
+
+        assert_eq!(config_options.sort_order, None);
+        assert_eq!(cli.output_css_file.as_deref(), Some("a.css"));
+        assert!(cli.dry_run);
+        assert!(!cli.write);
+    }
+
+    #[test]
+    fn test_command_line_overrides_config_flags() {
+        let config = parse_toml(
+            r#"
+            allowDuplicates = true
+            patternFallback = true
+            preserveWhitespace = false
+            quiet = true
+            "#,
+        )
+        .unwrap();
+
+        let mut cli = Cli::parse_from([
+            "rustywind",
+            "--no-allow-duplicates",
+            "--no-quiet",
+            "--preserve-whitespace",
+            ".",
+        ]);
+        config.apply_to(&mut cli, Path::new(""));
+
+        assert!(!cli.allow_duplicates);
+        assert!(cli.pattern_fallback);
+        assert!(cli.preserve_whitespace);
+        assert!(!cli.quiet);
+
+        let config = parse_toml("preserveWhitespace = false").unwrap();
+        let mut cli = Cli::parse_from(["rustywind", "."]);
+        config.apply_to(&mut cli, Path::new(""));
+
+        assert!(!cli.preserve_whitespace);
+        assert!(cli.no_preserve_whitespace);
+    }
+
+    #[test]
+    fn test_last_of_a_flag_and_its_negation_wins() {
+        let cli = Cli::parse_from([
+            "rustywind",
+            "--allow-duplicates",
+            "--no-allow-duplicates",
+            ".",
+        ]);
+        assert!(!cli.allow_duplicates);
+        assert!(cli.no_allow_duplicates);
+
+        let cli = Cli::parse_from([
+            "rustywind",
+            "--no-allow-duplicates",
+            "--allow-duplicates",
+            ".",
+        ]);
+        assert!(cli.allow_duplicates);
+        assert!(!cli.no_allow_duplicates);
+    }
+
+    #[test]
+    fn test_custom_regex_is_a_regex_or_a_list() {
+        let single = parse_toml(r#"customRegex = 'tw="([^"]*)"'"#).unwrap();
+        assert_eq!(
+            single.custom_regex,
+            Some(vec![r#"tw="([^"]*)""#.to_string()])
+        );
+
+        let list = parse_toml(r#"customRegex = ['tw="([^"]*)"', "x-(\\S+)"]"#).unwrap();
+        assert_eq!(
+            list.custom_regex,
+            Some(vec![r#"tw="([^"]*)""#.to_string(), r"x-(\S+)".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_files() {
+        let unknown_key = parse_toml("tailwindPrefx = \"tw\"").unwrap_err();
+        assert!(format!("{unknown_key:?}").contains("unknown field `tailwindPrefx`"));
+
+        let sort_order_sources =
+            parse_toml("sortOrder = []\noutputCssFile = \"a.css\"").unwrap_err();
+        assert_eq!(
+            sort_order_sources.to_string(),
+            "`sortOrder` and `outputCssFile` can't be used together"
+        );
+
+        let custom_regex_and_functions =
+            parse_toml("customRegex = \"a(b)\"\nfunctions = [\"clsx\"]").unwrap_err();
+        assert_eq!(
+            custom_regex_and_functions.to_string(),
+            "`customRegex` and `functions` can't be used together"
+        );
+
+        assert!(parse_toml("tailwindVersion = 2").is_err());
+        assert!(parse_toml("classWrapping = \"commas\"").is_err());
+        assert!(
+            parse_toml(
+                r#"
+                [[extract]]
+                files = ["*.rb"]
+                patterns = [{ classWrapping = "commas" }]
+                "#
+            )
+            .is_err()
+        );
+        assert!(parse_toml("[[extract]]\nfiles = []\npatterns = []\nglobs = []").is_err());
+    }
+
+    #[test]
+    fn test_find_nearest_config_file() {
+        let project = TempProject::new("config");
+        let root = project.path();
+        let nested = root.join("packages").join("app");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(".rustywindrc.json"), "{}").unwrap();
+
+        assert_eq!(
+            ConfigFile::find(&nested),
+            Some(root.join(".rustywindrc.json"))
+        );
+
+        fs::write(nested.join("rustywind.toml"), "").unwrap();
+        assert_eq!(
+            ConfigFile::find(&nested),
+            Some(nested.join("rustywind.toml"))
+        );
+    }
+}
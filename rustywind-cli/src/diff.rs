@@ -0,0 +1,121 @@
+use anstyle::{AnsiColor, Style};
+use similar::TextDiff;
+
+const HEADER: Style = Style::new().bold();
+const HUNK: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Cyan)));
+const REMOVED: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Red)));
+const ADDED: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Green)));
+
+/// A unified diff between the original and the sorted contents of a file, with
+/// `context_lines` unchanged lines around each change. Empty when nothing changed.
+pub fn unified_diff(
+    file_name: &str,
+    original: &str,
+    sorted: &str,
+    context_lines: usize,
+    color: bool,
+) -> String {
+    if original == sorted {
+        return String::new();
+    }
+
+    let (old_name, new_name) = if file_name.starts_with('/') || file_name.starts_with('<') {
+        (file_name.to_string(), file_name.to_string())
+    } else {
+        (format!("a/{file_name}"), format!("b/{file_name}"))
+    };
+
+    let diff = TextDiff::from_lines(original, sorted)
+        .unified_diff()
+        .context_radius(context_lines)
+        .header(&old_name, &new_name)
+        .to_string();
+
+    if !color {
+        return diff;
+    }
+
+    diff.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let style = match line.as_bytes().first() {
+                // the `---` and `+++` headers
+                _ if index < 2 => HEADER,
+                Some(b'@') => HUNK,
+                Some(b'-') => REMOVED,
+                Some(b'+') => ADDED,
+                _ => Style::new(),
+            };
+
+            format!("{}{line}{}\n", style.render(), style.render_reset())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const ORIGINAL: &str = indoc! {r#"
+        <div>
+          <a class="p-4 flex"></a>
+          <b></b>
+          <c></c>
+          <d></d>
+          <e class="p-4 flex"></e>
+        </div>
+    "#};
+
+    const SORTED: &str = indoc! {r#"
+        <div>
+          <a class="flex p-4"></a>
+          <b></b>
+          <c></c>
+          <d></d>
+          <e class="flex p-4"></e>
+        </div>
+    "#};
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(
+            unified_diff("src/index.html", ORIGINAL, SORTED, 1, false),
+            indoc! {r#"
+                --- a/src/index.html
+                +++ b/src/index.html
+                @@ -1,3 +1,3 @@
+                 <div>
+                -  <a class="p-4 flex"></a>
+                +  <a class="flex p-4"></a>
+                   <b></b>
+                @@ -5,3 +5,3 @@
+                   <d></d>
+                -  <e class="p-4 flex"></e>
+                +  <e class="flex p-4"></e>
+                 </div>
+            "#}
+        );
+
+        assert!(
+            unified_diff("src/index.html", ORIGINAL, SORTED, 3, false).contains("@@ -1,7 +1,7 @@")
+        );
+        assert_eq!(
+            unified_diff("src/index.html", ORIGINAL, ORIGINAL, 3, false),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_colored_unified_diff() {
+        let diff = unified_diff("<stdin>", ORIGINAL, SORTED, 0, true);
+
+        assert!(diff.starts_with(&format!("{}--- <stdin>", HEADER.render())));
+        assert!(diff.contains(&format!(
+            "{}-  <a class=\"p-4 flex\"></a>{}",
+            REMOVED.render(),
+            REMOVED.render_reset()
+        )));
+        assert!(diff.contains(&format!("{}+  <a", ADDED.render())));
+    }
+}
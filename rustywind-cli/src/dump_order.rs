@@ -0,0 +1,111 @@
+use clap::{ArgGroup, Args};
+use color_eyre::Help;
+use eyre::{Context, Result};
+use rustywind_core::sorter::Sorter;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::config::ConfigFile;
+use crate::options::get_custom_sorter;
+
+#[derive(Args, Debug, Clone)]
+#[command(group(
+    ArgGroup::new("sort_order_source")
+        .args(["config_file", "output_css_file", "vite_css"])
+        .required(true)
+))]
+pub struct DumpOrderArgs {
+    /// A config file with a sortOrder, outputCssFile or viteCss property, e.g.
+    /// { "sortOrder": ["class1", ...] }.
+    #[arg(long)]
+    config_file: Option<String>,
+    /// Derive the sort order from the order the classes appear in the given css file.
+    #[arg(long)]
+    output_css_file: Option<String>,
+    /// Derive the sort order from the CSS file that vite generates, given its full URL.
+    #[arg(long)]
+    vite_css: Option<String>,
+    /// Skip SSL verification for the vite_css option.
+    #[arg(long, requires = "vite_css")]
+    skip_ssl_verification: bool,
+    /// The file to write the sort order to, prints it to the terminal when not set.
+    #[arg(long, short)]
+    output: Option<String>,
+}
+
+pub fn run(args: DumpOrderArgs) -> Result<()> {
+    let sorter = match &args.config_file {
+        Some(config_file) => get_config_file_sorter(Path::new(config_file))?,
+        None => get_custom_sorter(
+            None,
+            args.output_css_file.as_deref(),
+            args.vite_css.as_deref(),
+            args.skip_ssl_verification,
+        )?,
+    };
+
+    if sorter.sort_order().is_none() {
+        return Err(eyre::eyre!("the config file has no sort order"))
+            .with_suggestion(|| "Set sortOrder, outputCssFile or viteCss in the config file");
+    }
+
+    match &args.output {
+        Some(output) => {
+            let file = File::create(output)
+                .wrap_err_with(|| format!("Error creating the file {output}"))
+                .with_suggestion(|| format!("Make sure the directory of {output} exists"))?;
+
+            let mut writer = BufWriter::new(file);
+            sorter.write_sort_order(&mut writer)?;
+            writer
+                .flush()
+                .wrap_err_with(|| format!("Error writing the file {output}"))?;
+        }
+        None => sorter.write_sort_order(std::io::stdout().lock())?,
+    }
+
+    Ok(())
+}
+
+fn get_config_file_sorter(config_file: &Path) -> Result<Sorter> {
+    let config = ConfigFile::load(config_file)?;
+    let config_dir = config_file.parent().unwrap_or(Path::new(""));
+
+    get_custom_sorter(
+        config.sort_order,
+        config
+            .output_css_file
+            .map(|css_file| config_dir.join(css_file).to_string_lossy().into_owned())
+            .as_deref(),
+        config.vite_css.as_deref(),
+        config.skip_ssl_verification.unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dumped_order_is_a_valid_config_file() {
+        let css_file = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../rustywind-core/tests/fixtures/tailwind.css"
+        );
+        let sorter = get_custom_sorter(None, Some(css_file), None, false).unwrap();
+
+        let mut json = Vec::new();
+        sorter.write_sort_order(&mut json).unwrap();
+
+        let config: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let sort_order: Vec<&str> = config["sortOrder"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|class| class.as_str().unwrap())
+            .collect();
+
+        assert_eq!(Some(sort_order), sorter.sorted_classes());
+    }
+}
@@ -0,0 +1,173 @@
+use clap::Args;
+use eyre::Result;
+use rustywind_core::RustyWind;
+use rustywind_core::explain::{ClassExplanation, Explanation};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::options::{CliClassWrapping, CliTailwindVersion, get_utility_extensions, plugin_names};
+
+#[derive(Args, Debug, Clone)]
+pub struct ExplainArgs {
+    /// The classes to explain, either as separate arguments or as a single quoted list.
+    #[arg(value_name = "CLASSES", required = true)]
+    classes: Vec<String>,
+    /// Specify how individual classes are wrapped.
+    #[arg(long)]
+    class_wrapping: Option<CliClassWrapping>,
+    /// Tailwind prefix used when sorting classes, e.g. tw for tw: or tw- classes.
+    #[arg(long)]
+    tailwind_prefix: Option<String>,
+    /// A TOML or JSON file that adds or overrides utility to CSS property mappings,
+    /// declaration counts and property order entries of the built-in sorter.
+    #[arg(long)]
+    utility_extensions: Option<String>,
+    /// Official Tailwind plugins whose utilities and variants the sorter should know.
+    #[arg(long, value_delimiter = ',', value_parser = plugin_names())]
+    plugins: Option<Vec<String>>,
+    /// The Tailwind version whose class order to follow.
+    #[arg(long)]
+    tailwind_version: Option<CliTailwindVersion>,
+}
+
+pub fn run(args: ExplainArgs) -> Result<()> {
+    let rustywind = RustyWind::default()
+        .with_class_wrapping(args.class_wrapping.map(Into::into).unwrap_or_default())
+        .with_tailwind_prefix(args.tailwind_prefix)
+        .with_utility_extensions(
+            get_utility_extensions(
+                args.plugins.iter().flatten(),
+                args.utility_extensions.as_deref(),
+            )?
+            .map(Arc::new),
+        )
+        .with_tailwind_version(args.tailwind_version.map(Into::into).unwrap_or_default());
+
+    let explanation = rustywind.explain_classes(&args.classes.join(" "));
+    print!("{}", format_explanation(&explanation));
+
+    Ok(())
+}
+
+fn format_explanation(explanation: &Explanation) -> String {
+    let mut output = format!("sorted: {}\n", explanation.sorted_classes().join(" "));
+
+    output.push_str("\nclasses:\n");
+    for class in &explanation.classes {
+        output.push_str(&format_class(class));
+    }
+
+    if !explanation.pairs.is_empty() {
+        output.push_str("\ndecided by:\n");
+    }
+
+    for pair in &explanation.pairs {
+        let ordering = match pair.ordering {
+            Ordering::Less => "<",
+            Ordering::Equal => "=",
+            Ordering::Greater => ">",
+        };
+
+        output.push_str(&format!(
+            "  {} {ordering} {}: {}\n",
+            pair.left,
+            pair.right,
+            pair.decision.as_str()
+        ));
+    }
+
+    output
+}
+
+fn format_class(class: &ClassExplanation) -> String {
+    let Some(key) = &class.sort_key else {
+        return format!(
+            "  {}\n    unknown class, sorted to the front\n",
+            class.class
+        );
+    };
+
+    let mut variants: Vec<String> = class
+        .variant_bits()
+        .into_iter()
+        .map(|(bit, variant)| format!("{variant} (bit {bit})"))
+        .collect();
+
+    if class.has_arbitrary_variant() {
+        variants.push("arbitrary".to_string());
+    }
+
+    let variant_chain: Vec<String> = key.variant_chain.iter().map(|v| v.to_string()).collect();
+
+    let properties: Vec<String> = class
+        .properties
+        .iter()
+        .map(|(property, index)| format!("{property} ({index})"))
+        .collect();
+
+    let numeric_value = key
+        .numeric_value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "none".to_string());
+
+    format!(
+        "  {}\n    \
+         variant order: {:#x} [{}]\n    \
+         variant chain: [{}]\n    \
+         properties: {}\n    \
+         numeric value: {numeric_value}\n    \
+         negative: {}\n    \
+         declaration count: {}\n    \
+         unparseable: {}\n",
+        class.class,
+        key.variant_order,
+        variants.join(", "),
+        variant_chain.join(", "),
+        properties.join(", "),
+        key.is_negative,
+        key.property_count,
+        key.is_unparseable,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_format_explanation() {
+        let explanation = RustyWind::default().explain_classes("hover:p-4 unknown-class m-4");
+
+        assert_eq!(
+            format_explanation(&explanation),
+            indoc! {"
+                sorted: unknown-class m-4 hover:p-4
+
+                classes:
+                  unknown-class
+                    unknown class, sorted to the front
+                  m-4
+                    variant order: 0x0 []
+                    variant chain: []
+                    properties: margin (26)
+                    numeric value: 4
+                    negative: false
+                    declaration count: 1
+                    unparseable: false
+                  hover:p-4
+                    variant order: 0x400000000000 [hover (bit 46)]
+                    variant chain: [hover]
+                    properties: padding (251)
+                    numeric value: 4
+                    negative: false
+                    declaration count: 1
+                    unparseable: false
+
+                decided by:
+                  unknown-class < m-4: unknown class
+                  m-4 < hover:p-4: base class
+            "}
+        );
+    }
+}
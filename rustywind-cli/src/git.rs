@@ -0,0 +1,448 @@
+use color_eyre::Help;
+use eyre::{Context, Result};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use ahash::AHashMap as HashMap;
+use ahash::AHashSet as HashSet;
+
+use crate::Cli;
+
+/// The files to run on, taken from git instead of every file of the paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitSelection {
+    /// Files changed in the working tree or the index since a revision, and untracked files
+    ChangedSince(String),
+    /// Files added or changed in the index
+    Staged,
+    /// Files changed since the merge base of `HEAD` and a branch, and untracked files
+    SinceMergeBase(String),
+}
+
+impl GitSelection {
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        if let Some(revision) = &cli.changed_since {
+            Some(GitSelection::ChangedSince(revision.clone()))
+        } else if cli.staged {
+            Some(GitSelection::Staged)
+        } else {
+            cli.since_merge_base
+                .as_ref()
+                .map(|branch| GitSelection::SinceMergeBase(branch.clone()))
+        }
+    }
+
+    /// The canonical paths of the selected files of the repositories containing the starting
+    /// paths. Deleted files are left out.
+    pub fn files(&self, starting_paths: &[PathBuf]) -> Result<HashSet<PathBuf>> {
+        let mut files = HashSet::new();
+
+        for top_level in repository_roots(starting_paths)? {
+            files.extend(self.files_in_repository(&top_level)?);
+        }
+
+        Ok(files)
+    }
+
+    fn files_in_repository(&self, top_level: &Path) -> Result<Vec<PathBuf>> {
+        let mut names = match self {
+            GitSelection::Staged => git(
+                top_level,
+                &["diff", "--name-only", "-z", "--diff-filter=d", "--cached"],
+            )?,
+            GitSelection::ChangedSince(revision) => git(
+                top_level,
+                &[
+                    "diff",
+                    "--name-only",
+                    "-z",
+                    "--diff-filter=d",
+                    revision,
+                    "--",
+                ],
+            )?,
+            GitSelection::SinceMergeBase(branch) => {
+                let merge_base = git(top_level, &["merge-base", branch, "HEAD"])?;
+                git(
+                    top_level,
+                    &[
+                        "diff",
+                        "--name-only",
+                        "-z",
+                        "--diff-filter=d",
+                        merge_base.trim(),
+                        "--",
+                    ],
+                )?
+            }
+        };
+
+        if !matches!(self, GitSelection::Staged) {
+            names.push_str(&git(
+                top_level,
+                &["ls-files", "-z", "--others", "--exclude-standard"],
+            )?);
+        }
+
+        Ok(names
+            .split('\0')
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| top_level.join(name).canonicalize().ok())
+            .collect())
+    }
+}
+
+/// The files staged in the git index of the repositories containing the starting paths.
+/// With `--staged`, files are read from the index, and sorted files are written to both the
+/// index and the working tree, so partially staged files are never mixed up.
+#[derive(Debug)]
+pub struct GitIndex {
+    entries: HashMap<PathBuf, IndexEntry>,
+    /// Held while updating the index, which git locks on each update.
+    write_lock: Mutex<()>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    top_level: PathBuf,
+    name: String,
+    mode: String,
+    object: String,
+    /// Whether the working tree file differs from the staged file
+    unstaged_changes: bool,
+}
+
+impl GitIndex {
+    pub fn new(starting_paths: &[PathBuf]) -> Result<Self> {
+        let mut entries = HashMap::new();
+
+        for top_level in repository_roots(starting_paths)? {
+            let unstaged = git(&top_level, &["diff", "--name-only", "-z"])?;
+            let unstaged: HashSet<&str> = unstaged.split('\0').collect();
+
+            // `:<old mode> <new mode> <old object> <new object> <status>` and the name of
+            // each staged file
+            let staged = git(
+                &top_level,
+                &[
+                    "diff",
+                    "--cached",
+                    "--raw",
+                    "-z",
+                    "--no-renames",
+                    "--diff-filter=d",
+                ],
+            )?;
+            let mut fields = staged.split('\0');
+
+            while let (Some(status), Some(name)) = (fields.next(), fields.next()) {
+                let status: Vec<&str> = status.split(' ').collect();
+                let (Some(mode), Some(object)) = (status.get(1), status.get(3)) else {
+                    continue;
+                };
+
+                let Ok(path) = top_level.join(name).canonicalize() else {
+                    continue;
+                };
+
+                entries.insert(
+                    path,
+                    IndexEntry {
+                        top_level: top_level.clone(),
+                        name: name.to_string(),
+                        mode: mode.to_string(),
+                        object: object.to_string(),
+                        unstaged_changes: unstaged.contains(name),
+                    },
+                );
+            }
+        }
+
+        Ok(GitIndex {
+            entries,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn entry(&self, file_path: &Path) -> io::Result<&IndexEntry> {
+        file_path
+            .canonicalize()
+            .ok()
+            .and_then(|path| self.entries.get(&path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file is not staged"))
+    }
+
+    /// Reads the staged contents of a file.
+    pub fn read(&self, file_path: &Path) -> io::Result<String> {
+        let entry = self.entry(file_path)?;
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&entry.top_level)
+            .args(["cat-file", "blob", &entry.object])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        String::from_utf8(output.stdout)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Stages the contents of a file and checks it out to the working tree. Files whose
+    /// working tree differs from the index are left alone.
+    pub fn write(&self, file_path: &Path, contents: &str) -> Result<()> {
+        let entry = self.entry(file_path)?;
+
+        if entry.unstaged_changes {
+            return Err(eyre::eyre!(
+                "the file has unstaged changes, its staged classes were not sorted"
+            ))
+            .with_suggestion(|| "Stage or stash the changes of the file and run again");
+        }
+
+        let mut hash_object = Command::new("git")
+            .arg("-C")
+            .arg(&entry.top_level)
+            .args(["hash-object", "-w", "--no-filters", "--stdin"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err("Error running git")?;
+
+        if let Some(mut stdin) = hash_object.stdin.take() {
+            stdin
+                .write_all(contents.as_bytes())
+                .wrap_err("Error writing to git hash-object")?;
+        }
+
+        let output = hash_object.wait_with_output()?;
+        if !output.status.success() {
+            eyre::bail!(
+                "git hash-object failed for {}: {}",
+                entry.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let object = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let cache_info = format!("{},{object},{}", entry.mode, entry.name);
+
+        let _lock = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        git(
+            &entry.top_level,
+            &["update-index", "--cacheinfo", &cache_info],
+        )?;
+        git(
+            &entry.top_level,
+            &["checkout-index", "--force", "--", &entry.name],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The root directories of the repositories containing the starting paths.
+fn repository_roots(starting_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut repositories = vec![];
+
+    for starting_path in starting_paths {
+        let directory = if starting_path.is_dir() {
+            starting_path.as_path()
+        } else {
+            starting_path.parent().unwrap_or(Path::new(""))
+        };
+
+        let top_level = repository_root(directory)?;
+        if !repositories.contains(&top_level) {
+            repositories.push(top_level);
+        }
+    }
+
+    Ok(repositories)
+}
+
+fn repository_root(directory: &Path) -> Result<PathBuf> {
+    let top_level = git(directory, &["rev-parse", "--show-toplevel"]).with_suggestion(
+        || "Only use --changed-since, --staged and --since-merge-base in a git repository",
+    )?;
+
+    Path::new(top_level.trim_end_matches('\n'))
+        .canonicalize()
+        .wrap_err_with(|| format!("Error reading the git repository {}", top_level.trim_end()))
+}
+
+/// Runs git in `directory`, returning its output.
+fn git(directory: &Path, args: &[&str]) -> Result<String> {
+    let directory = if directory.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        directory
+    };
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .output()
+        .wrap_err("Error running git")
+        .with_suggestion(|| "Make sure git is installed and in your PATH")?;
+
+    if !output.status.success() {
+        eyre::bail!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            directory.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).wrap_err("git printed file names that are not UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use std::fs;
+
+    /// Runs git in a test repository, with an identity for the commits.
+    fn run_git(repository: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(repository)
+            .args([
+                "-c",
+                "user.name=rustywind",
+                "-c",
+                "user.email=rustywind@example.com",
+            ])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn file_names(files: HashSet<PathBuf>) -> Vec<String> {
+        let mut names: Vec<_> = files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_git_selections() {
+        let project = TempProject::new("git");
+        let root = project.path().to_path_buf();
+
+        run_git(&root, &["init", "--quiet", "--initial-branch=main"]);
+        for name in ["a.html", "b.html", "deleted.html"] {
+            fs::write(root.join(name), "<div class=\"p-4 flex\"></div>").unwrap();
+        }
+        fs::write(root.join(".gitignore"), "ignored.html\n").unwrap();
+        run_git(&root, &["add", "."]);
+        run_git(&root, &["commit", "--quiet", "-m", "initial"]);
+        run_git(&root, &["checkout", "--quiet", "-b", "feature"]);
+
+        fs::write(root.join("a.html"), "<div class=\"flex\"></div>").unwrap();
+        run_git(&root, &["add", "a.html"]);
+        run_git(&root, &["commit", "--quiet", "-m", "change a"]);
+
+        fs::write(root.join("b.html"), "<div class=\"m-2 flex\"></div>").unwrap();
+        fs::write(root.join("c.html"), "<div class=\"m-2 flex\"></div>").unwrap();
+        fs::write(root.join("ignored.html"), "").unwrap();
+        fs::remove_file(root.join("deleted.html")).unwrap();
+        run_git(&root, &["add", "b.html"]);
+        fs::write(root.join("b.html"), "<div class=\"m-4 flex\"></div>").unwrap();
+
+        let starting_paths = [root.clone()];
+
+        assert_eq!(
+            file_names(GitSelection::Staged.files(&starting_paths).unwrap()),
+            vec!["b.html"]
+        );
+        assert_eq!(
+            file_names(
+                GitSelection::ChangedSince("HEAD".to_string())
+                    .files(&starting_paths)
+                    .unwrap()
+            ),
+            vec!["b.html", "c.html"]
+        );
+        assert_eq!(
+            file_names(
+                GitSelection::SinceMergeBase("main".to_string())
+                    .files(&starting_paths)
+                    .unwrap()
+            ),
+            vec!["a.html", "b.html", "c.html"]
+        );
+        assert!(
+            GitSelection::ChangedSince("no-such-revision".to_string())
+                .files(&starting_paths)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_git_index_reads_and_writes_staged_contents() {
+        let project = TempProject::new("index");
+        let root = project.path().to_path_buf();
+
+        run_git(&root, &["init", "--quiet"]);
+        fs::write(root.join("staged.html"), "<a class=\"p-4 flex\"></a>").unwrap();
+        fs::write(root.join("partial.html"), "<a class=\"p-4 flex\"></a>").unwrap();
+        run_git(&root, &["add", "."]);
+        fs::write(root.join("partial.html"), "<a class=\"m-2 flex\"></a>").unwrap();
+
+        let git_index = GitIndex::new(std::slice::from_ref(&root)).unwrap();
+        let staged = root.join("staged.html");
+        let partial = root.join("partial.html");
+
+        assert_eq!(
+            git_index.read(&partial).unwrap(),
+            "<a class=\"p-4 flex\"></a>"
+        );
+
+        git_index
+            .write(&staged, "<a class=\"flex p-4\"></a>")
+            .unwrap();
+        assert_eq!(
+            git(&root, &["show", ":staged.html"]).unwrap(),
+            "<a class=\"flex p-4\"></a>"
+        );
+        assert_eq!(
+            fs::read_to_string(&staged).unwrap(),
+            "<a class=\"flex p-4\"></a>"
+        );
+
+        let error = git_index
+            .write(&partial, "<a class=\"flex p-4\"></a>")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the file has unstaged changes, its staged classes were not sorted"
+        );
+        assert_eq!(
+            git(&root, &["show", ":partial.html"]).unwrap(),
+            "<a class=\"p-4 flex\"></a>"
+        );
+        assert_eq!(
+            fs::read_to_string(&partial).unwrap(),
+            "<a class=\"m-2 flex\"></a>"
+        );
+    }
+}
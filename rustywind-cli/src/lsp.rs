@@ -0,0 +1,929 @@
+use clap::Args;
+use eyre::{Context, Result};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+    DidSaveTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Formatting, RangeFormatting, RegisterCapability, Request as _,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesRegistrationOptions,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    DocumentFormattingParams, DocumentRangeFormattingParams, FileChangeType, FileSystemWatcher,
+    GlobPattern, InitializeResult, NumberOrString, OneOf, Position, PublishDiagnosticsParams,
+    Range, Registration, RegistrationParams, ServerCapabilities, ServerInfo,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, TextEdit, Url, WorkspaceEdit,
+};
+use rustywind_core::RustyWind;
+use rustywind_core::diagnostics::{UnknownAllowlist, UnknownKind};
+use rustywind_core::edit::{ClassListEdit, SortRange};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ahash::AHashMap as HashMap;
+use ahash::AHashSet as HashSet;
+
+use crate::config::CONFIG_FILE_NAMES;
+use crate::options::{ConfigFiles, ProjectOptions};
+use crate::prettier::PRETTIER_CONFIG_FILE_NAMES;
+
+const SOURCE: &str = "rustywind";
+
+#[derive(Args, Debug, Clone)]
+pub struct LspArgs {
+    /// Accepted for the editor clients that pass it, stdio is the only transport.
+    #[arg(long)]
+    stdio: bool,
+}
+
+/// Runs the language server over stdio until the client exits.
+pub fn run(_args: LspArgs) -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    serve(&connection)?;
+    drop(connection);
+
+    io_threads
+        .join()
+        .wrap_err("Error closing the language server connection")
+}
+
+fn serve(connection: &Connection) -> Result<()> {
+    let (initialize_id, initialize_params) = connection
+        .initialize_start()
+        .wrap_err("Error initializing the language server")?;
+
+    let initialize_result = InitializeResult {
+        capabilities: capabilities(),
+        server_info: Some(ServerInfo {
+            name: SOURCE.to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    };
+
+    connection
+        .initialize_finish(initialize_id, serde_json::to_value(initialize_result)?)
+        .wrap_err("Error initializing the language server")?;
+
+    if initialize_params["capabilities"]["workspace"]["didChangeWatchedFiles"]["dynamicRegistration"]
+        == true
+    {
+        connection
+            .sender
+            .send(Message::Request(watch_config_files_request()))?;
+    }
+
+    let mut server = Server::default();
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                for diagnostics in server.handle_notification(notification) {
+                    connection.sender.send(Message::Notification(diagnostics))?;
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::SOURCE_FIX_ALL,
+            ]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+}
+
+/// Asks the client to send `workspace/didChangeWatchedFiles` for the config files, so
+/// the options follow config files changed outside the editor.
+fn watch_config_files_request() -> Request {
+    let watchers = CONFIG_FILE_NAMES
+        .iter()
+        .chain(PRETTIER_CONFIG_FILE_NAMES.iter())
+        .map(|name| FileSystemWatcher {
+            glob_pattern: GlobPattern::String(format!("**/{name}")),
+            kind: None,
+        })
+        .collect();
+
+    Request::new(
+        "rustywind/watch-config-files".to_string().into(),
+        RegisterCapability::METHOD.to_string(),
+        RegistrationParams {
+            registrations: vec![Registration {
+                id: "rustywind/watch-config-files".to_string(),
+                method: DidChangeWatchedFiles::METHOD.to_string(),
+                register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
+                    watchers,
+                })
+                .ok(),
+            }],
+        },
+    )
+}
+
+/// The open documents and the project options of their directories. The options, and the
+/// sorters the core crate caches, stay warm across requests.
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, String>,
+    /// The nearest config files of each directory.
+    directory_config_files: HashMap<PathBuf, ConfigFiles>,
+    /// The project options of each pair of config files, shared by the directories using
+    /// them.
+    config_file_options: HashMap<ConfigFiles, Arc<ProjectOptions>>,
+    default_options: Option<Arc<ProjectOptions>>,
+}
+
+impl Server {
+    fn handle_request(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+
+        let result = match request.method.as_str() {
+            Formatting::METHOD => parse_params(request).and_then(|params| {
+                let params: DocumentFormattingParams = params;
+                to_value(self.formatting(&params.text_document.uri, None))
+            }),
+            RangeFormatting::METHOD => parse_params(request).and_then(|params| {
+                let params: DocumentRangeFormattingParams = params;
+                to_value(self.formatting(&params.text_document.uri, Some(params.range)))
+            }),
+            CodeActionRequest::METHOD => {
+                parse_params(request).and_then(|params| to_value(self.code_actions(&params)))
+            }
+            method => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request {method}"),
+                );
+            }
+        };
+
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err(error) => Response::new_err(id, ErrorCode::InvalidParams as i32, error.to_string()),
+        }
+    }
+
+    /// Updates the open documents, returning the diagnostics to publish.
+    fn handle_notification(&mut self, notification: Notification) -> Vec<Notification> {
+        let changed_documents = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = parse_notification::<DidOpenTextDocumentParams>(notification)
+                else {
+                    return vec![];
+                };
+
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                vec![uri]
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = parse_notification::<DidChangeTextDocumentParams>(notification)
+                else {
+                    return vec![];
+                };
+
+                // with full sync, each change is the whole document
+                let uri = params.text_document.uri;
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                vec![uri]
+            }
+            DidSaveTextDocument::METHOD => {
+                let Some(params) = parse_notification::<DidSaveTextDocumentParams>(notification)
+                else {
+                    return vec![];
+                };
+
+                match config_file_path(&params.text_document.uri) {
+                    Some(path) => self.config_file_changed(&path, false),
+                    None => return vec![],
+                }
+            }
+            DidChangeWatchedFiles::METHOD => {
+                let Some(params) = parse_notification::<DidChangeWatchedFilesParams>(notification)
+                else {
+                    return vec![];
+                };
+
+                let mut changed_documents = HashSet::new();
+                for change in params.changes {
+                    if let Some(path) = config_file_path(&change.uri) {
+                        let is_created_or_deleted = change.typ != FileChangeType::CHANGED;
+                        changed_documents
+                            .extend(self.config_file_changed(&path, is_created_or_deleted));
+                    }
+                }
+                changed_documents.into_iter().collect()
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = parse_notification::<DidCloseTextDocumentParams>(notification)
+                else {
+                    return vec![];
+                };
+
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                return vec![publish_diagnostics(uri, vec![])];
+            }
+            _ => return vec![],
+        };
+
+        changed_documents
+            .into_iter()
+            .filter_map(|uri| {
+                let text = self.documents.get(&uri)?.clone();
+                let diagnostics = self.diagnostics(&uri, &text);
+                Some(publish_diagnostics(uri, diagnostics))
+            })
+            .collect()
+    }
+
+    /// Drops the options built from a config file that changed, returning the open
+    /// documents whose diagnostics may change.
+    fn config_file_changed(&mut self, path: &Path, is_created_or_deleted: bool) -> Vec<Url> {
+        self.config_file_options
+            .retain(|config_files, _options| !config_files.contains(path));
+
+        // a new or deleted config file can change the nearest config file of any directory
+        if is_created_or_deleted {
+            self.directory_config_files.clear();
+            return self.documents.keys().cloned().collect();
+        }
+
+        self.documents
+            .keys()
+            .filter(|uri| {
+                document_directory(uri)
+                    .and_then(|directory| self.directory_config_files.get(&directory))
+                    .is_none_or(|config_files| config_files.contains(path))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The project options of the directory of a document, from the nearest config files.
+    /// Documents that are not files use the working directory.
+    fn project_options(&mut self, uri: &Url) -> Arc<ProjectOptions> {
+        let Some(directory) = document_directory(uri).or_else(|| std::env::current_dir().ok())
+        else {
+            return self.default_options();
+        };
+
+        let config_files = self
+            .directory_config_files
+            .entry(directory)
+            .or_insert_with_key(|directory| ConfigFiles::find(directory))
+            .clone();
+
+        if let Some(options) = self.config_file_options.get(&config_files) {
+            return Arc::clone(options);
+        }
+
+        match ProjectOptions::new_for_config_files(&config_files) {
+            Ok(options) => {
+                let options = Arc::new(options);
+                self.config_file_options
+                    .insert(config_files, Arc::clone(&options));
+                options
+            }
+            Err(error) => {
+                log::warn!("unable to load the options of {uri}: {error}");
+                self.default_options()
+            }
+        }
+    }
+
+    /// The options used when the config files can't be loaded.
+    fn default_options(&mut self) -> Arc<ProjectOptions> {
+        let options = self.default_options.get_or_insert_with(|| {
+            Arc::new(ProjectOptions {
+                rustywind: RustyWind::default(),
+                ignored_files: HashSet::new(),
+                unknown_allowlist: UnknownAllowlist::default(),
+            })
+        });
+
+        Arc::clone(options)
+    }
+
+    /// The options of a document and its path, `None` when the document is ignored.
+    fn document_options(&mut self, uri: &Url) -> Option<(Arc<ProjectOptions>, Option<PathBuf>)> {
+        let options = self.project_options(uri);
+        let path = uri.to_file_path().ok();
+
+        let is_ignored = path.as_ref().is_some_and(|path| {
+            !options.ignored_files.is_empty()
+                && path
+                    .canonicalize()
+                    .is_ok_and(|path| options.ignored_files.contains(&path))
+        });
+
+        (!is_ignored).then_some((options, path))
+    }
+
+    /// The edits of the class lists of a document, or only of the ones overlapping `range`.
+    fn class_list_edits(
+        &mut self,
+        uri: &Url,
+        text: &str,
+        range: Option<&SortRange>,
+    ) -> Vec<ClassListEdit> {
+        let Some((options, path)) = self.document_options(uri) else {
+            return vec![];
+        };
+
+        let rustywind = &options.rustywind;
+        match (path, range) {
+            (Some(path), Some(range)) => rustywind.analyze_in_range_for_path(&path, text, range),
+            (Some(path), None) => rustywind.analyze_for_path(&path, text),
+            (None, Some(range)) => rustywind.analyze_in_range(text, range),
+            (None, None) => rustywind.analyze(text),
+        }
+    }
+
+    /// The edits sorting the class lists of a document, or only the ones overlapping `range`.
+    fn formatting(&mut self, uri: &Url, range: Option<Range>) -> Vec<TextEdit> {
+        let Some(text) = self.documents.get(uri).cloned() else {
+            return vec![];
+        };
+
+        let index = LineIndex::new(&text);
+        let range =
+            range.map(|range| SortRange::Bytes(index.offset(range.start)..index.offset(range.end)));
+
+        self.class_list_edits(uri, &text, range.as_ref())
+            .iter()
+            .map(|edit| index.text_edit(edit))
+            .collect()
+    }
+
+    fn diagnostics(&mut self, uri: &Url, text: &str) -> Vec<Diagnostic> {
+        let Some((options, path)) = self.document_options(uri) else {
+            return vec![];
+        };
+
+        let rustywind = &options.rustywind;
+        let index = LineIndex::new(text);
+        let mut diagnostics = vec![];
+
+        let edits = match &path {
+            Some(path) => rustywind.analyze_for_path(path, text),
+            None => rustywind.analyze(text),
+        };
+
+        for edit in edits {
+            let duplicates: Vec<_> = edit
+                .classes
+                .iter()
+                .filter(|class| class.duplicate_removed)
+                .collect();
+
+            // a class list that only changes by removing duplicates is sorted
+            if duplicates.is_empty() || edit.classes.iter().any(|class| class.moved) {
+                diagnostics.push(diagnostic(
+                    index.range(&edit.range),
+                    DiagnosticSeverity::WARNING,
+                    "unsorted-classes",
+                    format!("Classes are not sorted, expected `{}`", edit.replacement),
+                ));
+            }
+
+            for class in duplicates {
+                diagnostics.push(diagnostic(
+                    index.range(&class.range),
+                    DiagnosticSeverity::WARNING,
+                    "duplicate-class",
+                    format!("Duplicate class `{}`", class.class),
+                ));
+            }
+        }
+
+        let unknown_classes = match &path {
+            Some(path) => {
+                rustywind.find_unknown_classes_for_path(path, text, &options.unknown_allowlist)
+            }
+            None => rustywind.find_unknown_classes(text, &options.unknown_allowlist),
+        };
+
+        for unknown in unknown_classes {
+            let description = match &unknown.kind {
+                UnknownKind::Utility => format!("Unknown class `{}`", unknown.class),
+                UnknownKind::Variant(variant) => {
+                    format!("Unknown variant `{variant}` in `{}`", unknown.class)
+                }
+            };
+
+            let suggestions = match unknown.suggestions.as_slice() {
+                [] => String::new(),
+                suggestions => format!(
+                    ", did you mean {}?",
+                    suggestions
+                        .iter()
+                        .map(|suggestion| format!("`{suggestion}`"))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ),
+            };
+
+            diagnostics.push(diagnostic(
+                index.range(&(unknown.offset..unknown.offset + unknown.class.len())),
+                DiagnosticSeverity::INFORMATION,
+                "unknown-class",
+                format!("{description}{suggestions}"),
+            ));
+        }
+
+        diagnostics
+    }
+
+    /// A quick fix sorting each class list in the range, and a fix-all action sorting every
+    /// class list of the document.
+    fn code_actions(&mut self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = &params.text_document.uri;
+        let Some(text) = self.documents.get(uri).cloned() else {
+            return vec![];
+        };
+
+        let index = LineIndex::new(&text);
+        let range = index.offset(params.range.start)..index.offset(params.range.end);
+        let edits = self.class_list_edits(uri, &text, None);
+
+        let wants = |kind: &CodeActionKind| {
+            params.context.only.as_ref().is_none_or(|only| {
+                only.iter()
+                    .any(|only| kind.as_str().starts_with(only.as_str()))
+            })
+        };
+
+        let mut actions = vec![];
+
+        if wants(&CodeActionKind::QUICKFIX) {
+            for edit in edits.iter().filter(|edit| edit.overlaps(&range)) {
+                actions.push(code_action(
+                    "Sort classes",
+                    CodeActionKind::QUICKFIX,
+                    uri,
+                    vec![index.text_edit(edit)],
+                ));
+            }
+        }
+
+        if wants(&CodeActionKind::SOURCE_FIX_ALL) && !edits.is_empty() {
+            actions.push(code_action(
+                "Sort all classes",
+                CodeActionKind::SOURCE_FIX_ALL,
+                uri,
+                edits.iter().map(|edit| index.text_edit(edit)).collect(),
+            ));
+        }
+
+        actions
+    }
+}
+
+/// Converts between byte offsets and LSP positions, whose characters are UTF-16 code units.
+struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        LineIndex { text, line_starts }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset].encode_utf16().count();
+
+        Position::new(line as u32, character as u32)
+    }
+
+    /// The byte offset of a position, positions past the end of a line are at its end.
+    fn offset(&self, position: Position) -> usize {
+        let Some(&line_start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+
+        let line_end = self
+            .line_starts
+            .get(position.line as usize + 1)
+            .map_or(self.text.len(), |next_line_start| next_line_start - 1);
+
+        let mut units = 0;
+        for (index, character) in self.text[line_start..line_end].char_indices() {
+            if units >= position.character as usize {
+                return line_start + index;
+            }
+            units += character.len_utf16();
+        }
+
+        line_end
+    }
+
+    fn range(&self, range: &std::ops::Range<usize>) -> Range {
+        Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    fn text_edit(&self, edit: &ClassListEdit) -> TextEdit {
+        TextEdit::new(self.range(&edit.range), edit.replacement.clone())
+    }
+}
+
+/// The path of a rustywind or prettier config file, `None` for other documents.
+fn config_file_path(uri: &Url) -> Option<PathBuf> {
+    let path = uri.to_file_path().ok()?;
+    let name = path.file_name()?.to_str()?;
+
+    (CONFIG_FILE_NAMES.contains(&name) || PRETTIER_CONFIG_FILE_NAMES.contains(&name))
+        .then_some(path)
+}
+
+fn document_directory(uri: &Url) -> Option<PathBuf> {
+    uri.to_file_path()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+}
+
+fn diagnostic(
+    range: Range,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some(SOURCE.to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn code_action(
+    title: &str,
+    kind: CodeActionKind,
+    uri: &Url,
+    edits: Vec<TextEdit>,
+) -> CodeActionOrCommand {
+    let is_preferred = kind == CodeActionKind::QUICKFIX;
+
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.to_string(),
+        kind: Some(kind),
+        edit: Some(WorkspaceEdit::new(
+            [(uri.clone(), edits)].into_iter().collect(),
+        )),
+        is_preferred: Some(is_preferred),
+        ..Default::default()
+    })
+}
+
+fn publish_diagnostics(uri: Url, diagnostics: Vec<Diagnostic>) -> Notification {
+    Notification::new(
+        PublishDiagnostics::METHOD.to_string(),
+        PublishDiagnosticsParams::new(uri, diagnostics, None),
+    )
+}
+
+fn parse_params<P: DeserializeOwned>(request: Request) -> serde_json::Result<P> {
+    serde_json::from_value(request.params)
+}
+
+fn parse_notification<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    serde_json::from_value(notification.params)
+        .map_err(|error| log::warn!("invalid {} notification: {error}", notification.method))
+        .ok()
+}
+
+fn to_value(result: impl Serialize) -> serde_json::Result<serde_json::Value> {
+    serde_json::to_value(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use lsp_server::RequestId;
+    use lsp_types::{
+        DidOpenTextDocumentParams, DocumentFormattingParams, DocumentRangeFormattingParams,
+        FileEvent, FormattingOptions, InitializeParams, TextDocumentIdentifier, TextDocumentItem,
+        WorkDoneProgressParams,
+    };
+    use std::fs;
+    use std::time::Duration;
+
+    fn receive(client: &Connection) -> Message {
+        client
+            .receiver
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_line_index() {
+        let text = "a\n😀 class=\"p-4\"\nb";
+        let index = LineIndex::new(text);
+
+        let offset = text.find("p-4").unwrap();
+        assert_eq!(index.position(offset), Position::new(1, 10));
+        assert_eq!(index.offset(Position::new(1, 10)), offset);
+        assert_eq!(index.offset(Position::new(0, 5)), 1);
+        assert_eq!(index.offset(Position::new(5, 0)), text.len());
+    }
+
+    fn diagnostic_messages(notifications: Vec<Notification>) -> Vec<(Url, Vec<String>)> {
+        notifications
+            .into_iter()
+            .map(|notification| {
+                let params: PublishDiagnosticsParams =
+                    serde_json::from_value(notification.params).unwrap();
+                let messages = params
+                    .diagnostics
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.message)
+                    .collect();
+                (params.uri, messages)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_config_file_changes() {
+        let project = TempProject::new("lsp-config");
+        let root = project.path();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        let a = Url::from_file_path(root.join("a/index.html")).unwrap();
+        let b = Url::from_file_path(root.join("b/index.html")).unwrap();
+        let config_file = root.join("a/rustywind.toml");
+        let config_uri = Url::from_file_path(&config_file).unwrap();
+
+        let mut server = Server::default();
+        for uri in [&a, &b] {
+            server.handle_notification(Notification::new(
+                DidOpenTextDocument::METHOD.to_string(),
+                DidOpenTextDocumentParams {
+                    text_document: TextDocumentItem::new(
+                        uri.clone(),
+                        "html".to_string(),
+                        1,
+                        "<div class=\"flex flex\"></div>".to_string(),
+                    ),
+                },
+            ));
+        }
+
+        // directories without a config file of their own share the options
+        assert!(Arc::ptr_eq(
+            &server.project_options(&a),
+            &server.project_options(&b)
+        ));
+
+        fs::write(&config_file, "allowDuplicates = true").unwrap();
+        let created = server.handle_notification(Notification::new(
+            DidChangeWatchedFiles::METHOD.to_string(),
+            DidChangeWatchedFilesParams {
+                changes: vec![FileEvent::new(config_uri.clone(), FileChangeType::CREATED)],
+            },
+        ));
+        let mut created = diagnostic_messages(created);
+        created.sort();
+        assert_eq!(
+            created,
+            vec![
+                (a.clone(), vec![]),
+                (b.clone(), vec!["Duplicate class `flex`".to_string()])
+            ]
+        );
+
+        // only the documents using the saved config file
+        fs::write(&config_file, "allowDuplicates = false").unwrap();
+        let saved = server.handle_notification(Notification::new(
+            DidSaveTextDocument::METHOD.to_string(),
+            DidSaveTextDocumentParams {
+                text_document: TextDocumentIdentifier::new(config_uri),
+                text: None,
+            },
+        ));
+        assert_eq!(
+            diagnostic_messages(saved),
+            vec![(a, vec!["Duplicate class `flex`".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_language_server() {
+        let project = TempProject::new("lsp");
+        let root = project.path();
+        let uri = Url::from_file_path(root.join("index.html")).unwrap();
+
+        let (server, client) = Connection::memory();
+        let server_thread = std::thread::spawn(move || serve(&server));
+
+        client
+            .sender
+            .send(Message::Request(Request::new(
+                RequestId::from(1),
+                "initialize".to_string(),
+                InitializeParams::default(),
+            )))
+            .unwrap();
+        let Message::Response(response) = receive(&client) else {
+            panic!("expected the initialize response");
+        };
+        assert_eq!(
+            response.result.unwrap()["capabilities"]["documentRangeFormattingProvider"],
+            true
+        );
+        client
+            .sender
+            .send(Message::Notification(Notification::new(
+                "initialized".to_string(),
+                serde_json::json!({}),
+            )))
+            .unwrap();
+
+        client
+            .sender
+            .send(Message::Notification(Notification::new(
+                DidOpenTextDocument::METHOD.to_string(),
+                DidOpenTextDocumentParams {
+                    text_document: TextDocumentItem::new(
+                        uri.clone(),
+                        "html".to_string(),
+                        1,
+                        "<div class=\"p-4 flex\">\n  <a class=\"m-2 flex flex\"></a>\n  <b class=\"itmes-center\"></b>\n</div>"
+                            .to_string(),
+                    ),
+                },
+            )))
+            .unwrap();
+
+        let Message::Notification(notification) = receive(&client) else {
+            panic!("expected the diagnostics");
+        };
+        let diagnostics: PublishDiagnosticsParams =
+            serde_json::from_value(notification.params).unwrap();
+        let diagnostics: Vec<_> = diagnostics
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.range, diagnostic.message))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    Range::new(Position::new(0, 12), Position::new(0, 20)),
+                    "Classes are not sorted, expected `flex p-4`".to_string()
+                ),
+                (
+                    Range::new(Position::new(1, 21), Position::new(1, 25)),
+                    "Duplicate class `flex`".to_string()
+                ),
+                (
+                    Range::new(Position::new(2, 12), Position::new(2, 24)),
+                    "Unknown class `itmes-center`, did you mean `items-center`?".to_string()
+                ),
+            ]
+        );
+
+        client
+            .sender
+            .send(Message::Request(Request::new(
+                RequestId::from(2),
+                Formatting::METHOD.to_string(),
+                DocumentFormattingParams {
+                    text_document: TextDocumentIdentifier::new(uri.clone()),
+                    options: FormattingOptions::default(),
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                },
+            )))
+            .unwrap();
+        let Message::Response(response) = receive(&client) else {
+            panic!("expected the formatting response");
+        };
+        let edits: Vec<TextEdit> = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                TextEdit::new(
+                    Range::new(Position::new(0, 12), Position::new(0, 20)),
+                    "flex p-4".to_string()
+                ),
+                TextEdit::new(
+                    Range::new(Position::new(1, 12), Position::new(1, 25)),
+                    "m-2 flex".to_string()
+                ),
+            ]
+        );
+
+        // only the class list of the selected line
+        client
+            .sender
+            .send(Message::Request(Request::new(
+                RequestId::from(3),
+                RangeFormatting::METHOD.to_string(),
+                DocumentRangeFormattingParams {
+                    text_document: TextDocumentIdentifier::new(uri.clone()),
+                    range: Range::new(Position::new(1, 0), Position::new(1, 5)),
+                    options: FormattingOptions::default(),
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                },
+            )))
+            .unwrap();
+        let Message::Response(response) = receive(&client) else {
+            panic!("expected the range formatting response");
+        };
+        let edits: Vec<TextEdit> = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(edits, vec![]);
+
+        client
+            .sender
+            .send(Message::Request(Request::new(
+                RequestId::from(4),
+                RangeFormatting::METHOD.to_string(),
+                DocumentRangeFormattingParams {
+                    text_document: TextDocumentIdentifier::new(uri.clone()),
+                    range: Range::new(Position::new(1, 0), Position::new(2, 0)),
+                    options: FormattingOptions::default(),
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                },
+            )))
+            .unwrap();
+        let Message::Response(response) = receive(&client) else {
+            panic!("expected the range formatting response");
+        };
+        let edits: Vec<TextEdit> = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            edits,
+            vec![TextEdit::new(
+                Range::new(Position::new(1, 12), Position::new(1, 25)),
+                "m-2 flex".to_string()
+            )]
+        );
+
+        client
+            .sender
+            .send(Message::Request(Request::new(
+                RequestId::from(5),
+                "shutdown".to_string(),
+                serde_json::Value::Null,
+            )))
+            .unwrap();
+        receive(&client);
+        client
+            .sender
+            .send(Message::Notification(Notification::new(
+                "exit".to_string(),
+                serde_json::Value::Null,
+            )))
+            .unwrap();
+
+        server_thread.join().unwrap().unwrap();
+    }
+}
@@ -1,18 +1,35 @@
 mod cli;
+mod config;
+mod diff;
+mod dump_order;
+mod explain;
+mod git;
+mod lsp;
 mod options;
+mod prettier;
+mod reporter;
+#[cfg(test)]
+mod test_utils;
+mod watch;
 
 use ahash::AHashSet as HashSet;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use eyre::Result;
 use indoc::indoc;
 use once_cell::sync::Lazy;
 use options::Options;
+use options::ProjectOptions;
 use options::WriteMode;
 use rayon::ThreadPoolBuilder;
 use rayon::iter::IntoParallelRefIterator as _;
 use rayon::iter::ParallelIterator;
+use rustywind_core::RustyWind;
+use rustywind_core::diagnostics::UnknownKind;
 use rustywind_core::sorter;
+use std::borrow::Cow;
 use std::fs;
+use std::io::Write as _;
+use std::ops::{Range, RangeInclusive};
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -22,10 +39,14 @@ use std::sync::atomic::Ordering;
 static EXIT_ERROR: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 static GRAY: Lazy<colored::CustomColor> = Lazy::new(|| colored::CustomColor::new(120, 120, 120));
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[clap(name = "RustyWind", author, version, about, long_about = None)]
 #[command(styles=cli::get_styles())]
-#[clap(args_override_self = true, arg_required_else_help = true)]
+#[clap(
+    args_override_self = true,
+    arg_required_else_help = true,
+    subcommand_negates_reqs = true
+)]
 #[clap(override_usage = indoc!("
     rustywind [OPTIONS] [PATH]...
 
@@ -39,8 +60,16 @@ static GRAY: Lazy<colored::CustomColor> = Lazy::new(|| colored::CustomColor::new
       rustywind --check-formatted .
 
     If you want to run it on your STDIN, you can do:
-      echo \"<FILE CONTENTS>\" | rustywind --stdin"))]
+      echo \"<FILE CONTENTS>\" | rustywind --stdin
+
+    To see why classes are sorted the way they are, run the `explain` subcommand
+      rustywind explain \"p-4 md:flex m-4\"
+
+    To freeze the sort order derived from a CSS file into a config file, run the `dump-order` subcommand
+      rustywind dump-order --output-css-file tailwind.css --output sort-order.json"))]
 pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// A file or directory to run on.
     #[arg(value_name = "PATH", required_unless_present = "stdin")]
     file_or_dir: Vec<String>,
@@ -51,6 +80,43 @@ pub struct Cli {
         required_unless_present = "file_or_dir",
     )]
     stdin: bool,
+    /// Only runs on the files of the paths that changed since a git revision, in the working
+    /// tree or the index, and on untracked files.
+    #[arg(
+        long,
+        value_name = "REVISION",
+        conflicts_with_all = &["stdin", "staged", "since_merge_base"]
+    )]
+    changed_since: Option<String>,
+    /// Only runs on the files of the paths that are staged in the git index, reading their
+    /// staged contents. With `--write`, the sorted files are staged and written to the working
+    /// tree, files with unstaged changes are skipped and make it exit with 1.
+    #[arg(long, conflicts_with_all = &["stdin", "since_merge_base"])]
+    staged: bool,
+    /// Only runs on the files of the paths that changed since the merge base of `HEAD` and a
+    /// branch, `main` by default, and on untracked files.
+    #[arg(
+        long,
+        value_name = "BRANCH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "main",
+        conflicts_with_all = &["stdin"]
+    )]
+    since_merge_base: Option<String>,
+    /// Only sorts the class lists overlapping a byte range of each file, e.g. 120:480, and
+    /// leaves every other class list byte for byte as it is.
+    #[arg(
+        long,
+        value_name = "START:END",
+        value_parser = options::parse_byte_range,
+        conflicts_with = "lines"
+    )]
+    range: Option<Range<usize>>,
+    /// Only sorts the class lists overlapping 1-based lines of each file, e.g. 10-40 or 10,
+    /// and leaves every other class list byte for byte as it is.
+    #[arg(long, value_name = "START-END", value_parser = options::parse_line_range)]
+    lines: Option<RangeInclusive<usize>>,
     /// Changes the files in place with the reorganized classes.
     #[arg(long, conflicts_with_all = &["stdin", "dry_run", "check_formatted"])]
     write: bool,
@@ -60,45 +126,162 @@ pub struct Cli {
     /// Checks if the files are already formatted, exits with 1 if not formatted.
     #[arg(long, conflicts_with_all = &["stdin", "write", "dry_run"])]
     check_formatted: bool,
+    /// Lists classes and variants that RustyWind does not recognize, with suggestions for
+    /// likely typos. Exits with 1 if any are found.
+    #[arg(long, conflicts_with_all = &["stdin", "write", "dry_run", "check_formatted"])]
+    report_unknown: bool,
+    /// Prints a unified diff of the changes sorting would make to each file, exits with 1 if
+    /// there are any.
+    #[arg(
+        long,
+        conflicts_with_all = &["write", "dry_run", "check_formatted", "report_unknown"]
+    )]
+    diff: bool,
+    /// The number of unchanged lines shown around each change with `--diff`, 3 by default.
+    #[arg(long, value_name = "LINES")]
+    diff_context: Option<usize>,
+    /// When to color the output of `--diff`.
+    #[arg(long, value_name = "WHEN")]
+    color: Option<clap::ColorChoice>,
+    /// Reports the unsorted classes of each file, with their location and sorted order, and
+    /// the files that can't be read or written, in a machine readable format. Exits with 1 if
+    /// any are found. With `--write` the files are sorted too.
+    #[arg(long, conflicts_with_all = &["stdin", "dry_run", "report_unknown", "diff"])]
+    reporter: Option<reporter::Reporter>,
+    /// Classes or variants that are intentionally unknown and should not be reported by
+    /// `--report-unknown`. A trailing `*` matches any class starting with the given text.
+    #[arg(long, requires = "report_unknown")]
+    allow_unknown: Option<Vec<String>>,
     /// When set, RustyWind will not delete duplicated classes.
-    #[arg(long)]
+    #[arg(long, overrides_with = "no_allow_duplicates")]
     allow_duplicates: bool,
-    /// When set, RustyWind will use the config file to derive configurations. The config file
-    /// current only supports json with one property sortOrder, e.g.
-    /// { "sortOrder": ["class1", ...] }.
-    #[arg(long, conflicts_with_all = &["output_css_file"])]
+    /// Deletes duplicated classes even if a config file sets `allowDuplicates`.
+    #[arg(long, overrides_with = "allow_duplicates")]
+    no_allow_duplicates: bool,
+    /// When set, RustyWind will use the config file to derive configurations, instead of the
+    /// nearest rustywind.toml or .rustywindrc.json. The config file is toml (.toml extension)
+    /// or json, and can set every option in camelCase, plus the sort order with a sortOrder
+    /// property, e.g. { "sortOrder": ["class1", ...], "tailwindPrefix": "tw" }. Options set on
+    /// the command line override the config file.
+    #[arg(long)]
     config_file: Option<String>,
     /// When set RustyWind will determine the sort order by the order the class appear in the the given css file.
-    #[arg(long, conflicts_with_all = &["config_file", "vite_css"])]
+    #[arg(long, conflicts_with_all = &["vite_css"])]
     output_css_file: Option<String>,
     /// When set RustyWind will determine the sort order by the order the class appear in the CSS file that vite generates.
     ///
     /// Please provide the full URL to the CSS file ex: `rustywind --vite-css "http://127.0.0.1:5173/src/assets/main.css" . --dry-run`
     ///
     /// Note: This option is experimental and may be removed in the future.
-    #[arg(long, conflicts_with_all = &["config_file", "output_css_file"])]
+    #[arg(long, conflicts_with_all = &["output_css_file"])]
     vite_css: Option<String>,
 
     /// When set, RustyWind will skip SSL verification for the vite_css option.
-    #[arg(long, conflicts_with_all = &["config_file", "output_css_file"])]
+    #[arg(
+        long,
+        conflicts_with_all = &["output_css_file"],
+        overrides_with = "no_skip_ssl_verification"
+    )]
     skip_ssl_verification: bool,
+    /// Verifies SSL for the vite_css option even if a config file sets `skipSslVerification`.
+    #[arg(long, overrides_with = "skip_ssl_verification")]
+    no_skip_ssl_verification: bool,
+
+    /// When set, the order from the config file, `--output-css-file` or `--vite-css` is only
+    /// used for the classes it lists, every other class is sorted by the built-in sorter.
+    /// Listed classes come before the classes sorted by the built-in sorter.
+    #[arg(long, overrides_with = "no_pattern_fallback")]
+    pattern_fallback: bool,
+    /// Only uses the order from the config file, `--output-css-file` or `--vite-css` even if a
+    /// config file sets `patternFallback`.
+    #[arg(long, overrides_with = "pattern_fallback")]
+    no_pattern_fallback: bool,
 
     /// When set, RustyWind will ignore this list of files
     #[arg(long)]
     ignored_files: Option<Vec<String>>,
-    /// Uses a custom regex instead of default one. Should have a single capture group that
-    /// includes only the classes.
+    /// Uses a custom regex instead of default one, can be repeated. Each named capture group,
+    /// e.g. (?P<classes>...), includes classes sorted on their own. A regex without named
+    /// groups uses its first capture group that matches. Each string literal in a group named
+    /// `arguments` is sorted on its own.
     #[arg(long)]
-    custom_regex: Option<String>,
+    custom_regex: Option<Vec<String>>,
+    /// Attribute names whose values are sorted like `class`, e.g. tw,:class,ngClass, like the
+    /// tailwindAttributes option of prettier-plugin-tailwindcss. Their Vue and Angular
+    /// bindings are sorted too. Can't be used with `--custom-regex`.
+    #[arg(long, value_delimiter = ',')]
+    attributes: Option<Vec<String>>,
+    /// Functions whose string arguments, or tagged template, are sorted like `class`,
+    /// e.g. clsx,cn,tw, like the tailwindFunctions option of prettier-plugin-tailwindcss.
+    /// Can't be used with `--custom-regex`.
+    #[arg(long, value_delimiter = ',')]
+    functions: Option<Vec<String>>,
+    /// Keep the whitespace around and between classes instead of joining them with single
+    /// spaces.
+    #[arg(long, overrides_with = "no_preserve_whitespace")]
+    preserve_whitespace: bool,
+    /// Joins classes with single spaces even if a config file sets `preserveWhitespace` or
+    /// the prettier config sets `tailwindPreserveWhitespace`.
+    #[arg(long, overrides_with = "preserve_whitespace")]
+    no_preserve_whitespace: bool,
     /// Specify how individual classes are wrapped.
     #[arg(long)]
     class_wrapping: Option<options::CliClassWrapping>,
     /// Tailwind prefix used when sorting classes, e.g. tw for tw: or tw- classes.
     #[arg(long)]
     tailwind_prefix: Option<String>,
+    /// A TOML or JSON file that adds or overrides utility to CSS property mappings,
+    /// declaration counts and property order entries of the built-in sorter, e.g.
+    /// { "utilities": { "prose-*": ["--tw-prose"] }, "declarationCounts": { "prose-lg": 2 },
+    /// "propertyOrder": [{ "property": "--tw-prose", "after": "display" }] }.
+    #[arg(long)]
+    utility_extensions: Option<String>,
+    /// Official Tailwind plugins whose utilities and variants the sorter should know, matching
+    /// the order prettier-plugin-tailwindcss produces when the plugins are loaded. Can also be
+    /// set with `plugins` in the utility extensions file.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = options::plugin_names()
+    )]
+    plugins: Option<Vec<String>>,
+    /// The Tailwind version whose class order to follow. Version 3 matches
+    /// prettier-plugin-tailwindcss v0.4 with a Tailwind v3 project, and can't be used with
+    /// --plugins or --utility-extensions.
+    #[arg(long)]
+    tailwind_version: Option<options::CliTailwindVersion>,
+    /// Keeps running after the first run, and runs again on each file that changes, e.g. to
+    /// sort the classes of files on save with `--write`. The options are reloaded when a
+    /// config file, the CSS file or the utility extensions file changes.
+    #[arg(
+        long,
+        conflicts_with_all = &["stdin", "reporter", "changed_since", "staged", "since_merge_base"]
+    )]
+    watch: bool,
     /// Do not print log messages
-    #[arg(long, default_value = "false", conflicts_with_all = &["dry_run"])]
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = &["dry_run"],
+        overrides_with = "no_quiet"
+    )]
     quiet: bool,
+    /// Print log messages even if a config file sets `quiet`.
+    #[arg(long, overrides_with = "quiet")]
+    no_quiet: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Explains why classes are sorted the way they are, by printing the sort key of each
+    /// class and the comparison step that decided the order of each adjacent pair.
+    Explain(explain::ExplainArgs),
+    /// Writes the sort order derived from a config file, a CSS file or the CSS that vite
+    /// generates as JSON, in the format `--config-file` reads.
+    DumpOrder(dump_order::DumpOrderArgs),
+    /// Runs a language server over stdio, which formats documents, reports unsorted,
+    /// duplicate and unknown classes as diagnostics and offers code actions to sort them.
+    Lsp(lsp::LspArgs),
 }
 
 fn main() -> Result<()> {
@@ -107,12 +290,20 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    if let Some(command) = cli.command {
+        return match command {
+            Command::Explain(args) => explain::run(args),
+            Command::DumpOrder(args) => dump_order::run(args),
+            Command::Lsp(args) => lsp::run(args),
+        };
+    }
+
+    let watch_cli = cli.watch.then(|| cli.clone());
     let mut options = Options::new_from_cli(cli)?;
 
     let search_paths = std::mem::take(&mut options.search_paths);
 
     let options = Arc::new(options);
-    let rustywind = &options.rustywind;
 
     match &options.write_mode {
         WriteMode::ToStdOut => (),
@@ -131,13 +322,22 @@ fn main() -> Result<()> {
             "\nprinting file contents to console, run with --write to save changes to files:"
         ),
         WriteMode::CheckFormatted => println!("\nonly printing changed files"),
+        WriteMode::ReportUnknown => println!("\nonly printing unknown classes and variants"),
+        // the diff is a patch and reports are machine readable, without other output
+        WriteMode::Diff { .. } | WriteMode::Report { .. } => (),
     }
 
-    if let WriteMode::ToStdOut = &options.write_mode {
+    if let (WriteMode::Diff { .. }, Some(contents)) = (&options.write_mode, &options.stdin) {
+        let rustywind = &options.project_options.rustywind;
+        let sorted_content = sort_file_contents(rustywind, None, contents, &options);
+
+        print_diff("<stdin>", contents, &sorted_content, options.as_ref());
+    } else if let WriteMode::ToStdOut = &options.write_mode {
         let contents = options.stdin.clone().unwrap_or_default();
+        let rustywind = &options.project_options.rustywind;
 
         if rustywind.has_classes(&contents) {
-            let sorted_content = rustywind.sort_file_contents(&contents);
+            let sorted_content = sort_file_contents(rustywind, None, &contents, &options);
             print!("{sorted_content}");
         } else {
             print!("{contents}");
@@ -159,31 +359,66 @@ fn main() -> Result<()> {
             .build_global()
             .expect("failed to build thread pool");
 
-        search_paths
-            .par_iter()
-            .for_each(|f| run_on_file_path(f, &options));
+        if let WriteMode::Report { reporter, write } = options.write_mode {
+            let reports: Vec<_> = search_paths
+                .par_iter()
+                .filter_map(|f| report_on_file_path(f, &options))
+                .collect();
 
-        // after running on all files, if there was an error, exit with 1
-        if EXIT_ERROR.load(Ordering::Relaxed) {
-            std::process::exit(1)
+            print!("{}", reporter.render(&reports, write));
+
+            let has_errors = reports.iter().any(|report| report.error.is_some());
+            let has_edits = reports.iter().any(|report| !report.edits.is_empty());
+            if has_errors || (has_edits && !write) {
+                EXIT_ERROR.store(true, Ordering::Relaxed);
+            }
+        } else {
+            search_paths
+                .par_iter()
+                .for_each(|f| run_on_file_path(f, &options));
         }
     }
 
+    if let Some(cli) = watch_cli {
+        return watch::run(cli, options, search_paths);
+    }
+
+    // after running on all files, if there was an error, exit with 1
+    if EXIT_ERROR.load(Ordering::Relaxed) {
+        std::process::exit(1)
+    }
+
     Ok(())
 }
 
 pub fn run_on_file_path(file_path: &Path, options: &Options) {
+    let project_options = match options.project_options_for(file_path) {
+        Ok(project_options) => project_options,
+        Err(error) => {
+            EXIT_ERROR.store(true, Ordering::Relaxed);
+            eprintln!(
+                "  * [SKIPPED] {}: {error}",
+                get_file_name(file_path, &options.starting_paths)
+            );
+            return;
+        }
+    };
+
     // if the file is in the ignored_files list return early
-    if should_ignore_current_file(&options.ignored_files, file_path) {
+    if should_ignore_current_file(&project_options.ignored_files, file_path) {
         log::debug!("file path {file_path:#?} found in ignored_files, will not sort");
         return;
     }
 
-    let rustywind = &options.rustywind;
-    match std::fs::read_to_string(file_path) {
+    let rustywind = &project_options.rustywind;
+    match read_file(file_path, options) {
+        Ok(contents) if matches!(options.write_mode, WriteMode::ReportUnknown) => {
+            print_unknown_classes(file_path, &contents, options, project_options);
+        }
         Ok(contents) => {
-            if rustywind.has_classes(&contents) {
-                let sorted_content = rustywind.sort_file_contents(&contents);
+            if rustywind.has_classes_for_path(file_path, &contents) {
+                let sorted_content =
+                    sort_file_contents(rustywind, Some(file_path), &contents, options);
                 let contents_changed = sorted_content != contents;
 
                 match (contents_changed, &options.write_mode) {
@@ -203,8 +438,18 @@ pub fn run_on_file_path(file_path: &Path, options: &Options) {
                     (false, WriteMode::ToConsole) => print_file_contents(&sorted_content),
 
                     (contents_changed, WriteMode::CheckFormatted) => {
-                        print_changed_files(file_path, contents_changed, options);
+                        print_changed_files(file_path, contents_changed, options, project_options);
                     }
+
+                    (_, WriteMode::ReportUnknown) => (),
+
+                    (true, WriteMode::Diff { .. }) => {
+                        let file_name = file_path.display().to_string();
+                        print_diff(&file_name, &contents, &sorted_content, options);
+                    }
+                    (false, WriteMode::Diff { .. }) => (),
+
+                    (_, WriteMode::Report { .. }) => (),
                 }
             }
         }
@@ -212,19 +457,157 @@ pub fn run_on_file_path(file_path: &Path, options: &Options) {
     }
 }
 
-fn print_changed_files(file_path: &Path, contents_changed: bool, options: &Options) {
+/// Sorts the classes of a file, or of stdin without a path, only in `--range` or `--lines`
+/// when one is set.
+fn sort_file_contents<'a>(
+    rustywind: &RustyWind,
+    file_path: Option<&Path>,
+    contents: &'a str,
+    options: &Options,
+) -> Cow<'a, str> {
+    match (file_path, &options.sort_range) {
+        (Some(file_path), Some(range)) => {
+            rustywind.sort_file_contents_in_range_for_path(file_path, contents, range)
+        }
+        (Some(file_path), None) => rustywind.sort_file_contents_for_path(file_path, contents),
+        (None, Some(range)) => rustywind.sort_file_contents_in_range(contents, range),
+        (None, None) => rustywind.sort_file_contents(contents),
+    }
+}
+
+/// Checks a file for `--reporter`, sorting it too with `--write`.
+fn report_on_file_path(file_path: &Path, options: &Options) -> Option<reporter::FileReport> {
+    // relative to the working directory, like the paths CI annotations expect
+    let path = file_path.display().to_string().replace('\\', "/");
+    let mut report = reporter::FileReport {
+        path: path.strip_prefix("./").unwrap_or(&path).to_string(),
+        ..Default::default()
+    };
+
+    let project_options = match options.project_options_for(file_path) {
+        Ok(project_options) => project_options,
+        Err(error) => {
+            report.error = Some(format!("unable to load the options: {error}"));
+            return Some(report);
+        }
+    };
+
+    if should_ignore_current_file(&project_options.ignored_files, file_path) {
+        return None;
+    }
+
+    let contents = match read_file(file_path, options) {
+        Ok(contents) => contents,
+        // binary files are skipped, like when sorting
+        Err(error) if error.kind() == std::io::ErrorKind::InvalidData => return None,
+        Err(error) => {
+            report.error = Some(format!("unable to read file: {error}"));
+            return Some(report);
+        }
+    };
+
+    let rustywind = &project_options.rustywind;
+    report.edits = match &options.sort_range {
+        Some(range) => rustywind.analyze_in_range_for_path(file_path, &contents, range),
+        None => rustywind.analyze_for_path(file_path, &contents),
+    };
+
+    if matches!(options.write_mode, WriteMode::Report { write: true, .. })
+        && !report.edits.is_empty()
+    {
+        let sorted_content = sort_file_contents(rustywind, Some(file_path), &contents, options);
+
+        if let Err(error) = write_file(file_path, &sorted_content, options) {
+            report.error = Some(format!("unable to write file: {error}"));
+        }
+    }
+
+    Some(report)
+}
+
+fn print_changed_files(
+    file_path: &Path,
+    contents_changed: bool,
+    options: &Options,
+    project_options: &ProjectOptions,
+) {
     if contents_changed {
         if !EXIT_ERROR.load(Ordering::Relaxed) {
             EXIT_ERROR.store(true, Ordering::Relaxed);
         }
 
-        if !should_ignore_current_file(&options.ignored_files, file_path) {
+        if !should_ignore_current_file(&project_options.ignored_files, file_path) {
             let file_name = get_file_name(file_path, &options.starting_paths);
             eprintln!("  * [UNFORMATTED FILE] {file_name}")
         }
     }
 }
 
+fn print_diff(file_name: &str, contents: &str, sorted_content: &str, options: &Options) {
+    let WriteMode::Diff {
+        context_lines,
+        color,
+    } = options.write_mode
+    else {
+        return;
+    };
+
+    let diff = diff::unified_diff(file_name, contents, sorted_content, context_lines, color);
+
+    if !diff.is_empty() {
+        EXIT_ERROR.store(true, Ordering::Relaxed);
+
+        // a single write, so the diffs of files sorted in parallel don't interleave
+        let _ = std::io::stdout().lock().write_all(diff.as_bytes());
+    }
+}
+
+fn print_unknown_classes(
+    file_path: &Path,
+    contents: &str,
+    options: &Options,
+    project_options: &ProjectOptions,
+) {
+    let unknown_classes = project_options.rustywind.find_unknown_classes_for_path(
+        file_path,
+        contents,
+        &project_options.unknown_allowlist,
+    );
+
+    if unknown_classes.is_empty() {
+        return;
+    }
+
+    EXIT_ERROR.store(true, Ordering::Relaxed);
+
+    let file_name = get_file_name(file_path, &options.starting_paths);
+    for unknown in unknown_classes {
+        let description = match &unknown.kind {
+            UnknownKind::Utility => format!("unknown class `{}`", unknown.class),
+            UnknownKind::Variant(variant) => {
+                format!("unknown variant `{variant}` in `{}`", unknown.class)
+            }
+        };
+
+        let suggestions = match unknown.suggestions.as_slice() {
+            [] => String::new(),
+            suggestions => format!(
+                ", did you mean {}?",
+                suggestions
+                    .iter()
+                    .map(|suggestion| format!("`{suggestion}`"))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        };
+
+        eprintln!(
+            "  * {file_name}:{}:{} {description}{suggestions}",
+            unknown.line, unknown.column
+        );
+    }
+}
+
 /// Return a boolean indicating whether the file should be ignored
 fn should_ignore_current_file(ignored_files: &HashSet<PathBuf>, current_file: &Path) -> bool {
     if ignored_files.is_empty() {
@@ -238,9 +621,34 @@ fn should_ignore_current_file(ignored_files: &HashSet<PathBuf>, current_file: &P
     }
 }
 
+/// Reads a file, from the git index with `--staged`.
+fn read_file(file_path: &Path, options: &Options) -> std::io::Result<String> {
+    match &options.git_index {
+        Some(git_index) => git_index.read(file_path),
+        None => fs::read_to_string(file_path),
+    }
+}
+
+/// Writes a file, to both the git index and the working tree with `--staged`.
+fn write_file(file_path: &Path, contents: &str, options: &Options) -> Result<()> {
+    match &options.git_index {
+        Some(git_index) => git_index.write(file_path, contents),
+        None => Ok(fs::write(file_path, contents.as_bytes())?),
+    }
+}
+
 fn write_to_file(file_path: &Path, sorted_contents: &str, options: &Options) {
-    match fs::write(file_path, sorted_contents.as_bytes()) {
+    match write_file(file_path, sorted_contents, options) {
         Ok(_) => print_file_name(file_path, true, options),
+        // the staged classes of a file with unstaged changes are left unsorted, which must
+        // fail a pre-commit hook
+        Err(err) if options.git_index.is_some() => {
+            EXIT_ERROR.store(true, Ordering::Relaxed);
+            eprintln!(
+                "  * [SKIPPED] {}: {err}",
+                get_file_name(file_path, &options.starting_paths)
+            );
+        }
         Err(err) => {
             eprintln!("\nError: {:?}", err);
             eprintln!(
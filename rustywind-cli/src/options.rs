@@ -1,22 +1,35 @@
-use clap::ValueEnum;
+use clap::{ColorChoice, Parser, ValueEnum};
 use color_eyre::Help;
 use eyre::{Context, Result};
 use ignore::WalkBuilder;
 use regex::Regex;
 use rustywind_core::RustyWind;
 use rustywind_core::class_wrapping::ClassWrapping;
+use rustywind_core::defaults::class_regex;
+use rustywind_core::diagnostics::UnknownAllowlist;
+use rustywind_core::edit::SortRange;
+use rustywind_core::plugins::Plugin;
+use rustywind_core::tailwind_version::TailwindVersion;
+use rustywind_core::utility_extensions::{PropertyPosition, UtilityExtensions};
 use rustywind_vite::create_vite_sorter;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::Read;
+use std::io::{IsTerminal, Read};
+use std::ops::{Range, RangeInclusive};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 
 use ahash::AHashMap as HashMap;
 use ahash::AHashSet as HashSet;
 
 use crate::Cli;
-use crate::sorter::{FinderRegex, Sorter};
+use crate::config::{ConfigFile, ConfigFileOptions, ExtractionRuleConfig};
+use crate::git::{GitIndex, GitSelection};
+use crate::prettier::PrettierConfig;
+use crate::reporter::Reporter;
+use crate::sorter::{ExtractionPattern, FinderRegex, FinderRegexSet, Sorter};
 
 #[derive(Debug)]
 pub enum WriteMode {
@@ -25,12 +38,30 @@ pub enum WriteMode {
     ToConsole,
     ToStdOut,
     CheckFormatted,
+    ReportUnknown,
+    Diff { context_lines: usize, color: bool },
+    Report { reporter: Reporter, write: bool },
 }
 
 #[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ConfigFileContents {
-    sort_order: Vec<String>,
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UtilityExtensionsFileContents {
+    #[serde(default)]
+    plugins: Vec<String>,
+    #[serde(default)]
+    utilities: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    declaration_counts: BTreeMap<String, usize>,
+    #[serde(default)]
+    property_order: Vec<PropertyOrderEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PropertyOrderEntry {
+    property: String,
+    before: Option<String>,
+    after: Option<String>,
 }
 
 // Wrapper to be able to use the `ValueEnum` trait without adding clap to the core crate
@@ -51,18 +82,138 @@ impl ValueEnum for CliClassWrapping {
     }
 }
 
+impl From<CliClassWrapping> for ClassWrapping {
+    fn from(class_wrapping: CliClassWrapping) -> Self {
+        class_wrapping.0
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CliTailwindVersion(TailwindVersion);
+
+impl ValueEnum for CliTailwindVersion {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            CliTailwindVersion(TailwindVersion::V3),
+            CliTailwindVersion(TailwindVersion::V4),
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(clap::builder::PossibleValue::new(self.0.as_str()))
+    }
+}
+
+impl From<CliTailwindVersion> for TailwindVersion {
+    fn from(tailwind_version: CliTailwindVersion) -> Self {
+        tailwind_version.0
+    }
+}
+
 #[derive(Debug)]
 pub struct Options {
     pub stdin: Option<String>,
-    pub rustywind: RustyWind,
+    /// The project options of the starting path, used for stdin and for files without a
+    /// config file of their own.
+    pub project_options: Arc<ProjectOptions>,
     pub write_mode: WriteMode,
     pub starting_paths: Vec<PathBuf>,
     pub search_paths: Vec<PathBuf>,
-    pub ignored_files: HashSet<PathBuf>,
     pub quiet: bool,
+    /// The config, prettier config, CSS and utility extensions files the options of the
+    /// starting path are built from, which `--watch` reloads the options on.
+    pub source_files: Vec<PathBuf>,
+    /// The git index that files are read from and written to with `--staged`.
+    pub git_index: Option<GitIndex>,
+    /// The part of each file to sort, from `--range` or `--lines`.
+    pub sort_range: Option<SortRange>,
+    /// The command line options the project options of the other config files are built
+    /// from.
+    cli: Cli,
+    /// The config files of each directory of the search paths.
+    directory_config_files: HashMap<PathBuf, ConfigFiles>,
+    /// The project options of each pair of config files, built the first time a file uses
+    /// them, or the error building them.
+    config_file_options: HashMap<ConfigFiles, OnceLock<Result<Arc<ProjectOptions>, String>>>,
+}
+
+/// The options that can differ between the packages of a monorepo, taken from the nearest
+/// config file. The write mode and `quiet` only come from the command line and the config
+/// file of the starting path.
+#[derive(Debug)]
+pub struct ProjectOptions {
+    pub rustywind: RustyWind,
+    pub ignored_files: HashSet<PathBuf>,
+    pub unknown_allowlist: UnknownAllowlist,
+}
+
+impl ProjectOptions {
+    fn new_from_cli(cli: &Cli, config_options: ConfigFileOptions) -> Result<Self> {
+        let custom_regexes = get_custom_regexes_from_cli(cli)?;
+        let default_regex = get_default_regex_from_cli(cli, &custom_regexes)?;
+
+        let rustywind = RustyWind::default()
+            .with_regex(
+                custom_regexes
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| default_regex.clone()),
+            )
+            .with_sorter(get_sorter_from_cli(cli, config_options.sort_order)?)
+            .with_allow_duplicates(cli.allow_duplicates)
+            .with_class_wrapping(get_class_wrapping_from_cli(cli))
+            .with_tailwind_prefix(cli.tailwind_prefix.clone())
+            .with_utility_extensions(get_utility_extensions_from_cli(cli)?.map(Arc::new))
+            .with_tailwind_version(cli.tailwind_version.map(Into::into).unwrap_or_default())
+            .with_regex_set(
+                get_regex_set(cli, custom_regexes, default_regex, config_options.extract)?
+                    .map(Arc::new),
+            )
+            .with_preserve_whitespace(cli.preserve_whitespace);
+
+        Ok(ProjectOptions {
+            rustywind,
+            ignored_files: get_ignored_files_from_cli(cli),
+            unknown_allowlist: UnknownAllowlist::new(cli.allow_unknown.iter().flatten()),
+        })
+    }
+
+    /// The project options from config files alone, for the files of editors the language
+    /// server sorts.
+    pub(crate) fn new_for_config_files(config_files: &ConfigFiles) -> Result<Self> {
+        let cli = Cli::parse_from(["rustywind", "--stdin"]);
+        let (configured_cli, config_options) = apply_config_files(&cli, config_files)?;
+
+        ProjectOptions::new_from_cli(&configured_cli, config_options)
+    }
 }
 
 impl Options {
+    /// The project options for a file, from the nearest config file in its directory or
+    /// the ancestors of its directory. Returns the error of a config file that can't be
+    /// loaded, for every file using it.
+    pub fn project_options_for(&self, file_path: &Path) -> Result<&ProjectOptions, &str> {
+        let Some((config_files, options)) = file_path
+            .parent()
+            .and_then(|directory| self.directory_config_files.get(directory))
+            .and_then(|config_files| self.config_file_options.get_key_value(config_files))
+        else {
+            return Ok(&self.project_options);
+        };
+
+        options
+            .get_or_init(|| {
+                let (configured_cli, config_options) = apply_config_files(&self.cli, config_files)
+                    .map_err(|error| format!("{error:#}"))?;
+
+                ProjectOptions::new_from_cli(&configured_cli, config_options)
+                    .map(Arc::new)
+                    .map_err(|error| format!("{error:#}"))
+            })
+            .as_deref()
+            .map_err(String::as_str)
+    }
+
     pub fn new_from_cli(cli: Cli) -> Result<Options> {
         let stdin = if cli.stdin {
             let mut buffer = String::new();
@@ -74,34 +225,210 @@ impl Options {
         };
 
         let starting_paths = get_starting_path_from_cli(&cli);
-        let search_paths = get_search_paths_from_starting_paths(&starting_paths);
-
-        let rustywind = RustyWind {
-            regex: get_custom_regex_from_cli(&cli)?,
-            sorter: get_sorter_from_cli(&cli)?,
-            allow_duplicates: cli.allow_duplicates,
-            class_wrapping: get_class_wrapping_from_cli(&cli),
-            tailwind_prefix: cli.tailwind_prefix.clone(),
+        let git_files = match GitSelection::from_cli(&cli) {
+            Some(git_selection) => Some(git_selection.files(&starting_paths)?),
+            None => None,
+        };
+        let search_paths = get_search_paths_from_starting_paths(&starting_paths, git_files);
+        let git_index = if cli.staged {
+            Some(GitIndex::new(&starting_paths)?)
+        } else {
+            None
+        };
+
+        let config_files = get_config_files_from_cli(&cli, &starting_paths);
+        let (configured_cli, config_options) = apply_config_files(&cli, &config_files)?;
+        let source_files = [
+            config_files.config_file.clone(),
+            config_files.prettier_config.clone(),
+            configured_cli.output_css_file.as_ref().map(PathBuf::from),
+            configured_cli
+                .utility_extensions
+                .as_ref()
+                .map(PathBuf::from),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let project_options = Arc::new(ProjectOptions::new_from_cli(
+            &configured_cli,
+            config_options,
+        )?);
+
+        // an explicit config file applies to every file
+        let directory_config_files = if cli.config_file.is_some() {
+            HashMap::new()
+        } else {
+            get_directory_config_files(&search_paths)
         };
 
+        let mut config_file_options: HashMap<_, _> = directory_config_files
+            .values()
+            .map(|config_files| (config_files.clone(), OnceLock::new()))
+            .collect();
+        config_file_options.insert(
+            config_files,
+            OnceLock::from(Ok(Arc::clone(&project_options))),
+        );
+
         Ok(Options {
             stdin,
-            rustywind,
+            project_options,
             starting_paths,
             search_paths,
-            write_mode: get_write_mode_from_cli(&cli),
-            ignored_files: get_ignored_files_from_cli(&cli),
-            quiet: cli.quiet,
+            write_mode: get_write_mode_from_cli(&configured_cli),
+            quiet: configured_cli.quiet,
+            source_files,
+            git_index,
+            sort_range: get_sort_range_from_cli(&cli),
+            cli,
+            directory_config_files,
+            config_file_options,
         })
     }
 }
 
-fn get_sorter_from_cli(cli: &Cli) -> Result<Sorter> {
-    if let Some(vite_css_url) = &cli.vite_css {
-        return create_vite_sorter(vite_css_url, cli.skip_ssl_verification);
+/// The rustywind and prettier config files that apply to a directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ConfigFiles {
+    config_file: Option<PathBuf>,
+    prettier_config: Option<PathBuf>,
+}
+
+impl ConfigFiles {
+    pub(crate) fn find(directory: &Path) -> Self {
+        ConfigFiles {
+            config_file: ConfigFile::find(directory),
+            prettier_config: PrettierConfig::find(directory),
+        }
     }
 
-    if let Some(css_file) = &cli.output_css_file {
+    /// Checks if `path` is one of the config files.
+    pub(crate) fn contains(&self, path: &Path) -> bool {
+        self.config_file.as_deref() == Some(path) || self.prettier_config.as_deref() == Some(path)
+    }
+}
+
+/// Applies the config file, then the prettier config, to a copy of the command line
+/// options, and returns the options that can only be set in the config file.
+fn apply_config_files(cli: &Cli, config_files: &ConfigFiles) -> Result<(Cli, ConfigFileOptions)> {
+    let mut cli = cli.clone();
+
+    let config_options = match &config_files.config_file {
+        Some(config_file) => {
+            let config_dir = config_file.parent().unwrap_or(Path::new(""));
+            ConfigFile::load(config_file)?.apply_to(&mut cli, config_dir)
+        }
+        None => ConfigFileOptions::default(),
+    };
+
+    if let Some(prettier_config) = &config_files.prettier_config {
+        PrettierConfig::load(prettier_config)?.apply_to(&mut cli);
+    }
+
+    Ok((cli, config_options))
+}
+
+/// Finds the config files of the directory of every search path.
+fn get_directory_config_files(search_paths: &[PathBuf]) -> HashMap<PathBuf, ConfigFiles> {
+    let mut directory_config_files = HashMap::new();
+
+    for directory in search_paths.iter().filter_map(|path| path.parent()) {
+        if directory_config_files.contains_key(directory) {
+            continue;
+        }
+
+        let config_files = fs::canonicalize(directory)
+            .map(|directory| ConfigFiles::find(&directory))
+            .unwrap_or_default();
+
+        directory_config_files.insert(directory.to_owned(), config_files);
+    }
+
+    directory_config_files
+}
+
+/// The config file given with `--config-file`, or the nearest config file in the
+/// directory of the first path, or of the working directory with `--stdin`, and their
+/// ancestors. The prettier config is looked up the same way.
+fn get_config_files_from_cli(cli: &Cli, starting_paths: &[PathBuf]) -> ConfigFiles {
+    let start = match starting_paths.first() {
+        Some(path) => fs::canonicalize(path).ok(),
+        None => std::env::current_dir().ok(),
+    };
+
+    let start = start.and_then(|start| {
+        if start.is_file() {
+            start.parent().map(Path::to_owned)
+        } else {
+            Some(start)
+        }
+    });
+
+    let mut config_files = start.as_deref().map(ConfigFiles::find).unwrap_or_default();
+
+    if let Some(config_file) = &cli.config_file {
+        config_files.config_file = Some(PathBuf::from(config_file));
+    }
+
+    config_files
+}
+
+fn get_sorter_from_cli(cli: &Cli, sort_order: Option<Vec<String>>) -> Result<Sorter> {
+    let sorter = get_custom_sorter(
+        sort_order,
+        cli.output_css_file.as_deref(),
+        cli.vite_css.as_deref(),
+        cli.skip_ssl_verification,
+    )?;
+
+    if cli.pattern_fallback {
+        if matches!(sorter, Sorter::PatternSorter) {
+            return Err(eyre::eyre!("--pattern-fallback needs a custom sort order"))
+                .with_suggestion(
+                    || "Set sortOrder in the config file, or use --output-css-file or --vite-css",
+                );
+        }
+
+        return Ok(sorter.with_pattern_fallback());
+    }
+
+    if matches!(sorter, Sorter::CustomSorter(_)) {
+        let pattern_option = [
+            ("--tailwind-version", cli.tailwind_version.is_some()),
+            ("--plugins", cli.plugins.is_some()),
+            ("--utility-extensions", cli.utility_extensions.is_some()),
+        ]
+        .into_iter()
+        .find_map(|(option, is_set)| is_set.then_some(option));
+
+        if let Some(option) = pattern_option {
+            return Err(eyre::eyre!(
+                "{option} only applies to the built-in sorter, not to a custom sort order"
+            ))
+            .with_suggestion(|| {
+                "Add --pattern-fallback to sort the classes missing from the custom sort order \
+                 with the built-in sorter"
+            });
+        }
+    }
+
+    Ok(sorter)
+}
+
+/// Builds the sorter from the vite CSS url, the CSS file or the sort order of a config
+/// file, in that order, falling back to the pattern sorter.
+pub fn get_custom_sorter(
+    sort_order: Option<Vec<String>>,
+    output_css_file: Option<&str>,
+    vite_css: Option<&str>,
+    skip_ssl_verification: bool,
+) -> Result<Sorter> {
+    if let Some(vite_css_url) = vite_css {
+        return create_vite_sorter(vite_css_url, skip_ssl_verification);
+    }
+
+    if let Some(css_file) = output_css_file {
         let css_file = std::fs::File::open(css_file)
             .wrap_err_with(|| format!("Error opening the css file {css_file}"))
             .with_suggestion(|| format!("Make sure the file {css_file} exists"))?;
@@ -111,18 +438,8 @@ fn get_sorter_from_cli(cli: &Cli) -> Result<Sorter> {
         return Ok(sorter);
     }
 
-    if let Some(config_file) = &cli.config_file {
-        let file_contents = fs::read_to_string(config_file)
-            .wrap_err_with(|| format!("Error reading the config file {config_file}"))
-            .with_suggestion(|| format!("Make sure the file {config_file} exists"));
-
-        let config_file: ConfigFileContents = serde_json::from_str(&file_contents?)
-            .wrap_err_with(|| format!("Error while parsing the config file {config_file}"))
-            .with_suggestion(|| {
-                format!("Make sure the {config_file} is valid json, with the expected format")
-            })?;
-
-        let sorter = parse_custom_sorter(config_file.sort_order);
+    if let Some(sort_order) = sort_order {
+        let sorter = parse_custom_sorter(sort_order);
         return Ok(Sorter::CustomSorter(sorter));
     }
 
@@ -130,19 +447,286 @@ fn get_sorter_from_cli(cli: &Cli) -> Result<Sorter> {
     Ok(Sorter::PatternSorter)
 }
 
-fn get_custom_regex_from_cli(cli: &Cli) -> Result<FinderRegex> {
-    match &cli.custom_regex {
-        Some(regex_string) => {
-            let regex = Regex::new(regex_string).wrap_err("Unable to parse custom regex")?;
+fn get_utility_extensions_from_cli(cli: &Cli) -> Result<Option<UtilityExtensions>> {
+    let extensions = get_utility_extensions(
+        cli.plugins.iter().flatten(),
+        cli.utility_extensions.as_deref(),
+    )?;
+
+    // v3 orders utilities by their core plugin, not by the properties extensions add
+    let tailwind_version = cli.tailwind_version.map(TailwindVersion::from);
+    if extensions.is_some() && tailwind_version == Some(TailwindVersion::V3) {
+        return Err(eyre::eyre!(
+            "`--plugins` and `--utility-extensions` can't be used with `--tailwind-version 3`"
+        ))
+        .with_suggestion(|| {
+            "Tailwind v3 orders utilities by their core plugin, remove --tailwind-version 3 to \
+             sort with plugins and utility extensions"
+        });
+    }
+
+    Ok(extensions)
+}
+
+/// Builds the utility extensions from the selected plugins and a TOML (`.toml`
+/// extension) or JSON utility extensions file. Entries from the file override the
+/// plugins' entries.
+pub fn get_utility_extensions<'a>(
+    plugins: impl IntoIterator<Item = &'a String>,
+    extensions_file: Option<&str>,
+) -> Result<Option<UtilityExtensions>> {
+    let mut extensions = UtilityExtensions::new();
 
-            if regex.captures_len() < 2 {
-                eyre::bail!("custom regex error, requires at-least 2 capture groups");
-            }
+    for plugin in plugins {
+        extensions.add_plugin(parse_plugin(plugin)?);
+    }
+
+    if let Some(extensions_file) = extensions_file {
+        load_utility_extensions(&mut extensions, extensions_file)?;
+    }
 
-            Ok(FinderRegex::CustomRegex(regex))
+    Ok((!extensions.is_empty()).then_some(extensions))
+}
+
+fn load_utility_extensions(
+    extensions: &mut UtilityExtensions,
+    extensions_file: &str,
+) -> Result<()> {
+    let file_contents = fs::read_to_string(extensions_file)
+        .wrap_err_with(|| format!("Error reading the utility extensions file {extensions_file}"))
+        .with_suggestion(|| format!("Make sure the file {extensions_file} exists"))?;
+
+    let contents: UtilityExtensionsFileContents = if extensions_file.ends_with(".toml") {
+        toml::from_str(&file_contents).wrap_err_with(|| {
+            format!("Error while parsing the utility extensions file {extensions_file}")
+        })
+    } else {
+        serde_json::from_str(&file_contents).wrap_err_with(|| {
+            format!("Error while parsing the utility extensions file {extensions_file}")
+        })
+    }
+    .with_suggestion(|| {
+        format!("Make sure the {extensions_file} is valid toml or json, with the expected format")
+    })?;
+
+    parse_utility_extensions(extensions, contents)
+        .wrap_err_with(|| format!("Invalid utility extensions file {extensions_file}"))
+}
+
+fn get_sort_range_from_cli(cli: &Cli) -> Option<SortRange> {
+    match (&cli.range, &cli.lines) {
+        (Some(range), _) => Some(SortRange::Bytes(range.clone())),
+        (None, Some(lines)) => Some(SortRange::Lines(lines.clone())),
+        (None, None) => None,
+    }
+}
+
+/// Parser for `--range`, a byte range written `start:end`.
+pub fn parse_byte_range(value: &str) -> Result<Range<usize>, String> {
+    let (start, end) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected `start:end`, got `{value}`"))?;
+
+    let start: usize = start
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid start `{start}`"))?;
+    let end: usize = end
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid end `{end}`"))?;
+
+    if start > end {
+        return Err(format!("the start {start} is after the end {end}"));
+    }
+
+    Ok(start..end)
+}
+
+/// Parser for `--lines`, 1-based lines written `start-end`, or a single line.
+pub fn parse_line_range(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+
+    let parse_line = |line: &str| match line.trim().parse::<usize>() {
+        Ok(line) if line > 0 => Ok(line),
+        _ => Err(format!("invalid line `{line}`, lines start at 1")),
+    };
+
+    let (start, end) = (parse_line(start)?, parse_line(end)?);
+    if start > end {
+        return Err(format!(
+            "the start line {start} is after the end line {end}"
+        ));
+    }
+
+    Ok(start..=end)
+}
+
+/// Parser for the names of the plugins compiled into this build.
+pub fn plugin_names() -> clap::builder::PossibleValuesParser {
+    clap::builder::PossibleValuesParser::new(Plugin::ALL.iter().map(Plugin::name))
+}
+
+fn parse_plugin(name: &str) -> Result<Plugin> {
+    Plugin::from_name(name)
+        .ok_or_else(|| eyre::eyre!("unknown plugin `{name}`"))
+        .with_suggestion(|| {
+            let available: Vec<_> = Plugin::ALL.iter().map(Plugin::name).collect();
+            format!(
+                "Available plugins in this build: {}",
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            )
+        })
+}
+
+fn parse_utility_extensions(
+    extensions: &mut UtilityExtensions,
+    contents: UtilityExtensionsFileContents,
+) -> Result<()> {
+    for plugin in &contents.plugins {
+        extensions.add_plugin(parse_plugin(plugin)?);
+    }
+
+    // place properties first, so utilities can use the added properties
+    for entry in contents.property_order {
+        let position = match (entry.before, entry.after) {
+            (Some(before), None) => PropertyPosition::Before(before),
+            (None, Some(after)) => PropertyPosition::After(after),
+            _ => eyre::bail!(
+                "property order entry for `{}` needs exactly one of `before` or `after`",
+                entry.property
+            ),
+        };
+
+        extensions.place_property(&entry.property, position)?;
+    }
+
+    for (utility, properties) in contents.utilities {
+        if let Some(unknown) = properties
+            .iter()
+            .find(|property| extensions.get_property_index(property).is_none())
+        {
+            eyre::bail!(
+                "utility `{utility}` uses property `{unknown}`, which is not in the property order"
+            );
         }
-        None => Ok(FinderRegex::DefaultRegex),
+
+        extensions.add_utility(&utility, properties);
+    }
+
+    for (utility, count) in contents.declaration_counts {
+        extensions.set_declaration_count(&utility, count);
+    }
+
+    Ok(())
+}
+
+fn get_custom_regexes_from_cli(cli: &Cli) -> Result<Vec<FinderRegex>> {
+    cli.custom_regex
+        .iter()
+        .flatten()
+        .map(|regex_string| parse_custom_regex(regex_string))
+        .collect()
+}
+
+/// The default regex, extended with the attributes from `--attributes` and the functions
+/// from `--functions`.
+fn get_default_regex_from_cli(cli: &Cli, custom_regexes: &[FinderRegex]) -> Result<FinderRegex> {
+    if cli.attributes.is_none() && cli.functions.is_none() {
+        return Ok(FinderRegex::DefaultRegex);
+    }
+
+    if !custom_regexes.is_empty() {
+        let option = if cli.attributes.is_some() {
+            "--attributes"
+        } else {
+            "--functions"
+        };
+
+        return Err(eyre::eyre!(
+            "`{option}` can't be used with `--custom-regex`"
+        ))
+        .with_suggestion(|| "Match the attributes and functions in the custom regex instead");
+    }
+
+    Ok(FinderRegex::CustomRegex(class_regex(
+        cli.attributes.iter().flatten(),
+        cli.functions.iter().flatten(),
+    )))
+}
+
+fn parse_custom_regex(regex_string: &str) -> Result<FinderRegex> {
+    let regex = Regex::new(regex_string)
+        .wrap_err_with(|| format!("Unable to parse custom regex `{regex_string}`"))?;
+
+    if regex.captures_len() < 2 {
+        return Err(eyre::eyre!(
+            "custom regex `{regex_string}` has no capture group for the classes"
+        ))
+        .with_suggestion(|| {
+            "Wrap the classes in a capture group, e.g. class=\"([^\"]*)\", or in named groups like (?P<classes>...)"
+        });
+    }
+
+    Ok(FinderRegex::CustomRegex(regex))
+}
+
+/// Builds the extraction patterns per file type from the config file, followed by the
+/// built-in ones unless a custom regex replaces the default regex. More than one custom
+/// regex are used together for the other files.
+fn get_regex_set(
+    cli: &Cli,
+    custom_regexes: Vec<FinderRegex>,
+    default_regex: FinderRegex,
+    extract: Vec<ExtractionRuleConfig>,
+) -> Result<Option<FinderRegexSet>> {
+    let mut regex_set = FinderRegexSet::new();
+
+    for rule in extract {
+        let patterns = rule
+            .patterns
+            .iter()
+            .map(|pattern| {
+                let regex = match &pattern.regex {
+                    Some(regex_string) => parse_custom_regex(regex_string)
+                        .wrap_err_with(|| format!("Invalid regex for {}", rule.files.join(", ")))?,
+                    None => default_regex.clone(),
+                };
+
+                let class_wrapping = pattern
+                    .class_wrapping
+                    .as_deref()
+                    .map(ClassWrapping::from)
+                    .unwrap_or_default();
+
+                Ok(ExtractionPattern::new(regex, class_wrapping))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        regex_set
+            .add_rule(&rule.files, patterns)
+            .wrap_err("Invalid `files` in an `extract` rule of the config file")?;
+    }
+
+    if custom_regexes.is_empty() {
+        regex_set.add_default_rules_with_regex(default_regex, get_class_wrapping_from_cli(cli));
     }
+
+    if custom_regexes.len() > 1 {
+        let class_wrapping = get_class_wrapping_from_cli(cli);
+        regex_set.set_fallback_patterns(
+            custom_regexes
+                .into_iter()
+                .map(|regex| ExtractionPattern::new(regex, class_wrapping))
+                .collect(),
+        );
+    }
+
+    Ok((!regex_set.is_empty()).then_some(regex_set))
 }
 
 fn get_class_wrapping_from_cli(cli: &Cli) -> ClassWrapping {
@@ -160,12 +744,24 @@ fn get_starting_path_from_cli(cli: &Cli) -> Vec<PathBuf> {
 }
 
 fn get_write_mode_from_cli(cli: &Cli) -> WriteMode {
-    if cli.dry_run {
+    if let Some(reporter) = cli.reporter {
+        WriteMode::Report {
+            reporter,
+            write: cli.write,
+        }
+    } else if cli.dry_run {
         WriteMode::DryRun
     } else if cli.write {
         WriteMode::ToFile
     } else if cli.check_formatted {
         WriteMode::CheckFormatted
+    } else if cli.report_unknown {
+        WriteMode::ReportUnknown
+    } else if cli.diff {
+        WriteMode::Diff {
+            context_lines: cli.diff_context.unwrap_or(3),
+            color: get_color_from_cli(cli),
+        }
     } else if cli.stdin {
         WriteMode::ToStdOut
     } else {
@@ -173,11 +769,71 @@ fn get_write_mode_from_cli(cli: &Cli) -> WriteMode {
     }
 }
 
-fn get_search_paths_from_starting_paths(starting_paths: &[PathBuf]) -> Vec<PathBuf> {
+/// Colors are used with `--color always`, or by default when printing to a terminal and
+/// `NO_COLOR` is not set.
+fn get_color_from_cli(cli: &Cli) -> bool {
+    match cli.color.unwrap_or(ColorChoice::Auto) {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+        }
+    }
+}
+
+/// The files of the starting paths that are not ignored. With `selected_files`, only the
+/// files whose canonical path is selected, without walking the directories that don't
+/// contain any.
+pub fn get_search_paths_from_starting_paths(
+    starting_paths: &[PathBuf],
+    selected_files: Option<HashSet<PathBuf>>,
+) -> Vec<PathBuf> {
+    let Some(selected_files) = selected_files else {
+        return starting_paths
+            .iter()
+            .flat_map(|starting_path| {
+                WalkBuilder::new(starting_path)
+                    .build()
+                    .filter_map(Result::ok)
+                    .filter(|f| f.path().is_file())
+                    .map(|file| file.path().to_owned())
+            })
+            .collect();
+    };
+
+    let selected_directories: HashSet<PathBuf> = selected_files
+        .iter()
+        .flat_map(|file| file.ancestors().skip(1))
+        .map(Path::to_path_buf)
+        .collect();
+    let selected_files = Arc::new(selected_files);
+    let selected_directories = Arc::new(selected_directories);
+
     starting_paths
         .iter()
-        .flat_map(|starting_path| {
-            WalkBuilder::new(starting_path)
+        .filter_map(|starting_path| Some((starting_path, fs::canonicalize(starting_path).ok()?)))
+        .flat_map(|(starting_path, canonical_path)| {
+            let selected_files = selected_files.clone();
+            let selected_directories = selected_directories.clone();
+            let starting_path = starting_path.clone();
+
+            WalkBuilder::new(&starting_path)
+                .filter_entry(move |entry| {
+                    let relative_path = entry
+                        .path()
+                        .strip_prefix(&starting_path)
+                        .unwrap_or(Path::new(""));
+                    let path = canonical_path.join(relative_path);
+
+                    if entry
+                        .file_type()
+                        .is_some_and(|file_type| file_type.is_dir())
+                    {
+                        selected_directories.contains(&path)
+                    } else {
+                        selected_files.contains(&path)
+                    }
+                })
                 .build()
                 .filter_map(Result::ok)
                 .filter(|f| f.path().is_file())
@@ -206,3 +862,210 @@ fn parse_custom_sorter(contents: Vec<String>) -> HashMap<String, usize> {
         .map(|(index, class)| (class, index))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use clap::Parser;
+
+    #[test]
+    fn test_nearest_config_file_wins_per_directory() {
+        let project = TempProject::new("monorepo");
+        let root = project.path();
+        let v3_app = root.join("apps").join("v3");
+        let v4_app = root.join("apps").join("v4").join("src");
+        fs::create_dir_all(&v3_app).unwrap();
+        fs::create_dir_all(&v4_app).unwrap();
+
+        fs::write(root.join("rustywind.toml"), "allowDuplicates = true").unwrap();
+        fs::write(v3_app.join("rustywind.toml"), "tailwindPrefix = \"tw-\"").unwrap();
+        for directory in [root, &v3_app, &v4_app] {
+            fs::write(directory.join("index.html"), "").unwrap();
+        }
+
+        let root_path = root.to_string_lossy().into_owned();
+        let options = Options::new_from_cli(Cli::parse_from(["rustywind", &root_path])).unwrap();
+
+        let v3_options = options
+            .project_options_for(&v3_app.join("index.html"))
+            .unwrap();
+        assert_eq!(v3_options.rustywind.tailwind_prefix(), Some("tw-"));
+        assert!(!v3_options.rustywind.allow_duplicates());
+
+        let v4_options = options
+            .project_options_for(&v4_app.join("index.html"))
+            .unwrap();
+        assert_eq!(v4_options.rustywind.tailwind_prefix(), None);
+        assert!(v4_options.rustywind.allow_duplicates());
+        assert!(std::ptr::eq(
+            v4_options,
+            options
+                .project_options_for(&root.join("index.html"))
+                .unwrap()
+        ));
+
+        // a config file that can't be loaded only fails the files using it
+        fs::write(v3_app.join("rustywind.toml"), "customRegex = \"(\"").unwrap();
+        let options = Options::new_from_cli(Cli::parse_from(["rustywind", &root_path])).unwrap();
+        assert!(
+            options
+                .project_options_for(&v3_app.join("index.html"))
+                .is_err()
+        );
+        assert!(
+            options
+                .project_options_for(&v4_app.join("index.html"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_repeated_custom_regexes() {
+        let cli = Cli::parse_from([
+            "rustywind",
+            "--custom-regex",
+            r#"tw="([^"]*)""#,
+            "--custom-regex",
+            r"classList\.add\('(?P<classes>[^']*)'\)",
+            "--stdin",
+        ]);
+        let project_options =
+            ProjectOptions::new_from_cli(&cli, ConfigFileOptions::default()).unwrap();
+
+        assert_eq!(
+            project_options.rustywind.sort_file_contents(
+                r#"<a tw="p-4 flex" class="p-4 flex"></a> el.classList.add('p-4 flex')"#
+            ),
+            r#"<a tw="flex p-4" class="p-4 flex"></a> el.classList.add('flex p-4')"#
+        );
+
+        let cli = Cli::parse_from(["rustywind", "--custom-regex", r#"tw="[^"]*""#, "--stdin"]);
+        let error = ProjectOptions::new_from_cli(&cli, ConfigFileOptions::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"custom regex `tw="[^"]*"` has no capture group for the classes"#
+        );
+    }
+
+    #[test]
+    fn test_utility_extensions_with_tailwind_v3() {
+        let project = TempProject::new("v3-extensions");
+        let extensions_file = project.path().join("extensions.json");
+        fs::write(
+            &extensions_file,
+            r#"{ "utilities": { "btn": ["display"] } }"#,
+        )
+        .unwrap();
+        let extensions_path = extensions_file.to_string_lossy().into_owned();
+
+        let cli = Cli::parse_from([
+            "rustywind",
+            "--utility-extensions",
+            &extensions_path,
+            "--tailwind-version",
+            "3",
+            "--stdin",
+        ]);
+        let error = ProjectOptions::new_from_cli(&cli, ConfigFileOptions::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`--plugins` and `--utility-extensions` can't be used with `--tailwind-version 3`"
+        );
+
+        let cli = Cli::parse_from([
+            "rustywind",
+            "--utility-extensions",
+            &extensions_path,
+            "--stdin",
+        ]);
+        assert!(ProjectOptions::new_from_cli(&cli, ConfigFileOptions::default()).is_ok());
+
+        fs::remove_file(&extensions_file).unwrap();
+    }
+
+    #[test]
+    fn test_attributes() {
+        let cli = Cli::parse_from(["rustywind", "--attributes", "tw,ngClass", "--stdin"]);
+        let project_options =
+            ProjectOptions::new_from_cli(&cli, ConfigFileOptions::default()).unwrap();
+
+        assert_eq!(
+            project_options
+                .rustywind
+                .sort_file_contents(r#"<a tw="p-4 flex" [ngClass]="p-4 flex" class="p-4 flex">"#),
+            r#"<a tw="flex p-4" [ngClass]="flex p-4" class="flex p-4">"#
+        );
+
+        let cli = Cli::parse_from([
+            "rustywind",
+            "--attributes",
+            "tw",
+            "--custom-regex",
+            r#"tw="([^"]*)""#,
+            "--stdin",
+        ]);
+        let error = ProjectOptions::new_from_cli(&cli, ConfigFileOptions::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`--attributes` can't be used with `--custom-regex`"
+        );
+    }
+
+    #[test]
+    fn test_search_paths_of_selected_files() {
+        let project = TempProject::new("selected");
+        let root = project.path().to_path_buf();
+        let app = root.join("app");
+        let other = root.join("other");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        fs::write(root.join(".ignore"), "ignored.html\n").unwrap();
+        for file in [
+            app.join("changed.html"),
+            app.join("ignored.html"),
+            app.join("unchanged.html"),
+            other.join("changed.html"),
+        ] {
+            fs::write(file, "").unwrap();
+        }
+
+        let selected_files: HashSet<PathBuf> = [
+            app.join("changed.html"),
+            app.join("ignored.html"),
+            other.join("changed.html"),
+        ]
+        .into_iter()
+        .map(|file| fs::canonicalize(file).unwrap())
+        .collect();
+
+        let search_paths = get_search_paths_from_starting_paths(
+            std::slice::from_ref(&app),
+            Some(selected_files.clone()),
+        );
+        assert_eq!(search_paths, vec![app.join("changed.html")]);
+
+        let mut search_paths =
+            get_search_paths_from_starting_paths(std::slice::from_ref(&root), Some(selected_files));
+        search_paths.sort();
+        assert_eq!(
+            search_paths,
+            vec![app.join("changed.html"), other.join("changed.html")]
+        );
+    }
+
+    #[test]
+    fn test_sort_range() {
+        assert_eq!(parse_byte_range("120:480"), Ok(120..480));
+        assert!(parse_byte_range("480:120").is_err());
+        assert!(parse_byte_range("120").is_err());
+        assert_eq!(parse_line_range("10-40"), Ok(10..=40));
+        assert_eq!(parse_line_range("10"), Ok(10..=10));
+        assert!(parse_line_range("0-4").is_err());
+
+        let options =
+            Options::new_from_cli(Cli::parse_from(["rustywind", "--lines", "3-4", "."])).unwrap();
+        assert_eq!(options.sort_range, Some(SortRange::Lines(3..=4)));
+    }
+}
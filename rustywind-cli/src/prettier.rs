@@ -0,0 +1,252 @@
+use clap::ValueEnum;
+use color_eyre::Help;
+use eyre::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Cli;
+use crate::options::CliTailwindVersion;
+
+/// The prettier config file names looked up in each directory, in the order prettier looks
+/// them up. Only the JSON ones are read, the others end the lookup like they do for
+/// prettier, without setting any option.
+pub const PRETTIER_CONFIG_FILE_NAMES: [&str; 16] = [
+    "package.json",
+    "package.yaml",
+    ".prettierrc",
+    ".prettierrc.json",
+    ".prettierrc.yaml",
+    ".prettierrc.yml",
+    ".prettierrc.json5",
+    ".prettierrc.js",
+    "prettier.config.js",
+    ".prettierrc.ts",
+    "prettier.config.ts",
+    ".prettierrc.mjs",
+    "prettier.config.mjs",
+    ".prettierrc.cjs",
+    "prettier.config.cjs",
+    ".prettierrc.toml",
+];
+
+/// The prettier-plugin-tailwindcss options of a `.prettierrc`, `.prettierrc.json` or the
+/// `prettier` entry of a `package.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrettierConfig {
+    pub tailwind_attributes: Option<Vec<String>>,
+    pub tailwind_functions: Option<Vec<String>>,
+    #[serde(default)]
+    pub tailwind_preserve_whitespace: bool,
+    #[serde(default)]
+    pub tailwind_preserve_duplicates: bool,
+    /// Only selects the Tailwind v4 order, the stylesheet is not read.
+    pub tailwind_stylesheet: Option<String>,
+}
+
+impl PrettierConfig {
+    /// Reads the prettier-plugin-tailwindcss options of a prettier config file. Config files
+    /// that are not JSON, like a YAML `.prettierrc`, have no options.
+    pub fn load(prettier_config: &Path) -> Result<Self> {
+        let name = prettier_config.display();
+        let file_name = prettier_config.file_name().unwrap_or_default();
+
+        if file_name != "package.json"
+            && file_name != ".prettierrc"
+            && file_name != ".prettierrc.json"
+        {
+            log::debug!("prettier config {name} is not JSON, its options are not read");
+            return Ok(Self::default());
+        }
+
+        let file_contents = fs::read_to_string(prettier_config)
+            .wrap_err_with(|| format!("Error reading the prettier config {name}"))?;
+
+        let contents: serde_json::Value = match serde_json::from_str(&file_contents) {
+            Ok(contents) => contents,
+            // a `.prettierrc` can also be written in YAML
+            Err(_) if file_name == ".prettierrc" => {
+                log::debug!("prettier config {name} is not JSON, its options are not read");
+                return Ok(Self::default());
+            }
+            Err(error) => {
+                return Err(error)
+                    .wrap_err_with(|| format!("Error while parsing the prettier config {name}"));
+            }
+        };
+
+        let options = if file_name == "package.json" {
+            match contents.get("prettier") {
+                Some(options @ serde_json::Value::Object(_)) => options.clone(),
+                _ => {
+                    log::debug!("the prettier entry of {name} is a shared config, it is not read");
+                    return Ok(Self::default());
+                }
+            }
+        } else {
+            contents
+        };
+
+        serde_json::from_value(options)
+            .wrap_err_with(|| format!("Error while parsing the prettier config {name}"))
+            .with_suggestion(|| {
+                "Make sure the tailwind options have the types prettier-plugin-tailwindcss expects"
+            })
+    }
+
+    /// Finds the prettier config file prettier uses for the files of `start`, in `start` or
+    /// its ancestors. A `package.json` without a `prettier` entry is skipped.
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start.ancestors().find_map(|directory| {
+            PRETTIER_CONFIG_FILE_NAMES
+                .iter()
+                .map(|name| directory.join(name))
+                .find(|prettier_config| {
+                    prettier_config.is_file()
+                        && (!prettier_config.ends_with("package.json")
+                            || has_prettier_entry(prettier_config))
+                })
+        })
+    }
+
+    /// Fills in the options that were not set on the command line or in the config file.
+    pub fn apply_to(self, cli: &mut Cli) {
+        if cli.custom_regex.is_none() && cli.attributes.is_none() && cli.functions.is_none() {
+            cli.attributes = self.tailwind_attributes;
+            cli.functions = self.tailwind_functions;
+        }
+
+        if !cli.no_preserve_whitespace {
+            cli.preserve_whitespace |= self.tailwind_preserve_whitespace;
+        }
+        if !cli.no_allow_duplicates {
+            cli.allow_duplicates |= self.tailwind_preserve_duplicates;
+        }
+
+        // prettier-plugin-tailwindcss only reads a stylesheet for Tailwind v4
+        if self.tailwind_stylesheet.is_some() && cli.tailwind_version.is_none() {
+            cli.tailwind_version = CliTailwindVersion::from_str("4", false).ok();
+        }
+    }
+}
+
+fn has_prettier_entry(package_json: &Path) -> bool {
+    fs::read_to_string(package_json)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .is_some_and(|contents| contents.get("prettier").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use clap::Parser;
+    use rustywind_core::tailwind_version::TailwindVersion;
+
+    #[test]
+    fn test_prettier_options_set_options_not_given_on_the_command_line() {
+        let prettier_config: PrettierConfig = serde_json::from_str(
+            r#"{
+                "plugins": ["prettier-plugin-tailwindcss"],
+                "tailwindAttributes": ["tw"],
+                "tailwindFunctions": ["clsx"],
+                "tailwindPreserveWhitespace": true,
+                "tailwindPreserveDuplicates": true,
+                "tailwindStylesheet": "./app.css"
+            }"#,
+        )
+        .unwrap();
+
+        let mut cli = Cli::parse_from(["rustywind", "."]);
+        prettier_config.apply_to(&mut cli);
+
+        assert_eq!(cli.attributes, Some(vec!["tw".to_string()]));
+        assert_eq!(cli.functions, Some(vec!["clsx".to_string()]));
+        assert!(cli.preserve_whitespace);
+        assert!(cli.allow_duplicates);
+        assert_eq!(
+            cli.tailwind_version.map(TailwindVersion::from),
+            Some(TailwindVersion::V4)
+        );
+
+        let prettier_config: PrettierConfig =
+            serde_json::from_str(r#"{ "tailwindAttributes": ["tw"] }"#).unwrap();
+        let mut cli = Cli::parse_from(["rustywind", "--custom-regex", "tw=\"([^\"]*)\"", "."]);
+        prettier_config.apply_to(&mut cli);
+
+        assert_eq!(cli.attributes, None);
+    }
+
+    #[test]
+    fn test_command_line_overrides_prettier_flags() {
+        let prettier_config: PrettierConfig = serde_json::from_str(
+            r#"{
+                "tailwindPreserveWhitespace": true,
+                "tailwindPreserveDuplicates": true
+            }"#,
+        )
+        .unwrap();
+
+        let mut cli = Cli::parse_from([
+            "rustywind",
+            "--no-preserve-whitespace",
+            "--no-allow-duplicates",
+            ".",
+        ]);
+        prettier_config.apply_to(&mut cli);
+
+        assert!(!cli.preserve_whitespace);
+        assert!(!cli.allow_duplicates);
+    }
+
+    #[test]
+    fn test_find_and_load_prettier_config() {
+        let project = TempProject::new("prettier");
+        let root = project.path();
+        let package = root.join("packages").join("app");
+        let yaml_package = root.join("packages").join("yaml");
+        fs::create_dir_all(&package).unwrap();
+        fs::create_dir_all(&yaml_package).unwrap();
+
+        fs::write(
+            root.join("package.json"),
+            r#"{ "name": "root", "prettier": { "tailwindFunctions": ["cn"] } }"#,
+        )
+        .unwrap();
+        fs::write(package.join("package.json"), r#"{ "name": "app" }"#).unwrap();
+        fs::write(
+            yaml_package.join(".prettierrc"),
+            "tailwindFunctions:\n  - tw\n",
+        )
+        .unwrap();
+
+        // a package.json without a prettier entry is skipped
+        let prettier_config = PrettierConfig::find(&package).unwrap();
+        assert_eq!(prettier_config, root.join("package.json"));
+        assert_eq!(
+            PrettierConfig::load(&prettier_config)
+                .unwrap()
+                .tailwind_functions,
+            Some(vec!["cn".to_string()])
+        );
+
+        // a YAML .prettierrc ends the lookup without options
+        let prettier_config = PrettierConfig::find(&yaml_package).unwrap();
+        assert_eq!(prettier_config, yaml_package.join(".prettierrc"));
+        assert!(
+            PrettierConfig::load(&prettier_config)
+                .unwrap()
+                .tailwind_functions
+                .is_none()
+        );
+
+        fs::write(
+            package.join(".prettierrc.json"),
+            r#"{ "tailwindFunctions": "cn" }"#,
+        )
+        .unwrap();
+        assert!(PrettierConfig::load(&package.join(".prettierrc.json")).is_err());
+    }
+}
@@ -0,0 +1,433 @@
+use clap::ValueEnum;
+use rustywind_core::edit::ClassListEdit;
+use serde_json::json;
+use std::fmt::Write as _;
+
+const RULE_ID: &str = "unsorted-classes";
+const IO_RULE_ID: &str = "io-error";
+
+/// The machine readable formats of `--reporter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Reporter {
+    Json,
+    Sarif,
+    Checkstyle,
+    Github,
+}
+
+/// The class lists of a file that are not sorted, or the error reading or writing it.
+#[derive(Debug, Default)]
+pub struct FileReport {
+    pub path: String,
+    pub edits: Vec<ClassListEdit>,
+    pub error: Option<String>,
+}
+
+/// The counts reported along with the files.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub files_checked: usize,
+    pub files_unsorted: usize,
+    pub class_lists_unsorted: usize,
+    pub errors: usize,
+}
+
+impl Summary {
+    pub fn new(reports: &[FileReport]) -> Self {
+        Summary {
+            files_checked: reports.len(),
+            files_unsorted: reports
+                .iter()
+                .filter(|report| !report.edits.is_empty())
+                .count(),
+            class_lists_unsorted: reports.iter().map(|report| report.edits.len()).sum(),
+            errors: reports
+                .iter()
+                .filter(|report| report.error.is_some())
+                .count(),
+        }
+    }
+}
+
+impl Reporter {
+    /// Renders the reports. With `sorted`, the class lists were sorted in place and are
+    /// reported as notes rather than errors.
+    pub fn render(self, reports: &[FileReport], sorted: bool) -> String {
+        match self {
+            Reporter::Json => render_json(reports, sorted),
+            Reporter::Sarif => render_sarif(reports, sorted),
+            Reporter::Checkstyle => render_checkstyle(reports, sorted),
+            Reporter::Github => render_github(reports, sorted),
+        }
+    }
+}
+
+fn message(edit: &ClassListEdit, sorted: bool) -> String {
+    if sorted {
+        format!("Sorted classes to `{}`", edit.replacement)
+    } else {
+        format!("Classes are not sorted, expected `{}`", edit.replacement)
+    }
+}
+
+fn render_json(reports: &[FileReport], sorted: bool) -> String {
+    let summary = Summary::new(reports);
+
+    let files: Vec<_> = reports
+        .iter()
+        .filter(|report| !report.edits.is_empty())
+        .map(|report| {
+            let edits: Vec<_> = report
+                .edits
+                .iter()
+                .map(|edit| {
+                    json!({
+                        "line": edit.line,
+                        "column": edit.column,
+                        "endLine": edit.end_line,
+                        "endColumn": edit.end_column,
+                        "original": edit.original,
+                        "sorted": edit.replacement,
+                    })
+                })
+                .collect();
+
+            json!({ "path": report.path, "unsortedClassLists": edits })
+        })
+        .collect();
+
+    let errors: Vec<_> = reports
+        .iter()
+        .filter_map(|report| {
+            let error = report.error.as_ref()?;
+            Some(json!({ "path": report.path, "message": error }))
+        })
+        .collect();
+
+    let report = json!({
+        "sorted": sorted,
+        "summary": {
+            "filesChecked": summary.files_checked,
+            "filesWithUnsortedClasses": summary.files_unsorted,
+            "unsortedClassLists": summary.class_lists_unsorted,
+            "errors": summary.errors,
+        },
+        "files": files,
+        "errors": errors,
+    });
+
+    format!("{report:#}\n")
+}
+
+fn render_sarif(reports: &[FileReport], sorted: bool) -> String {
+    let summary = Summary::new(reports);
+    let level = if sorted { "note" } else { "error" };
+
+    let results: Vec<_> = reports
+        .iter()
+        .flat_map(|report| report.edits.iter().map(move |edit| (report, edit)))
+        .map(|(report, edit)| {
+            let region = json!({
+                "startLine": edit.line,
+                "startColumn": edit.column,
+                "endLine": edit.end_line,
+                "endColumn": edit.end_column,
+                "snippet": { "text": edit.original },
+            });
+
+            json!({
+                "ruleId": RULE_ID,
+                "level": level,
+                "message": { "text": message(edit, sorted) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": report.path },
+                        "region": region,
+                    }
+                }],
+                "fixes": [{
+                    "description": { "text": "Sort the classes" },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": report.path },
+                        "replacements": [{
+                            "deletedRegion": region,
+                            "insertedContent": { "text": edit.replacement },
+                        }],
+                    }],
+                }],
+            })
+        })
+        .collect();
+
+    let notifications: Vec<_> = reports
+        .iter()
+        .filter_map(|report| {
+            let error = report.error.as_ref()?;
+            Some(json!({
+                "level": "error",
+                "descriptor": { "id": IO_RULE_ID },
+                "message": { "text": error },
+                "locations": [{
+                    "physicalLocation": { "artifactLocation": { "uri": report.path } }
+                }],
+            }))
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rustywind",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/avencera/rustywind",
+                    "rules": [{
+                        "id": RULE_ID,
+                        "shortDescription": { "text": "Tailwind CSS classes are not sorted" },
+                    }],
+                },
+            },
+            // the columns of the edits count characters, not UTF-16 code units
+            "columnKind": "unicodeCodePoints",
+            "invocations": [{
+                "executionSuccessful": summary.errors == 0,
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": results,
+            "properties": {
+                "filesChecked": summary.files_checked,
+                "filesWithUnsortedClasses": summary.files_unsorted,
+                "unsortedClassLists": summary.class_lists_unsorted,
+                "errors": summary.errors,
+            },
+        }],
+    });
+
+    format!("{sarif:#}\n")
+}
+
+fn render_checkstyle(reports: &[FileReport], sorted: bool) -> String {
+    let severity = if sorted { "info" } else { "error" };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<checkstyle version=\"4.3\">\n");
+
+    for report in reports {
+        if report.edits.is_empty() && report.error.is_none() {
+            continue;
+        }
+
+        let _ = writeln!(xml, "  <file name=\"{}\">", xml_escape(&report.path));
+
+        if let Some(error) = &report.error {
+            let _ = writeln!(
+                xml,
+                "    <error line=\"0\" severity=\"error\" message=\"{}\" source=\"rustywind.{IO_RULE_ID}\"/>",
+                xml_escape(error)
+            );
+        }
+
+        for edit in &report.edits {
+            let _ = writeln!(
+                xml,
+                "    <error line=\"{}\" column=\"{}\" severity=\"{severity}\" message=\"{}\" source=\"rustywind.{RULE_ID}\"/>",
+                edit.line,
+                edit.column,
+                xml_escape(&message(edit, sorted))
+            );
+        }
+
+        xml.push_str("  </file>\n");
+    }
+
+    xml.push_str("</checkstyle>\n");
+    xml
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// GitHub Actions workflow commands, which show up as annotations on pull requests.
+fn render_github(reports: &[FileReport], sorted: bool) -> String {
+    let summary = Summary::new(reports);
+    let (command, title) = if sorted {
+        ("notice", "Sorted classes")
+    } else {
+        ("error", "Unsorted classes")
+    };
+
+    let mut output = String::new();
+
+    for report in reports {
+        let file = github_property(&report.path);
+
+        if let Some(error) = &report.error {
+            let _ = writeln!(
+                output,
+                "::error file={file},title=Unable to process file::{}",
+                github_message(error)
+            );
+        }
+
+        for edit in &report.edits {
+            let _ = writeln!(
+                output,
+                "::{command} file={file},line={},col={},endLine={},endColumn={},title={title}::{}",
+                edit.line,
+                edit.column,
+                edit.end_line,
+                edit.end_column,
+                github_message(&message(edit, sorted))
+            );
+        }
+    }
+
+    let _ = writeln!(
+        output,
+        "::notice title=RustyWind::{} unsorted class lists in {} of {} files, {} errors",
+        summary.class_lists_unsorted, summary.files_unsorted, summary.files_checked, summary.errors
+    );
+
+    output
+}
+
+fn github_message(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn github_property(text: &str) -> String {
+    github_message(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn reports() -> Vec<FileReport> {
+        vec![
+            FileReport {
+                path: "src/a.html".to_string(),
+                edits: vec![ClassListEdit {
+                    range: 12..20,
+                    line: 1,
+                    column: 13,
+                    end_line: 1,
+                    end_column: 21,
+                    original: "p-4 flex".to_string(),
+                    replacement: "flex p-4".to_string(),
+                    classes: vec![],
+                }],
+                error: None,
+            },
+            FileReport {
+                path: "src/sorted.html".to_string(),
+                ..FileReport::default()
+            },
+            FileReport {
+                path: "src/b&c.html".to_string(),
+                edits: vec![],
+                error: Some("stream did not contain valid UTF-8".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            Summary::new(&reports()),
+            Summary {
+                files_checked: 3,
+                files_unsorted: 1,
+                class_lists_unsorted: 1,
+                errors: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_json_report() {
+        let report: serde_json::Value =
+            serde_json::from_str(&Reporter::Json.render(&reports(), false)).unwrap();
+
+        assert_eq!(report["summary"]["unsortedClassLists"], 1);
+        assert_eq!(report["files"][0]["path"], "src/a.html");
+        assert_eq!(report["files"][0]["unsortedClassLists"][0]["column"], 13);
+        assert_eq!(
+            report["files"][0]["unsortedClassLists"][0]["sorted"],
+            "flex p-4"
+        );
+        assert_eq!(report["errors"][0]["path"], "src/b&c.html");
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let report: serde_json::Value =
+            serde_json::from_str(&Reporter::Sarif.render(&reports(), false)).unwrap();
+        let run = &report["runs"][0];
+
+        assert_eq!(report["version"], "2.1.0");
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+        assert_eq!(run["results"][0]["ruleId"], RULE_ID);
+        assert_eq!(run["results"][0]["level"], "error");
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["region"]["startColumn"],
+            13
+        );
+        assert_eq!(
+            run["results"][0]["fixes"][0]["artifactChanges"][0]["replacements"][0]["insertedContent"]
+                ["text"],
+            "flex p-4"
+        );
+        assert_eq!(run["invocations"][0]["executionSuccessful"], false);
+    }
+
+    #[test]
+    fn test_checkstyle_report() {
+        assert_eq!(
+            Reporter::Checkstyle.render(&reports(), false),
+            indoc! {r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <checkstyle version="4.3">
+                  <file name="src/a.html">
+                    <error line="1" column="13" severity="error" message="Classes are not sorted, expected `flex p-4`" source="rustywind.unsorted-classes"/>
+                  </file>
+                  <file name="src/b&amp;c.html">
+                    <error line="0" severity="error" message="stream did not contain valid UTF-8" source="rustywind.io-error"/>
+                  </file>
+                </checkstyle>
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_github_report() {
+        assert_eq!(
+            Reporter::Github.render(&reports(), true),
+            indoc! {"
+                ::notice file=src/a.html,line=1,col=13,endLine=1,endColumn=21,title=Sorted classes::Sorted classes to `flex p-4`
+                ::error file=src/b&c.html,title=Unable to process file::stream did not contain valid UTF-8
+                ::notice title=RustyWind::1 unsorted class lists in 1 of 3 files, 1 errors
+            "}
+        );
+    }
+}
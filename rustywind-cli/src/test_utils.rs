@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary project directory for tests, removed with everything in it when dropped, even
+/// when an assertion fails.
+///
+/// The project is created in a directory holding an empty `rustywind.toml` and `.prettierrc`,
+/// so looking for config files from the project stops there instead of reading any config file
+/// above the system temp directory.
+pub struct TempProject {
+    root: PathBuf,
+    path: PathBuf,
+}
+
+impl TempProject {
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "rustywind-{name}-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let path = root.join("project");
+
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(root.join("rustywind.toml"), "").unwrap();
+        fs::write(root.join(".prettierrc"), "{}").unwrap();
+
+        Self { root, path }
+    }
+
+    /// The project directory, which is empty when created.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,359 @@
+use eyre::{Context, Result};
+use notify_debouncer_full::notify::RecursiveMode;
+use notify_debouncer_full::{DebounceEventResult, new_debouncer};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use ahash::AHashMap as HashMap;
+use ahash::AHashSet as HashSet;
+
+use crate::Cli;
+use crate::config::CONFIG_FILE_NAMES;
+use crate::options::{ConfigFiles, Options, get_search_paths_from_starting_paths};
+use crate::prettier::PRETTIER_CONFIG_FILE_NAMES;
+
+/// How long a file has to stop changing before it is sorted, so that the events of a
+/// single save, or of our own write, are handled once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The files being watched, and the contents of the files this process wrote.
+struct WatchState {
+    /// The starting paths, with their canonical paths
+    starting_paths: Vec<(PathBuf, PathBuf)>,
+    search_paths: HashSet<PathBuf>,
+    /// The canonical directories of the search paths
+    search_directories: HashSet<PathBuf>,
+    /// The canonical paths of the files the options are built from
+    source_files: HashSet<PathBuf>,
+    /// Whether each file uses its nearest config file, false with `--config-file`
+    uses_nearest_config_files: bool,
+    /// A hash of the contents of each file sorted in place, to ignore the events of our
+    /// own writes
+    own_writes: HashMap<PathBuf, u64>,
+}
+
+impl WatchState {
+    fn new(options: &Options) -> Self {
+        WatchState {
+            starting_paths: options
+                .starting_paths
+                .iter()
+                .filter_map(|path| Some((path.clone(), fs::canonicalize(path).ok()?)))
+                .collect(),
+            search_paths: HashSet::new(),
+            search_directories: HashSet::new(),
+            source_files: canonical_paths(&options.source_files),
+            uses_nearest_config_files: true,
+            own_writes: HashMap::new(),
+        }
+    }
+
+    fn set_search_paths(&mut self, search_paths: impl IntoIterator<Item = PathBuf>) {
+        self.search_paths = search_paths.into_iter().collect();
+
+        let directories: HashSet<&Path> = self
+            .search_paths
+            .iter()
+            .filter_map(|path| path.parent())
+            .collect();
+        self.search_directories = directories
+            .into_iter()
+            .filter_map(|directory| fs::canonicalize(directory).ok())
+            .collect();
+    }
+
+    /// Adds the created files the walk of the starting paths would find, only walking the
+    /// directories containing them. Files ignored by the ignore rules are left out.
+    fn add_created_files(&mut self, starting_paths: &[PathBuf], created_paths: &[PathBuf]) {
+        let created_files: HashSet<PathBuf> = created_paths
+            .iter()
+            .filter(|path| {
+                self.file_path(path)
+                    .is_some_and(|file_path| !self.search_paths.contains(&file_path))
+            })
+            .filter_map(|path| fs::canonicalize(path).ok())
+            .collect();
+
+        if created_files.is_empty() {
+            return;
+        }
+
+        let search_paths = std::mem::take(&mut self.search_paths);
+        self.set_search_paths(search_paths.into_iter().chain(
+            get_search_paths_from_starting_paths(starting_paths, Some(created_files)),
+        ));
+    }
+
+    /// The path of a changed file written like the starting path it is in, the way the
+    /// files are printed on the first run. `None` for files outside of the starting paths.
+    fn file_path(&self, event_path: &Path) -> Option<PathBuf> {
+        let event_path = fs::canonicalize(event_path).ok()?;
+
+        self.starting_paths
+            .iter()
+            .find_map(|(starting_path, canonical_path)| {
+                let relative_path = event_path.strip_prefix(canonical_path).ok()?;
+
+                // a starting path that is the file itself
+                if relative_path.as_os_str().is_empty() {
+                    return Some(starting_path.clone());
+                }
+
+                Some(starting_path.join(relative_path))
+            })
+    }
+
+    /// Whether a changed file is one the options are built from: a file the options were
+    /// loaded from, or the nearest config file of the directory of a search path.
+    fn is_source_file(&self, event_path: &Path) -> bool {
+        let Ok(event_path) = fs::canonicalize(event_path) else {
+            return false;
+        };
+
+        if self.source_files.contains(&event_path) {
+            return true;
+        }
+
+        let is_config_file = event_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                CONFIG_FILE_NAMES.contains(&name) || PRETTIER_CONFIG_FILE_NAMES.contains(&name)
+            });
+
+        self.uses_nearest_config_files
+            && is_config_file
+            && event_path.parent().is_some_and(|directory| {
+                self.search_directories
+                    .iter()
+                    .any(|search_directory| search_directory.starts_with(directory))
+                    && ConfigFiles::find(directory).contains(&event_path)
+            })
+    }
+
+    /// Whether the event of a file is for the contents this process wrote to it.
+    fn is_own_write(&mut self, file_path: &Path) -> bool {
+        let Some(written) = self.own_writes.get(file_path) else {
+            return false;
+        };
+
+        let is_own_write = fs::read(file_path).is_ok_and(|contents| hash(&contents) == *written);
+        if !is_own_write {
+            self.own_writes.remove(file_path);
+        }
+
+        is_own_write
+    }
+}
+
+/// Watches the starting paths, sorting the files that change and reloading the options when
+/// a config file, the CSS file or the utility extensions change. Runs until interrupted.
+pub fn run(cli: Cli, mut options: Arc<Options>, search_paths: Vec<PathBuf>) -> Result<()> {
+    let (sender, receiver) = mpsc::channel::<DebounceEventResult>();
+    let mut debouncer =
+        new_debouncer(DEBOUNCE, None, sender).wrap_err("Error starting to watch")?;
+
+    let mut state = WatchState::new(&options);
+    state.set_search_paths(search_paths);
+    state.uses_nearest_config_files = cli.config_file.is_none();
+
+    for (starting_path, _canonical_path) in &state.starting_paths {
+        debouncer
+            .watch(starting_path, RecursiveMode::Recursive)
+            .wrap_err_with(|| format!("Error watching {}", starting_path.display()))?;
+    }
+
+    // config files above the starting paths, and sort order files outside of them
+    for source_file in &options.source_files {
+        if let Err(error) = debouncer.watch(source_file, RecursiveMode::NonRecursive) {
+            log::warn!("unable to watch {}: {error}", source_file.display());
+        }
+    }
+
+    if !options.quiet {
+        println!("\nwatching for changes, press Ctrl-C to stop");
+    }
+
+    for events in receiver {
+        let events = match events {
+            Ok(events) => events,
+            Err(errors) => {
+                for error in errors {
+                    log::warn!("watch error: {error}");
+                }
+                continue;
+            }
+        };
+
+        // reading a file is an event too, including our own reads
+        let mut changed_paths: Vec<PathBuf> = vec![];
+        let mut created_paths: Vec<PathBuf> = vec![];
+        for event in events {
+            if event.kind.is_create() {
+                created_paths.extend(
+                    event
+                        .event
+                        .paths
+                        .iter()
+                        .filter(|path| path.is_file())
+                        .cloned(),
+                );
+            }
+            if event.kind.is_create() || event.kind.is_modify() {
+                changed_paths.extend(event.event.paths.into_iter().filter(|path| path.is_file()));
+            }
+        }
+
+        let (source_files, changed_paths): (Vec<PathBuf>, Vec<PathBuf>) = changed_paths
+            .into_iter()
+            .partition(|path| state.is_source_file(path));
+
+        if !source_files.is_empty() {
+            match Options::new_from_cli(cli.clone()) {
+                Ok(mut new_options) => {
+                    state.set_search_paths(std::mem::take(&mut new_options.search_paths));
+                    state.source_files = canonical_paths(&new_options.source_files);
+                    options = Arc::new(new_options);
+
+                    if !options.quiet {
+                        println!("\nreloaded the options");
+                    }
+                }
+                Err(error) => {
+                    eprintln!("\nError reloading the options, keeping the previous ones: {error}")
+                }
+            }
+        }
+
+        let mut file_paths: Vec<PathBuf> = changed_paths
+            .iter()
+            .filter_map(|path| state.file_path(path))
+            .collect();
+        file_paths.sort();
+        file_paths.dedup();
+
+        // files created since the last walk, which may be ignored
+        state.add_created_files(&options.starting_paths, &created_paths);
+
+        for file_path in file_paths {
+            if !state.search_paths.contains(&file_path) || state.is_own_write(&file_path) {
+                continue;
+            }
+
+            let before = fs::read(&file_path).ok();
+            crate::run_on_file_path(&file_path, &options);
+
+            if let Ok(after) = fs::read(&file_path)
+                && before.as_deref() != Some(&after)
+            {
+                state.own_writes.insert(file_path, hash(&after));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn canonical_paths(paths: &[PathBuf]) -> HashSet<PathBuf> {
+    paths
+        .iter()
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .collect()
+}
+
+fn hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use clap::Parser;
+
+    #[test]
+    fn test_watch_state() {
+        let project = TempProject::new("watch");
+        let root = project.path();
+        let src = root.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("index.html"), "<a class=\"p-4 flex\"></a>").unwrap();
+        fs::write(root.join("tailwind.css"), ".flex{display:flex}").unwrap();
+
+        let root_path = root.to_string_lossy().into_owned();
+        let css_path = root.join("tailwind.css").to_string_lossy().into_owned();
+        let options = Options::new_from_cli(Cli::parse_from([
+            "rustywind",
+            "--write",
+            "--output-css-file",
+            &css_path,
+            &root_path,
+        ]))
+        .unwrap();
+        let mut state = WatchState::new(&options);
+        state.set_search_paths(options.search_paths.clone());
+
+        let file_path = state
+            .file_path(&src.join("index.html").canonicalize().unwrap())
+            .unwrap();
+        assert_eq!(file_path, root.join("src").join("index.html"));
+        assert_eq!(state.file_path(root.parent().unwrap()), None);
+
+        assert!(state.is_source_file(&root.join("tailwind.css")));
+        fs::write(src.join("rustywind.toml"), "").unwrap();
+        assert!(state.is_source_file(&src.join("rustywind.toml")));
+        assert!(!state.is_source_file(&file_path));
+
+        // config files that are not the nearest one of a directory with files to sort
+        fs::write(src.join(".rustywindrc.json"), "{}").unwrap();
+        assert!(!state.is_source_file(&src.join(".rustywindrc.json")));
+        fs::write(src.join("package.json"), "{}").unwrap();
+        assert!(!state.is_source_file(&src.join("package.json")));
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("empty").join("rustywind.toml"), "").unwrap();
+        assert!(!state.is_source_file(&root.join("empty").join("rustywind.toml")));
+
+        // created files are added unless the ignore rules leave them out
+        fs::write(root.join(".ignore"), "build/\n").unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        let created_paths = [
+            src.join("new.html"),
+            root.join("build").join("index.html"),
+            root.join(".git").join("index"),
+        ];
+        for path in &created_paths {
+            fs::write(path, "<a class=\"p-4 flex\"></a>").unwrap();
+        }
+        state.add_created_files(&options.starting_paths, &created_paths);
+        assert!(state.search_paths.contains(&src.join("new.html")));
+        assert!(state.search_paths.contains(&file_path));
+        assert!(
+            !state
+                .search_paths
+                .contains(&root.join("build").join("index.html"))
+        );
+        assert!(
+            !state
+                .search_paths
+                .contains(&root.join(".git").join("index"))
+        );
+
+        fs::write(&file_path, "<a class=\"flex p-4\"></a>").unwrap();
+        state
+            .own_writes
+            .insert(file_path.clone(), hash(b"<a class=\"flex p-4\"></a>"));
+        assert!(state.is_own_write(&file_path));
+
+        // a save after our own write is sorted again
+        fs::write(&file_path, "<a class=\"p-2 flex\"></a>").unwrap();
+        assert!(!state.is_own_write(&file_path));
+        assert!(!state.own_writes.contains_key(&file_path));
+    }
+}
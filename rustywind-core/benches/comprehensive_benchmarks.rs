@@ -163,10 +163,7 @@ fn bench_custom_sorter(c: &mut Criterion) {
 
     for size in [10, 50, 100, 500].iter() {
         let classes_str = generate_realistic_classes(*size).join(" ");
-        let app = RustyWind {
-            sorter: Sorter::CustomSorter(custom_map.clone()),
-            ..Default::default()
-        };
+        let app = RustyWind::default().with_sorter(Sorter::CustomSorter(custom_map.clone()));
 
         group.throughput(Throughput::Elements(*size as u64));
         group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, _| {
@@ -1,35 +1,83 @@
 use std::borrow::Cow;
 
 use crate::{
+    class_parser::parse_class,
     class_wrapping::ClassWrapping,
-    consts::{VARIANT_SEARCHER, VARIANTS},
+    diagnostics::{
+        UnknownAllowlist, UnknownClass, UnknownKind, is_known_variant_with, suggest_utilities,
+        suggest_variants_with,
+    },
+    edit::{ClassEdit, ClassListEdit, SortRange, apply_edits, overlaps},
+    explain::{Explanation, explain_classes},
     hybrid_sorter::HybridSorter,
-    sorter::{FinderRegex, Sorter},
-    tailwind_prefix::{normalize_tailwind_prefix, normalize_tailwind_prefix_value},
+    parser::{
+        ClassOccurrence,
+        regex::{
+            LineCursor, find_class_spans, find_occurrences, is_ellipsis_placeholder,
+            substring_offset, unwrap_wrapped_classes,
+        },
+    },
+    sorter::{FinderRegex, FinderRegexSet, Rank, Sorter},
+    tailwind_prefix::{normalize_tailwind_prefix, normalize_tailwind_prefix_value, utility_start},
+    tailwind_v3,
+    tailwind_version::TailwindVersion,
+    utility_extensions::UtilityExtensions,
+    variant_order::VariantOrder,
 };
 use ahash::{AHashMap as HashMap, AHashSet as HashSet};
-use aho_corasick::{Anchored, Input};
-use regex::Captures;
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
 use std::sync::{Arc, LazyLock, RwLock};
 
 /// Global instance of the HybridSorter for pattern-based sorting.
-static PATTERN_SORTER: LazyLock<HybridSorter> = LazyLock::new(HybridSorter::new);
-static PREFIXED_PATTERN_SORTERS: LazyLock<RwLock<HashMap<String, Arc<HybridSorter>>>> =
+pub(crate) static PATTERN_SORTER: LazyLock<HybridSorter> = LazyLock::new(HybridSorter::new);
+/// Global instance of the HybridSorter following the Tailwind v3 class order.
+static V3_PATTERN_SORTER: LazyLock<HybridSorter> =
+    LazyLock::new(|| HybridSorter::new().with_tailwind_version(TailwindVersion::V3));
+/// Pattern sorters using a Tailwind prefix, keyed by Tailwind version and prefix.
+type PrefixedPatternSorters = HashMap<(TailwindVersion, String), Arc<HybridSorter>>;
+static PREFIXED_PATTERN_SORTERS: LazyLock<RwLock<PrefixedPatternSorters>> =
     LazyLock::new(|| RwLock::new(HashMap::new()));
 
-struct SortCandidate<'a> {
-    original: &'a str,
-    lookup: Cow<'a, str>,
+/// A pattern sorter using utility extensions.
+#[derive(Clone)]
+struct ExtendedPatternSorter(Arc<HybridSorter>);
+
+impl std::fmt::Debug for ExtendedPatternSorter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ExtendedPatternSorter")
+            .finish_non_exhaustive()
+    }
 }
 
 /// The options to pass to the sorter.
+///
+/// Build it from [RustyWind::default] with the `with_*` methods:
+///
+/// ```
+/// use rustywind_core::RustyWind;
+/// use rustywind_core::tailwind_version::TailwindVersion;
+///
+/// let rustywind = RustyWind::default()
+///     .with_tailwind_prefix(Some("tw".to_string()))
+///     .with_tailwind_version(TailwindVersion::V3);
+///
+/// assert_eq!(rustywind.sort_classes("tw-p-4 tw-m-4"), "tw-m-4 tw-p-4");
+/// ```
 #[derive(Debug, Clone)]
 pub struct RustyWind {
-    pub regex: FinderRegex,
-    pub sorter: Sorter,
-    pub allow_duplicates: bool,
-    pub class_wrapping: ClassWrapping,
-    pub tailwind_prefix: Option<String>,
+    regex: FinderRegex,
+    sorter: Sorter,
+    allow_duplicates: bool,
+    class_wrapping: ClassWrapping,
+    tailwind_prefix: Option<String>,
+    utility_extensions: Option<Arc<UtilityExtensions>>,
+    tailwind_version: TailwindVersion,
+    regex_set: Option<Arc<FinderRegexSet>>,
+    preserve_whitespace: bool,
+    /// The pattern sorter for the utility extensions, built by the `with_*` methods.
+    extended_pattern_sorter: Option<ExtendedPatternSorter>,
 }
 
 impl Default for RustyWind {
@@ -40,6 +88,11 @@ impl Default for RustyWind {
             allow_duplicates: false,
             class_wrapping: ClassWrapping::NoWrapping,
             tailwind_prefix: None,
+            utility_extensions: None,
+            tailwind_version: TailwindVersion::V4,
+            regex_set: None,
+            preserve_whitespace: false,
+            extended_pattern_sorter: None,
         }
     }
 }
@@ -67,202 +120,647 @@ impl RustyWind {
             allow_duplicates,
             class_wrapping,
             tailwind_prefix,
+            utility_extensions: None,
+            tailwind_version: TailwindVersion::V4,
+            regex_set: None,
+            preserve_whitespace: false,
+            extended_pattern_sorter: None,
         }
     }
 
+    /// Sets the regex finding the class lists.
+    pub fn with_regex(mut self, regex: FinderRegex) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    /// Sets the sorter ordering the classes.
+    pub fn with_sorter(mut self, sorter: Sorter) -> Self {
+        self.sorter = sorter;
+        self
+    }
+
+    /// Sets whether duplicate classes are kept.
+    pub fn with_allow_duplicates(mut self, allow_duplicates: bool) -> Self {
+        self.allow_duplicates = allow_duplicates;
+        self
+    }
+
+    /// Sets how individual classes are wrapped.
+    pub fn with_class_wrapping(mut self, class_wrapping: ClassWrapping) -> Self {
+        self.class_wrapping = class_wrapping;
+        self
+    }
+
+    /// Sets the Tailwind prefix of the classes, e.g. `tw` for `tw:` or `tw-` classes.
+    pub fn with_tailwind_prefix(mut self, tailwind_prefix: Option<String>) -> Self {
+        self.tailwind_prefix = tailwind_prefix;
+        self.with_extended_pattern_sorter()
+    }
+
+    /// Sets the utility extensions layered on top of the built-in tables of the pattern
+    /// sorter.
+    pub fn with_utility_extensions(
+        mut self,
+        utility_extensions: Option<Arc<UtilityExtensions>>,
+    ) -> Self {
+        self.utility_extensions = utility_extensions;
+        self.with_extended_pattern_sorter()
+    }
+
+    /// Sets the Tailwind version whose class order the pattern sorter follows.
+    pub fn with_tailwind_version(mut self, tailwind_version: TailwindVersion) -> Self {
+        self.tailwind_version = tailwind_version;
+        self.with_extended_pattern_sorter()
+    }
+
+    /// Sets the extraction patterns per file type.
+    pub fn with_regex_set(mut self, regex_set: Option<Arc<FinderRegexSet>>) -> Self {
+        self.regex_set = regex_set;
+        self
+    }
+
+    /// Sets whether the whitespace around and between unwrapped classes is kept.
+    pub fn with_preserve_whitespace(mut self, preserve_whitespace: bool) -> Self {
+        self.preserve_whitespace = preserve_whitespace;
+        self
+    }
+
+    /// Builds the pattern sorter for the utility extensions, once for all the classes
+    /// sorted with these options.
+    fn with_extended_pattern_sorter(mut self) -> Self {
+        self.extended_pattern_sorter = self.utility_extensions.as_ref().map(|extensions| {
+            let sorter = HybridSorter::new_with_tailwind_prefix(self.normalized_tailwind_prefix())
+                .with_extensions(Some(Arc::clone(extensions)))
+                .with_tailwind_version(self.tailwind_version);
+            ExtendedPatternSorter(Arc::new(sorter))
+        });
+        self
+    }
+
+    /// The regex finding the class lists.
+    pub fn regex(&self) -> &FinderRegex {
+        &self.regex
+    }
+
+    /// The sorter ordering the classes.
+    pub fn sorter(&self) -> &Sorter {
+        &self.sorter
+    }
+
+    /// Whether duplicate classes are kept.
+    pub fn allow_duplicates(&self) -> bool {
+        self.allow_duplicates
+    }
+
+    /// How individual classes are wrapped.
+    pub fn class_wrapping(&self) -> ClassWrapping {
+        self.class_wrapping
+    }
+
+    /// The Tailwind prefix of the classes.
+    pub fn tailwind_prefix(&self) -> Option<&str> {
+        self.tailwind_prefix.as_deref()
+    }
+
+    /// The utility extensions layered on top of the built-in tables of the pattern sorter.
+    pub fn utility_extensions(&self) -> Option<&Arc<UtilityExtensions>> {
+        self.utility_extensions.as_ref()
+    }
+
+    /// The Tailwind version whose class order the pattern sorter follows.
+    pub fn tailwind_version(&self) -> TailwindVersion {
+        self.tailwind_version
+    }
+
+    /// The extraction patterns per file type, used by the `*_for_path` methods instead of
+    /// the regex and class wrapping for the files they match.
+    pub fn regex_set(&self) -> Option<&Arc<FinderRegexSet>> {
+        self.regex_set.as_ref()
+    }
+
+    /// Whether the whitespace around and between unwrapped classes is kept instead of
+    /// joining them with single spaces.
+    pub fn preserve_whitespace(&self) -> bool {
+        self.preserve_whitespace
+    }
+
+    fn normalized_tailwind_prefix(&self) -> Option<&str> {
+        self.tailwind_prefix
+            .as_deref()
+            .and_then(normalize_tailwind_prefix_value)
+    }
+
     /// Checks if the file contents have any classes.
     pub fn has_classes(&self, file_contents: &str) -> bool {
-        self.regex.is_match(file_contents)
+        has_matches(&self.patterns_for(None), file_contents)
+    }
+
+    /// Checks if the file contents have any classes, using the extraction patterns for
+    /// the file type of `path`.
+    pub fn has_classes_for_path(&self, path: &Path, file_contents: &str) -> bool {
+        has_matches(&self.patterns_for(Some(path)), file_contents)
     }
 
     /// Sorts the classes in the file contents.
+    ///
+    /// Each capture group of the regex named `(?P<name>...)` holds classes sorted on their
+    /// own. A regex without named groups uses its first capture group that matched.
     pub fn sort_file_contents<'a>(&self, file_contents: &'a str) -> Cow<'a, str> {
-        self.regex.replace_all(file_contents, |caps: &Captures| {
-            let classes = caps
-                .get(1)
-                .or_else(|| caps.get(2))
-                .expect("class extractor regex must include a capture group")
-                .as_str();
-            let sorted_classes = self.sort_classes(classes);
-            caps[0].replace(classes, &sorted_classes)
-        })
+        self.sort_class_spans(&self.patterns_for(None), file_contents, None)
+    }
+
+    /// Sorts the classes in the file contents, using the extraction patterns for the file
+    /// type of `path`.
+    ///
+    /// When the classes found by the patterns overlap, the ones starting first are sorted,
+    /// and for the same start the ones of the earlier pattern.
+    pub fn sort_file_contents_for_path<'a>(
+        &self,
+        path: &Path,
+        file_contents: &'a str,
+    ) -> Cow<'a, str> {
+        self.sort_class_spans(&self.patterns_for(Some(path)), file_contents, None)
+    }
+
+    /// Sorts the class lists overlapping the range, leaving the rest of the file contents
+    /// byte for byte as they are.
+    pub fn sort_file_contents_in_range<'a>(
+        &self,
+        file_contents: &'a str,
+        range: &SortRange,
+    ) -> Cow<'a, str> {
+        let range = range.byte_range(file_contents);
+        self.sort_class_spans(&self.patterns_for(None), file_contents, Some(&range))
+    }
+
+    /// Like [RustyWind::sort_file_contents_in_range], using the extraction patterns for the
+    /// file type of `path`.
+    pub fn sort_file_contents_in_range_for_path<'a>(
+        &self,
+        path: &Path,
+        file_contents: &'a str,
+        range: &SortRange,
+    ) -> Cow<'a, str> {
+        let range = range.byte_range(file_contents);
+        self.sort_class_spans(&self.patterns_for(Some(path)), file_contents, Some(&range))
+    }
+
+    fn sort_class_spans<'a>(
+        &self,
+        patterns: &[(&Regex, ClassWrapping)],
+        file_contents: &'a str,
+        range: Option<&Range<usize>>,
+    ) -> Cow<'a, str> {
+        let edits = self.find_class_list_edits(patterns, file_contents, range, false);
+        apply_edits(file_contents, &edits)
+    }
+
+    /// Finds the class lists that sorting changes, in the order they appear in the file
+    /// contents, with what happens to each of their classes.
+    ///
+    /// [RustyWind::sort_file_contents] applies the same edits.
+    pub fn analyze(&self, file_contents: &str) -> Vec<ClassListEdit> {
+        self.find_class_list_edits(&self.patterns_for(None), file_contents, None, true)
+    }
+
+    /// Like [RustyWind::analyze], using the extraction patterns for the file type of `path`.
+    pub fn analyze_for_path(&self, path: &Path, file_contents: &str) -> Vec<ClassListEdit> {
+        self.find_class_list_edits(&self.patterns_for(Some(path)), file_contents, None, true)
+    }
+
+    /// Like [RustyWind::analyze], only for the class lists overlapping the range.
+    pub fn analyze_in_range(&self, file_contents: &str, range: &SortRange) -> Vec<ClassListEdit> {
+        let range = range.byte_range(file_contents);
+        self.find_class_list_edits(&self.patterns_for(None), file_contents, Some(&range), true)
+    }
+
+    /// Like [RustyWind::analyze_in_range], using the extraction patterns for the file type
+    /// of `path`.
+    pub fn analyze_in_range_for_path(
+        &self,
+        path: &Path,
+        file_contents: &str,
+        range: &SortRange,
+    ) -> Vec<ClassListEdit> {
+        let range = range.byte_range(file_contents);
+        let patterns = self.patterns_for(Some(path));
+        self.find_class_list_edits(&patterns, file_contents, Some(&range), true)
+    }
+
+    /// The edits of the class lists sorting changes, of the class lists overlapping `range`
+    /// when there is one. The classes of each edit are only described `with_classes`, which
+    /// is slower.
+    fn find_class_list_edits(
+        &self,
+        patterns: &[(&Regex, ClassWrapping)],
+        file_contents: &str,
+        range: Option<&Range<usize>>,
+        with_classes: bool,
+    ) -> Vec<ClassListEdit> {
+        let mut cursor = LineCursor::default();
+
+        find_class_spans(patterns, file_contents)
+            .into_iter()
+            .filter(|span| range.is_none_or(|range| overlaps(&span.classes.range(), range)))
+            .filter_map(|span| {
+                let original = span.classes.as_str();
+                let (extracted, kept, replacement) =
+                    self.sort_class_list(original, span.class_wrapping);
+
+                if replacement == original {
+                    return None;
+                }
+
+                let (line, column) = cursor.line_column(file_contents, span.classes.start());
+                let (end_line, end_column) = cursor.line_column(file_contents, span.classes.end());
+
+                let classes = if with_classes {
+                    self.class_edits(original, span.classes.start(), &extracted, &kept)
+                } else {
+                    vec![]
+                };
+
+                Some(ClassListEdit {
+                    range: span.classes.range(),
+                    line,
+                    column,
+                    end_line,
+                    end_column,
+                    original: original.to_string(),
+                    replacement,
+                    classes,
+                })
+            })
+            .collect()
+    }
+
+    /// Describes each class of a class list, given the classes in the source order and the
+    /// classes kept in the sorted order, both subslices of `class_string`.
+    fn class_edits(
+        &self,
+        class_string: &str,
+        offset: usize,
+        extracted: &[&str],
+        kept: &[&str],
+    ) -> Vec<ClassEdit> {
+        let mut new_indexes: Vec<Option<usize>> = vec![None; extracted.len()];
+
+        for (new_index, class) in kept.iter().enumerate() {
+            // the same subslice, or an equal class for sorters that return other strings
+            let original_index = (0..extracted.len())
+                .filter(|index| new_indexes[*index].is_none())
+                .find(|index| std::ptr::eq(extracted[*index], *class))
+                .or_else(|| {
+                    (0..extracted.len())
+                        .filter(|index| new_indexes[*index].is_none())
+                        .find(|index| extracted[*index] == *class)
+                });
+
+            if let Some(original_index) = original_index {
+                new_indexes[original_index] = Some(new_index);
+            }
+        }
+
+        let variant_order = self.variant_order();
+        let mut kept_index = 0;
+        extracted
+            .iter()
+            .zip(new_indexes)
+            .map(|(class, new_index)| {
+                let start = offset + substring_offset(class_string, class);
+                let moved = new_index.is_some_and(|new_index| new_index != kept_index);
+                if new_index.is_some() {
+                    kept_index += 1;
+                }
+
+                ClassEdit {
+                    class: class.to_string(),
+                    range: start..start + class.len(),
+                    new_index,
+                    moved,
+                    duplicate_removed: new_index.is_none(),
+                    unknown: self.is_unknown_class(class, &variant_order),
+                }
+            })
+            .collect()
+    }
+
+    /// Finds every class of the class lists in the file contents, with its location and
+    /// the class list containing it. See [crate::parser] to aggregate them.
+    pub fn find_class_occurrences(&self, file_contents: &str) -> Vec<ClassOccurrence> {
+        find_occurrences(&self.patterns_for(None), file_contents)
+    }
+
+    /// Like [RustyWind::find_class_occurrences], using the extraction patterns for the file
+    /// type of `path`.
+    pub fn find_class_occurrences_for_path(
+        &self,
+        path: &Path,
+        file_contents: &str,
+    ) -> Vec<ClassOccurrence> {
+        find_occurrences(&self.patterns_for(Some(path)), file_contents)
+    }
+
+    /// The patterns for the file type of `path`, or for files without an extraction rule:
+    /// the fallback patterns of the regex set, or `regex` and `class_wrapping`.
+    fn patterns_for(&self, path: Option<&Path>) -> Vec<(&Regex, ClassWrapping)> {
+        let patterns = self.regex_set.as_deref().and_then(|regex_set| match path {
+            Some(path) => regex_set.patterns_for(path),
+            None => regex_set.fallback_patterns(),
+        });
+
+        match patterns {
+            Some(patterns) => patterns
+                .iter()
+                .map(|pattern| (&*pattern.regex, pattern.class_wrapping))
+                .collect(),
+            None => vec![(&*self.regex, self.class_wrapping)],
+        }
     }
 
     /// Given a [&str] of whitespace-separated classes, returns a [String] of sorted classes.
     /// Does not preserve whitespace.
     pub fn sort_classes(&self, class_string: &str) -> String {
-        let extracted_classes = self.unwrap_wrapped_classes(class_string);
+        self.sort_wrapped_classes(class_string, self.class_wrapping)
+    }
+
+    fn sort_wrapped_classes(&self, class_string: &str, class_wrapping: ClassWrapping) -> String {
+        self.sort_class_list(class_string, class_wrapping).2
+    }
+
+    /// Sorts a class list, returning the classes in the source order, the classes kept in
+    /// the sorted order and the sorted class list.
+    fn sort_class_list<'a>(
+        &self,
+        class_string: &'a str,
+        class_wrapping: ClassWrapping,
+    ) -> (Vec<&'a str>, Vec<&'a str>, String) {
+        let extracted_classes = unwrap_wrapped_classes(class_string, class_wrapping);
 
-        let mut sorted = self.sort_classes_vec(extracted_classes.into_iter());
+        let mut sorted = self.sort_classes_vec(extracted_classes.iter().copied());
 
         if !self.allow_duplicates {
             deduplicate_classes(&mut sorted);
         }
 
-        self.rewrap_wrapped_classes(sorted)
+        let sorted_string =
+            if self.preserve_whitespace && matches!(class_wrapping, ClassWrapping::NoWrapping) {
+                join_with_original_whitespace(class_string, &extracted_classes, sorted.clone())
+            } else {
+                rewrap_wrapped_classes(sorted.clone(), class_wrapping)
+            };
+
+        (extracted_classes, sorted, sorted_string)
     }
 
-    fn unwrap_wrapped_classes<'a>(&self, class_string: &'a str) -> Vec<&'a str> {
-        match self.class_wrapping {
-            ClassWrapping::NoWrapping => split_class_tokens(class_string),
-            ClassWrapping::CommaSingleQuotes => class_string
-                .split(',')
-                .flat_map(split_class_tokens)
-                .map(|class| class.trim_matches('\''))
-                .collect(),
-            ClassWrapping::CommaDoubleQuotes => class_string
-                .split(',')
-                .flat_map(split_class_tokens)
-                .map(|class| class.trim_matches('"'))
-                .collect(),
-        }
+    /// Given a [&str] of whitespace-separated classes, explains the order the pattern
+    /// sorter puts them in. Wrapped classes are unwrapped first.
+    ///
+    /// The explanation always describes the pattern sorter, even when a custom sort
+    /// order is configured.
+    pub fn explain_classes(&self, class_string: &str) -> Explanation {
+        let classes = self.unwrap_wrapped_classes(class_string);
+        self.with_pattern_sorter(|sorter| explain_classes(sorter, &classes))
     }
 
-    fn rewrap_wrapped_classes(&self, classes: Vec<&str>) -> String {
-        match self.class_wrapping {
-            ClassWrapping::NoWrapping => classes.join(" "),
-            ClassWrapping::CommaSingleQuotes => classes
-                .iter()
-                .map(|class| format!("'{}'", class))
-                .collect::<Vec<String>>()
-                .join(", "),
-            ClassWrapping::CommaDoubleQuotes => classes
-                .iter()
-                .map(|class| format!("\"{}\"", class))
-                .collect::<Vec<String>>()
-                .join(", "),
-        }
+    /// The rank of a class in the sort order, or `None` if the sorter doesn't know the
+    /// class. A lower rank sorts first. See [Sorter::rank], this uses the pattern sorter
+    /// matching the Tailwind version, prefix and utility extensions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::RustyWind;
+    /// use rustywind_core::sorter::{Rank, Sorter};
+    ///
+    /// let rustywind = RustyWind::default();
+    /// assert!(rustywind.rank("m-4") < rustywind.rank("p-4"));
+    /// assert_eq!(rustywind.rank("btn"), None);
+    ///
+    /// let rustywind = rustywind.with_sorter(
+    ///     Sorter::new([("btn".to_string(), 0)].into_iter().collect()).with_pattern_fallback(),
+    /// );
+    /// assert_eq!(rustywind.rank("btn"), Some(Rank::Index(0)));
+    /// assert!(rustywind.rank("btn") < rustywind.rank("p-4"));
+    /// ```
+    pub fn rank(&self, class: &str) -> Option<Rank> {
+        self.with_pattern_sorter(|pattern_sorter| {
+            self.sorter.with_class_sorter(
+                self.tailwind_prefix.as_deref(),
+                pattern_sorter,
+                |sorter| sorter.rank(class),
+            )
+        })
     }
 
-    fn sort_classes_vec<'a>(&self, classes: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
-        // use pattern-based sorting if PatternSorter is selected
-        if matches!(self.sorter, Sorter::PatternSorter) {
-            let classes_vec: Vec<&str> = classes.collect();
-            if let Some(tailwind_prefix) = self
-                .tailwind_prefix
-                .as_deref()
-                .and_then(normalize_tailwind_prefix_value)
-            {
-                return prefixed_pattern_sorter(tailwind_prefix).sort_classes(&classes_vec);
-            }
-            return PATTERN_SORTER.sort_classes(&classes_vec);
-        }
+    /// Finds the classes in the file contents that the pattern sorter does not recognize.
+    ///
+    /// A class is reported once for each unknown variant it uses, and once more if its
+    /// utility is unknown. Classes, utilities and variants matched by the allowlist
+    /// are skipped.
+    pub fn find_unknown_classes(
+        &self,
+        file_contents: &str,
+        allowlist: &UnknownAllowlist,
+    ) -> Vec<UnknownClass> {
+        self.find_unknown_class_spans(&self.patterns_for(None), file_contents, allowlist)
+    }
 
-        // otherwise, use the old HashMap-based approach
-        let candidates = classes.map(|class| SortCandidate {
-            original: class,
-            lookup: normalize_tailwind_prefix(class, self.tailwind_prefix.as_deref()),
-        });
+    /// Like [RustyWind::find_unknown_classes], using the extraction patterns for the file
+    /// type of `path`.
+    pub fn find_unknown_classes_for_path(
+        &self,
+        path: &Path,
+        file_contents: &str,
+        allowlist: &UnknownAllowlist,
+    ) -> Vec<UnknownClass> {
+        self.find_unknown_class_spans(&self.patterns_for(Some(path)), file_contents, allowlist)
+    }
 
-        let mut tailwind_classes: Vec<(&str, &usize)> = vec![];
-        let mut custom_classes: Vec<&str> = vec![];
-        let mut variants: HashMap<&str, Vec<SortCandidate>> = HashMap::new();
-
-        for candidate in candidates {
-            match self
-                .sorter
-                .get(candidate.original)
-                .or_else(|| self.sorter.get(candidate.lookup.as_ref()))
-            {
-                Some(size) => tailwind_classes.push((candidate.original, size)),
-                None => {
-                    let input = Input::new(candidate.lookup.as_ref()).anchored(Anchored::Yes);
-                    match VARIANT_SEARCHER.find(input) {
-                        Some(prefix_match) => {
-                            let prefix = VARIANTS[prefix_match.pattern()];
-                            variants.entry(prefix).or_default().push(candidate)
-                        }
-                        None => custom_classes.push(candidate.original),
-                    }
+    fn find_unknown_class_spans(
+        &self,
+        patterns: &[(&Regex, ClassWrapping)],
+        file_contents: &str,
+        allowlist: &UnknownAllowlist,
+    ) -> Vec<UnknownClass> {
+        let mut unknown_classes = vec![];
+        let mut cursor = LineCursor::default();
+        let variant_order = self.variant_order();
+
+        for span in find_class_spans(patterns, file_contents) {
+            let classes = span.classes;
+
+            for class in unwrap_wrapped_classes(classes.as_str(), span.class_wrapping) {
+                if is_ellipsis_placeholder(class) || allowlist.allows(class) {
+                    continue;
                 }
+
+                let offset = classes.start() + substring_offset(classes.as_str(), class);
+                let (line, column) = cursor.line_column(file_contents, offset);
+
+                unknown_classes.extend(
+                    self.diagnose_class(class, allowlist, &variant_order)
+                        .into_iter()
+                        .map(|(kind, suggestions)| UnknownClass {
+                            class: class.to_string(),
+                            kind,
+                            offset,
+                            line,
+                            column,
+                            suggestions,
+                        }),
+                );
             }
         }
 
-        tailwind_classes.sort_by_key(|&(_class, class_placement)| class_placement);
-
-        let sorted_tailwind_classes: Vec<&str> = tailwind_classes
-            .iter()
-            .map(|(class, _index)| *class)
-            .collect();
-
-        let mut sorted_variant_classes = vec![];
+        unknown_classes
+    }
 
-        for key in VARIANTS.iter() {
-            let (mut sorted_classes, new_custom_classes) = self.sort_variant_classes(
-                variants.remove(key).unwrap_or_default(),
-                custom_classes,
-                key.len() + 1,
-            );
+    fn diagnose_class(
+        &self,
+        class: &str,
+        allowlist: &UnknownAllowlist,
+        variant_order: &VariantOrder,
+    ) -> Vec<(UnknownKind, Vec<String>)> {
+        self.diagnose_class_with(class, allowlist, variant_order, true)
+    }
 
-            sorted_variant_classes.append(&mut sorted_classes);
-            custom_classes = new_custom_classes
-        }
+    /// Whether the pattern sorter does not recognize the class or one of its variants.
+    fn is_unknown_class(&self, class: &str, variant_order: &VariantOrder) -> bool {
+        !is_ellipsis_placeholder(class)
+            && !self
+                .diagnose_class_with(class, &UnknownAllowlist::default(), variant_order, false)
+                .is_empty()
+    }
 
-        [
-            &sorted_tailwind_classes[..],
-            &sorted_variant_classes[..],
-            &custom_classes[..],
-        ]
-        .concat()
+    /// The variant order knowing the variants of the plugins enabled in the utility
+    /// extensions.
+    fn variant_order(&self) -> VariantOrder<'_> {
+        VariantOrder::new(self.utility_extensions.as_deref())
     }
 
-    fn sort_variant_classes<'a>(
+    fn diagnose_class_with(
         &self,
-        classes: Vec<SortCandidate<'a>>,
-        mut custom_classes: Vec<&'a str>,
-        class_after: usize,
-    ) -> (Vec<&'a str>, Vec<&'a str>) {
-        let mut tailwind_classes = Vec::with_capacity(classes.len());
-
-        for candidate in classes {
-            let normalized_remainder = candidate.lookup.get(class_after..);
-            let v4_original_remainder = self
-                .tailwind_prefix
-                .as_deref()
-                .and_then(normalize_tailwind_prefix_value)
-                .and_then(|prefix| {
-                    candidate
-                        .original
-                        .strip_prefix(prefix)
-                        .and_then(|rest| rest.strip_prefix(':'))?;
-                    normalized_remainder
-                        .map(|normalized_remainder| format!("{prefix}:{normalized_remainder}"))
-                });
+        class: &str,
+        allowlist: &UnknownAllowlist,
+        variant_order: &VariantOrder,
+        suggest: bool,
+    ) -> Vec<(UnknownKind, Vec<String>)> {
+        let lookup = normalize_tailwind_prefix(class, self.tailwind_prefix.as_deref());
+        let Some(parsed) = parse_class(&lookup) else {
+            return vec![];
+        };
 
-            match candidate
-                .original
-                .get(class_after..)
-                .and_then(|class| self.sorter.get(class))
-                .or_else(|| {
-                    v4_original_remainder
-                        .as_deref()
-                        .and_then(|class| self.sorter.get(class))
-                })
-                .or_else(|| normalized_remainder.and_then(|class| self.sorter.get(class)))
-            {
-                Some(class_placement) => {
-                    tailwind_classes.push((candidate.original, class_placement))
+        let mut problems = vec![];
+
+        for variant in parsed.variants.iter().rev() {
+            let known = match self.tailwind_version {
+                TailwindVersion::V3 => {
+                    variant.starts_with('[') || tailwind_v3::get_variant_index(variant).is_some()
                 }
-                None => custom_classes.push(candidate.original),
+                TailwindVersion::V4 => is_known_variant_with(variant, variant_order),
+            };
+
+            if known || allowlist.allows(variant) {
+                continue;
             }
+
+            let suggestions = if suggest {
+                suggest_variants_with(variant, variant_order, self.tailwind_version)
+            } else {
+                Vec::new()
+            };
+            let suggestions = suggestions
+                .into_iter()
+                .map(|suggestion| {
+                    class.replacen(&format!("{variant}:"), &format!("{suggestion}:"), 1)
+                })
+                .collect();
+
+            problems.push((UnknownKind::Variant(variant.to_string()), suggestions));
         }
 
-        tailwind_classes.sort_by_key(|&(_class, class_placement)| class_placement);
+        let utility_class = &lookup[utility_start(&lookup)..];
+        let utility = utility_class.trim_start_matches('!').trim_end_matches('!');
 
-        let sorted_classes = tailwind_classes
-            .iter()
-            .map(|(class, _index)| *class)
-            .collect();
+        // v3 generates nothing for classes with unknown variants, those are reported above
+        let key_class = match self.tailwind_version {
+            TailwindVersion::V3 => utility_class,
+            TailwindVersion::V4 => lookup.as_ref(),
+        };
+
+        if !allowlist.allows(utility)
+            && self.with_pattern_sorter(|sorter| sorter.get_sort_key(key_class).is_none())
+        {
+            let utility_offset = class.rfind(utility);
+            let suggestions = if suggest {
+                suggest_utilities(utility)
+            } else {
+                Vec::new()
+            };
+            let suggestions = suggestions
+                .into_iter()
+                .map(|suggestion| match utility_offset {
+                    Some(offset) => {
+                        let mut suggested_class = class.to_string();
+                        suggested_class.replace_range(offset..offset + utility.len(), &suggestion);
+                        suggested_class
+                    }
+                    None => suggestion,
+                })
+                .collect();
+
+            problems.push((UnknownKind::Utility, suggestions));
+        }
+
+        problems
+    }
+
+    fn unwrap_wrapped_classes<'a>(&self, class_string: &'a str) -> Vec<&'a str> {
+        unwrap_wrapped_classes(class_string, self.class_wrapping)
+    }
+
+    fn sort_classes_vec<'a>(&self, classes: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+        let classes: Vec<&str> = classes.collect();
+
+        self.with_pattern_sorter(|pattern_sorter| {
+            self.sorter.with_class_sorter(
+                self.tailwind_prefix.as_deref(),
+                pattern_sorter,
+                |sorter| sorter.sort_classes(&classes),
+            )
+        })
+    }
 
-        (sorted_classes, custom_classes)
+    /// Runs `f` with the pattern sorter matching this instance's Tailwind prefix.
+    fn with_pattern_sorter<R>(&self, f: impl FnOnce(&HybridSorter) -> R) -> R {
+        let tailwind_prefix = self.normalized_tailwind_prefix();
+        let tailwind_version = self.tailwind_version;
+
+        match (tailwind_prefix, &self.extended_pattern_sorter) {
+            (_, Some(ExtendedPatternSorter(sorter))) => f(sorter),
+            (Some(tailwind_prefix), None) => {
+                f(&prefixed_pattern_sorter(tailwind_version, tailwind_prefix))
+            }
+            (None, None) => match tailwind_version {
+                TailwindVersion::V3 => f(&V3_PATTERN_SORTER),
+                TailwindVersion::V4 => f(&PATTERN_SORTER),
+            },
+        }
     }
 }
 
-fn prefixed_pattern_sorter(tailwind_prefix: &str) -> Arc<HybridSorter> {
+fn prefixed_pattern_sorter(
+    tailwind_version: TailwindVersion,
+    tailwind_prefix: &str,
+) -> Arc<HybridSorter> {
+    let key = (tailwind_version, tailwind_prefix.to_string());
+
     if let Some(sorter) = PREFIXED_PATTERN_SORTERS
         .read()
         .expect("prefixed pattern sorter cache should not be poisoned")
-        .get(tailwind_prefix)
+        .get(&key)
     {
         return Arc::clone(sorter);
     }
@@ -271,43 +769,66 @@ fn prefixed_pattern_sorter(tailwind_prefix: &str) -> Arc<HybridSorter> {
         .write()
         .expect("prefixed pattern sorter cache should not be poisoned");
 
-    Arc::clone(
-        sorters
-            .entry(tailwind_prefix.to_string())
-            .or_insert_with(|| {
-                Arc::new(HybridSorter::new_with_tailwind_prefix(Some(
-                    tailwind_prefix,
-                )))
-            }),
-    )
+    Arc::clone(sorters.entry(key).or_insert_with(|| {
+        Arc::new(
+            HybridSorter::new_with_tailwind_prefix(Some(tailwind_prefix))
+                .with_tailwind_version(tailwind_version),
+        )
+    }))
 }
 
-fn split_class_tokens(class_string: &str) -> Vec<&str> {
-    let mut tokens = Vec::new();
-    let mut start = None;
-    let mut bracket_depth: u32 = 0;
-
-    for (index, character) in class_string.char_indices() {
-        match character {
-            '[' => bracket_depth += 1,
-            ']' => bracket_depth = bracket_depth.saturating_sub(1),
-            _ => {}
-        }
+fn has_matches(patterns: &[(&Regex, ClassWrapping)], file_contents: &str) -> bool {
+    patterns
+        .iter()
+        .any(|(regex, _class_wrapping)| regex.is_match(file_contents))
+}
 
-        if character.is_ascii_whitespace() && bracket_depth == 0 {
-            if let Some(token_start) = start.take() {
-                tokens.push(&class_string[token_start..index]);
-            }
-        } else if start.is_none() {
-            start = Some(index);
-        }
+fn rewrap_wrapped_classes(classes: Vec<&str>, class_wrapping: ClassWrapping) -> String {
+    match class_wrapping {
+        ClassWrapping::NoWrapping => classes.join(" "),
+        ClassWrapping::CommaSingleQuotes => classes
+            .iter()
+            .map(|class| format!("'{}'", class))
+            .collect::<Vec<String>>()
+            .join(", "),
+        ClassWrapping::CommaDoubleQuotes => classes
+            .iter()
+            .map(|class| format!("\"{}\"", class))
+            .collect::<Vec<String>>()
+            .join(", "),
     }
+}
 
-    if let Some(token_start) = start {
-        tokens.push(&class_string[token_start..]);
+/// Joins the sorted classes with the whitespace found between the original classes, keeping
+/// the leading and trailing whitespace. Removed duplicates drop the last separators.
+fn join_with_original_whitespace(
+    class_string: &str,
+    original: &[&str],
+    sorted: Vec<&str>,
+) -> String {
+    let (Some(first), Some(last)) = (original.first(), original.last()) else {
+        return class_string.to_string();
+    };
+
+    let last_end = substring_offset(class_string, last) + last.len();
+    let separators = original.windows(2).map(|pair| {
+        let start = substring_offset(class_string, pair[0]) + pair[0].len();
+        &class_string[start..substring_offset(class_string, pair[1])]
+    });
+
+    let mut joined = String::with_capacity(class_string.len());
+    joined.push_str(&class_string[..substring_offset(class_string, first)]);
+
+    let mut separators = separators.chain(std::iter::repeat(" "));
+    for (index, class) in sorted.into_iter().enumerate() {
+        if index > 0 {
+            joined.push_str(separators.next().unwrap_or(" "));
+        }
+        joined.push_str(class);
     }
 
-    tokens
+    joined.push_str(&class_string[last_end..]);
+    joined
 }
 
 fn deduplicate_classes(classes: &mut Vec<&str>) {
@@ -315,13 +836,10 @@ fn deduplicate_classes(classes: &mut Vec<&str>) {
     classes.retain(|class| is_ellipsis_placeholder(class) || seen.insert(*class));
 }
 
-fn is_ellipsis_placeholder(class: &str) -> bool {
-    class == "..." || class == "…"
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sorter::{ClassSorter, ExtractionPattern};
     use pretty_assertions::assert_eq;
     use regex::Regex;
     use test_case::test_case;
@@ -331,6 +849,11 @@ mod tests {
         allow_duplicates: false,
         class_wrapping: ClassWrapping::NoWrapping,
         tailwind_prefix: None,
+        utility_extensions: None,
+        tailwind_version: TailwindVersion::V4,
+        regex_set: None,
+        preserve_whitespace: false,
+        extended_pattern_sorter: None,
     };
 
     // HAS_CLASSES --------------------------------------------------------------------------------
@@ -436,6 +959,11 @@ mod tests {
             regex: FinderRegex::DefaultRegex,
             class_wrapping: ClassWrapping::NoWrapping,
             tailwind_prefix: None,
+            utility_extensions: None,
+            tailwind_version: TailwindVersion::V4,
+            regex_set: None,
+            preserve_whitespace: false,
+            extended_pattern_sorter: None,
         };
 
         let input = r#"<div class="flex flex m-4 m-4"></div>"#;
@@ -577,12 +1105,7 @@ mod tests {
         ; "comma double quotes"
     )]
     fn test_rewrap_wrapped_classes(input: Vec<&str>, wrapping: ClassWrapping, output: &str) {
-        let app = RustyWind {
-            class_wrapping: wrapping,
-            ..RUSTYWIND_DEFAULT
-        };
-
-        assert_eq!(app.rewrap_wrapped_classes(input), output)
+        assert_eq!(rewrap_wrapped_classes(input, wrapping), output)
     }
 
     #[test]
@@ -657,6 +1180,496 @@ mod tests {
         assert!(app.has_classes(input5), "Should match calc with percentage");
     }
 
+    #[test]
+    fn test_find_unknown_classes() {
+        let input =
+            "<div class=\"flex itmes-center\">\n  <p class='hvoer:bg-red-500 p-4'></p>\n</div>";
+        let unknown = RUSTYWIND_DEFAULT.find_unknown_classes(input, &UnknownAllowlist::default());
+
+        assert_eq!(unknown.len(), 2);
+
+        assert_eq!(unknown[0].class, "itmes-center");
+        assert_eq!(unknown[0].kind, UnknownKind::Utility);
+        assert_eq!((unknown[0].line, unknown[0].column), (1, 18));
+        assert_eq!(unknown[0].suggestions[0], "items-center");
+
+        assert_eq!(unknown[1].class, "hvoer:bg-red-500");
+        assert_eq!(unknown[1].kind, UnknownKind::Variant("hvoer".to_string()));
+        assert_eq!((unknown[1].line, unknown[1].column), (2, 13));
+        assert_eq!(unknown[1].suggestions, vec!["hover:bg-red-500"]);
+    }
+
+    #[test]
+    fn test_find_unknown_classes_respects_allowlist() {
+        let input = r#"<div class="btn js-toggle md:itmes-center ... flex"></div>"#;
+        let allowlist = UnknownAllowlist::new(["btn", "js-*"]);
+        let unknown = RUSTYWIND_DEFAULT.find_unknown_classes(input, &allowlist);
+
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].class, "md:itmes-center");
+        assert_eq!(unknown[0].suggestions[0], "md:items-center");
+    }
+
+    #[test]
+    fn test_find_unknown_classes_with_tailwind_prefix() {
+        let app = RustyWind {
+            tailwind_prefix: Some("tw".to_string()),
+            ..RUSTYWIND_DEFAULT
+        };
+        let input = r#"<div class="tw-flex md:tw-itmes-center"></div>"#;
+        let unknown = app.find_unknown_classes(input, &UnknownAllowlist::default());
+
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].suggestions[0], "md:tw-items-center");
+    }
+
+    #[test]
+    fn test_sort_with_utility_extensions() {
+        let mut extensions = UtilityExtensions::new();
+        extensions
+            .place_property(
+                "--tw-scrollbar",
+                crate::utility_extensions::PropertyPosition::After("padding".to_string()),
+            )
+            .unwrap();
+        extensions.add_utility("scrollbar-*", ["--tw-scrollbar"]);
+        extensions.add_utility("btn", ["display"]);
+        extensions.set_declaration_count("btn", 4);
+
+        let input = r#"<div class="p-4 flex scrollbar-thin btn"></div>"#;
+
+        assert_eq!(
+            RUSTYWIND_DEFAULT.sort_file_contents(input),
+            r#"<div class="scrollbar-thin btn flex p-4"></div>"#
+        );
+
+        let app = RustyWind::default().with_utility_extensions(Some(Arc::new(extensions)));
+        assert!(app.extended_pattern_sorter.is_some());
+
+        assert_eq!(
+            app.sort_file_contents(input),
+            r#"<div class="btn flex p-4 scrollbar-thin"></div>"#
+        );
+        assert!(
+            app.find_unknown_classes(input, &UnknownAllowlist::default())
+                .is_empty()
+        );
+
+        // the other builder methods rebuild the sorter
+        let app = app.with_tailwind_version(TailwindVersion::V3);
+        assert_eq!(
+            app.sort_classes("text-opacity-50 bg-red-500"),
+            "bg-red-500 text-opacity-50"
+        );
+    }
+
+    #[test_case(
+        "p-4 px-2 m-4",
+        "m-4 p-4 px-2"
+        ; "utilities of the same plugin"
+    )]
+    #[test_case(
+        "px-2 p-4 mt-2 m-4",
+        "m-4 mt-2 p-4 px-2"
+        ; "utilities in the order the plugin registers them"
+    )]
+    #[test_case(
+        "left-0 top-0 inset-y-0 inset-x-0 inset-0",
+        "inset-0 inset-x-0 inset-y-0 top-0 left-0"
+        ; "inset utilities"
+    )]
+    #[test_case(
+        "rounded-tl-lg rounded-t-lg rounded-lg border-t-2 border-2",
+        "rounded-lg rounded-t-lg rounded-tl-lg border-2 border-t-2"
+        ; "border utilities"
+    )]
+    #[test_case("p-4 p-2 px-4 px-2", "p-4 p-2 px-4 px-2" ; "values of a utility keep their order")]
+    #[test_case(
+        "text-opacity-50 bg-opacity-50 bg-red-500",
+        "bg-red-500 bg-opacity-50 text-opacity-50"
+        ; "opacity utilities"
+    )]
+    #[test_case("p-4 gap-2 space-x-4", "gap-2 space-x-4 p-4" ; "space after gap")]
+    #[test_case("ring-red-500 ring shadow", "shadow ring ring-red-500" ; "ring width")]
+    #[test_case("md:p-4 dark:flex hover:m-4", "hover:m-4 dark:flex md:p-4" ; "variant order")]
+    #[test_case("flex p-4! !m-4", "p-4! !m-4 flex" ; "important prefix only")]
+    fn test_sort_with_tailwind_v3(input: &str, output: &str) {
+        let app = RustyWind {
+            tailwind_version: TailwindVersion::V3,
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(app.sort_classes(input), output);
+    }
+
+    #[test]
+    fn test_find_unknown_classes_with_tailwind_v3() {
+        let app = RustyWind {
+            tailwind_version: TailwindVersion::V3,
+            ..RUSTYWIND_DEFAULT
+        };
+        let unknown_classes = app.find_unknown_classes(
+            r#"<div class="bg-opacity-50 group-hover:flex not-first:flex"></div>"#,
+            &UnknownAllowlist::default(),
+        );
+
+        assert_eq!(unknown_classes.len(), 1);
+        assert_eq!(unknown_classes[0].class, "not-first:flex");
+        assert_eq!(
+            unknown_classes[0].kind,
+            UnknownKind::Variant("not-first".to_string())
+        );
+    }
+
+    #[test_case("p-4 card m-4 btn", "btn card m-4 p-4" ; "listed classes before pattern classes")]
+    #[test_case("flex p-4 block", "block flex p-4" ; "listed utilities keep the custom order")]
+    #[test_case("md:flex unknown btn p-4", "unknown btn p-4 md:flex" ; "unknown classes first")]
+    #[test_case("p-4 ... btn", "btn p-4 ..." ; "placeholder last")]
+    #[test_case("tw:p-4 tw:btn tw:m-4", "tw:btn tw:m-4 tw:p-4" ; "tailwind prefix")]
+    fn test_sort_with_pattern_fallback(input: &str, output: &str) {
+        let sort_order = ["btn", "card", "block", "flex"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, class)| (class.to_string(), index))
+            .collect();
+
+        let app = RustyWind {
+            sorter: Sorter::new(sort_order).with_pattern_fallback(),
+            tailwind_prefix: input.starts_with("tw:").then(|| "tw".to_string()),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(app.sort_classes(input), output);
+    }
+
+    #[test]
+    fn test_sort_file_contents_for_path() {
+        let mut regex_set = FinderRegexSet::with_defaults();
+        regex_set
+            .add_rule(
+                ["*.py"],
+                vec![ExtractionPattern::new(
+                    FinderRegex::CustomRegex(Regex::new(r"classes=\[([^\]]*)\]").unwrap()),
+                    ClassWrapping::CommaSingleQuotes,
+                )],
+            )
+            .unwrap();
+
+        let app = RustyWind {
+            regex_set: Some(Arc::new(regex_set)),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        let ruby = r#"tag(class: "p-4 flex") <div class="p-4 flex"></div>"#;
+        assert_eq!(
+            app.sort_file_contents_for_path(Path::new("app/a.rb"), ruby),
+            r#"tag(class: "flex p-4") <div class="flex p-4"></div>"#
+        );
+
+        let python = r#"x(classes=['p-4', 'flex']) <div class="p-4 flex"></div>"#;
+        assert_eq!(
+            app.sort_file_contents_for_path(Path::new("b.py"), python),
+            r#"x(classes=['flex', 'p-4']) <div class="p-4 flex"></div>"#
+        );
+        assert!(!app.has_classes_for_path(Path::new("b.py"), r#"<div class="flex"></div>"#));
+
+        // files without a rule use the regex and class wrapping of the instance
+        assert_eq!(
+            app.sort_file_contents_for_path(Path::new("c.html"), ruby),
+            r#"tag(class: "p-4 flex") <div class="flex p-4"></div>"#
+        );
+
+        let unknown = app.find_unknown_classes_for_path(
+            Path::new("a.rb"),
+            r#"<div class="flexx"></div> tag(class: "blockk")"#,
+            &UnknownAllowlist::default(),
+        );
+        let unknown: Vec<&str> = unknown
+            .iter()
+            .map(|unknown| unknown.class.as_str())
+            .collect();
+        assert_eq!(unknown, ["flexx", "blockk"]);
+    }
+
+    #[test]
+    fn test_sort_with_multiple_regexes_and_named_groups() {
+        let pattern = |regex: &str| {
+            ExtractionPattern::new(
+                FinderRegex::CustomRegex(Regex::new(regex).unwrap()),
+                ClassWrapping::NoWrapping,
+            )
+        };
+
+        let mut regex_set = FinderRegexSet::new();
+        regex_set.set_fallback_patterns(vec![
+            pattern(r#"tw="(?P<base>[^"|]*)\|(?P<hover>[^"]*)""#),
+            pattern(r#"class="([^"]*)""#),
+            // overlaps the `hover` group, the earlier pattern wins
+            pattern(r#"\|([^"]*)""#),
+            pattern(r"classList\.add\('([^']*)'\)"),
+        ]);
+
+        let app = RustyWind {
+            regex_set: Some(Arc::new(regex_set)),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(
+            app.sort_file_contents(
+                r#"<a tw="p-4 flex|hover:p-2 hover:block" class="p-4 flex"></a> el.classList.add('p-4 flex')"#
+            ),
+            r#"<a tw="flex p-4|hover:block hover:p-2" class="flex p-4"></a> el.classList.add('flex p-4')"#
+        );
+
+        let unknown = app.find_unknown_classes(
+            r#"<a tw="flexx|hover:blockk"></a>"#,
+            &UnknownAllowlist::default(),
+        );
+        let unknown: Vec<&str> = unknown
+            .iter()
+            .map(|unknown| unknown.class.as_str())
+            .collect();
+        assert_eq!(unknown, ["flexx", "hover:blockk"]);
+    }
+
+    #[test_case(r#"<a tw="p-4 flex"></a>"#, r#"<a tw="flex p-4"></a>"# ; "attribute")]
+    #[test_case(r#"<a tw = 'p-4 flex'></a>"#, r#"<a tw = 'flex p-4'></a>"# ; "single quotes and whitespace")]
+    #[test_case(r#"<a :activeClass="p-4 flex"></a>"#, r#"<a :activeClass="flex p-4"></a>"# ; "vue binding")]
+    #[test_case(r#"<a v-bind:activeClass="p-4 flex"></a>"#, r#"<a v-bind:activeClass="flex p-4"></a>"# ; "vue bind")]
+    #[test_case(r#"<a [activeClass]="p-4 flex"></a>"#, r#"<a [activeClass]="flex p-4"></a>"# ; "angular binding")]
+    #[test_case(r#"<a data-classes="p-4 flex"></a>"#, r#"<a data-classes="flex p-4"></a>"# ; "data attribute")]
+    #[test_case(r#"<a class="p-4 flex"></a>"#, r#"<a class="flex p-4"></a>"# ; "class is kept")]
+    #[test_case(r#"<a data-tw="p-4 flex" stw="p-4 flex"></a>"#, r#"<a data-tw="p-4 flex" stw="p-4 flex"></a>"# ; "other attributes")]
+    fn test_sort_with_attributes_regex(input: &str, output: &str) {
+        let app = RustyWind {
+            regex: FinderRegex::CustomRegex(crate::defaults::attributes_regex([
+                "tw",
+                "activeClass",
+                "data-classes",
+            ])),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(app.sort_file_contents(input), output);
+    }
+
+    #[test_case("  p-4\n  flex  ", "  flex\n  p-4  " ; "leading and trailing")]
+    #[test_case("p-4  m-2\tflex", "m-2  flex\tp-4" ; "separators keep their position")]
+    #[test_case("p-4 flex  p-4\tblock", "block flex  p-4" ; "duplicates drop the last separators")]
+    fn test_sort_preserving_whitespace(input: &str, output: &str) {
+        let app = RustyWind {
+            preserve_whitespace: true,
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(app.sort_classes(input), output);
+    }
+
+    #[test]
+    fn test_sort_with_class_regex_functions() {
+        let app = RustyWind {
+            regex: FinderRegex::CustomRegex(crate::defaults::class_regex(
+                ["tw"],
+                ["clsx", "tw.div"],
+            )),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(
+            app.sort_file_contents(
+                r#"clsx("p-4 flex", active) clsx('p-4 flex') clsx(`p-4 flex`) tw.div`p-4 flex` tw.div`p-4 ${x}` <a tw="p-4 flex" class="p-4 flex">"#
+            ),
+            r#"clsx("flex p-4", active) clsx('flex p-4') clsx(`flex p-4`) tw.div`flex p-4` tw.div`p-4 ${x}` <a tw="flex p-4" class="flex p-4">"#
+        );
+    }
+
+    #[test]
+    fn test_rank_uses_the_options() {
+        let prefixed = RUSTYWIND_DEFAULT
+            .clone()
+            .with_tailwind_prefix(Some("tw".to_string()));
+        assert!(matches!(prefixed.rank("tw:m-4"), Some(Rank::SortKey(_))));
+        assert!(prefixed.rank("tw:m-4") < prefixed.rank("tw:p-4"));
+
+        let v3 = RUSTYWIND_DEFAULT
+            .clone()
+            .with_tailwind_version(TailwindVersion::V3);
+        assert!(v3.rank("bg-red-500") < v3.rank("text-opacity-50"));
+        assert_eq!(RUSTYWIND_DEFAULT.rank("text-opacity-50"), None);
+    }
+
+    #[test]
+    fn test_sort_every_string_argument_of_a_call() {
+        let app = RustyWind {
+            regex: FinderRegex::CustomRegex(crate::defaults::class_regex(["tw"], ["clsx"])),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(
+            app.sort_file_contents(
+                r#"clsx("p-4 flex", active && "p-2 block", { "flex m-4": big }, fn('p-4 flex'), `p-4 ${x}`, `p-4 flex`) "p-4 flex""#
+            ),
+            r#"clsx("flex p-4", active && "block p-2", { "m-4 flex": big }, fn('flex p-4'), `p-4 ${x}`, `flex p-4`) "p-4 flex""#
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        let contents = "<div class=\"flex p-4\">\n  <a class=\"p-4\n    flex\"></a>\n</div>";
+        let edits = RUSTYWIND_DEFAULT.analyze(contents);
+
+        assert_eq!(
+            edits,
+            vec![ClassListEdit {
+                range: 35..47,
+                line: 2,
+                column: 13,
+                end_line: 3,
+                end_column: 9,
+                original: "p-4\n    flex".to_string(),
+                replacement: "flex p-4".to_string(),
+                classes: vec![
+                    ClassEdit {
+                        class: "p-4".to_string(),
+                        range: 35..38,
+                        new_index: Some(1),
+                        moved: true,
+                        duplicate_removed: false,
+                        unknown: false,
+                    },
+                    ClassEdit {
+                        class: "flex".to_string(),
+                        range: 43..47,
+                        new_index: Some(0),
+                        moved: true,
+                        duplicate_removed: false,
+                        unknown: false,
+                    },
+                ],
+            }]
+        );
+        assert_eq!(&contents[edits[0].range.clone()], edits[0].original);
+
+        assert_eq!(
+            apply_edits(contents, &edits),
+            RUSTYWIND_DEFAULT.sort_file_contents(contents)
+        );
+    }
+
+    #[test]
+    fn test_analyze_class_flags() {
+        let contents = r#"<div class="itmes-center flex mt-2 p-4 flex"></div>"#;
+        let edits = RUSTYWIND_DEFAULT.analyze(contents);
+
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].replacement, "itmes-center mt-2 flex p-4");
+
+        let flags: Vec<_> = edits[0]
+            .classes
+            .iter()
+            .map(|class| {
+                (
+                    &contents[class.range.clone()],
+                    class.new_index,
+                    class.moved,
+                    class.duplicate_removed,
+                    class.unknown,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            flags,
+            vec![
+                ("itmes-center", Some(0), false, false, true),
+                ("flex", Some(2), true, false, false),
+                ("mt-2", Some(1), true, false, false),
+                ("p-4", Some(3), false, false, false),
+                ("flex", None, false, true, false),
+            ]
+        );
+    }
+
+    #[test_case(SortRange::Lines(2..=2), "<a class=\"p-4  flex\">\n<b class=\"flex p-4\">\n<i class=\"p-4 flex\">" ; "one line")]
+    #[test_case(SortRange::Lines(2..=9), "<a class=\"p-4  flex\">\n<b class=\"flex p-4\">\n<i class=\"flex p-4\">" ; "lines past the end")]
+    #[test_case(SortRange::Bytes(12..12), "<a class=\"flex p-4\">\n<b class=\"p-4 flex\">\n<i class=\"p-4 flex\">" ; "cursor in a class list")]
+    #[test_case(SortRange::Bytes(21..30), "<a class=\"p-4  flex\">\n<b class=\"p-4 flex\">\n<i class=\"p-4 flex\">" ; "between class lists")]
+    fn test_sort_file_contents_in_range(range: SortRange, expected: &str) {
+        let contents = "<a class=\"p-4  flex\">\n<b class=\"p-4 flex\">\n<i class=\"p-4 flex\">";
+
+        assert_eq!(
+            RUSTYWIND_DEFAULT.sort_file_contents_in_range(contents, &range),
+            expected
+        );
+
+        let edits = RUSTYWIND_DEFAULT.analyze_in_range(contents, &range);
+        assert_eq!(apply_edits(contents, &edits), expected);
+    }
+
+    #[test]
+    fn test_find_class_occurrences_with_class_wrapping() {
+        let app = RustyWind {
+            regex: FinderRegex::CustomRegex(Regex::new(r#"classes=\[([^\]]*)\]"#).unwrap()),
+            class_wrapping: ClassWrapping::CommaSingleQuotes,
+            ..RUSTYWIND_DEFAULT
+        };
+        let contents = "x\nclasses=['p-4', 'flex']";
+
+        let occurrences: Vec<_> = app
+            .find_class_occurrences(contents)
+            .into_iter()
+            .map(|occurrence| {
+                (
+                    occurrence.class,
+                    occurrence.range,
+                    occurrence.line,
+                    occurrence.column,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            occurrences,
+            vec![
+                ("p-4".to_string(), 12..15, 2, 11),
+                ("flex".to_string(), 19..23, 2, 18),
+            ]
+        );
+    }
+
+    struct ReverseSorter;
+
+    impl ClassSorter for ReverseSorter {
+        fn sort_classes<'a>(&self, classes: &[&'a str]) -> Vec<&'a str> {
+            classes.iter().rev().copied().collect()
+        }
+    }
+
+    #[test]
+    fn test_sort_with_class_sorter() {
+        let app = RustyWind {
+            sorter: Sorter::from_class_sorter(ReverseSorter),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(
+            app.sort_file_contents(r#"<div class="a b c b"></div>"#),
+            r#"<div class="b c a"></div>"#
+        );
+
+        let app = RustyWind {
+            sorter: Sorter::from_class_sorter(
+                HybridSorter::new().with_tailwind_version(TailwindVersion::V3),
+            ),
+            ..RUSTYWIND_DEFAULT
+        };
+
+        assert_eq!(
+            app.sort_classes("p-4 gap-2 space-x-4"),
+            "gap-2 space-x-4 p-4"
+        );
+    }
+
     #[test_case(
         None,
         ClassWrapping::NoWrapping,
@@ -695,6 +1708,11 @@ mod tests {
             allow_duplicates: false,
             class_wrapping,
             tailwind_prefix: None,
+            utility_extensions: None,
+            tailwind_version: TailwindVersion::V4,
+            regex_set: None,
+            preserve_whitespace: false,
+            extended_pattern_sorter: None,
         };
 
         assert_eq!(app.sort_file_contents(input), output);
@@ -1,6 +1,95 @@
-//! Contains the default [Sorter](SORTER) and default [Regex](RE)
+//! Contains the default [Sorter](SORTER) and default [Regex](RE), and the built-in
+//! regexes used per file type
 use regex::Regex;
 use std::sync::LazyLock;
 
+/// The `=` and quoted value after an attribute name, the classes are in the first group for
+/// double quotes and in the second one for single quotes.
+const QUOTED_VALUE: &str = r#"\s*=\s*(?:"([^"]+)"|'([^']+)')"#;
+
 pub static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r#"\bclass(?:Name)?\s*=\s*(?:"([^"]+)"|'([^']+)')"#).unwrap());
+    LazyLock::new(|| Regex::new(&format!(r"\bclass(?:Name)?{QUOTED_VALUE}")).unwrap());
+
+/// The name of the capture group holding the arguments of a call. Each string literal in it
+/// is sorted on its own, templates with `${...}` expressions are skipped.
+pub const ARGUMENTS_GROUP: &str = "arguments";
+
+/// The string literals of call arguments, the classes are in the first group for double
+/// quotes, the second one for single quotes and the third one for templates.
+pub(crate) static STRING_LITERAL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""([^"]+)"|'([^']+)'|`([^`$]+)`|`[^`]*`"#).unwrap());
+
+/// The arguments of a call, up to one level of nested parentheses.
+const CALL_ARGUMENTS: &str = r#"\s*\(\s*(?P<arguments>(?:[^()"'`]|"[^"]*"|'[^']*'|`[^`]*`|\((?:[^()"'`]|"[^"]*"|'[^']*'|`[^`]*`)*\))*)\)"#;
+
+/// Matches `class: "..."` hash and keyword arguments, as used by Ruby and Elixir view
+/// helpers.
+pub static CLASS_KEY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\bclass:\s*(?:"([^"]+)"|'([^']+)')"#).unwrap());
+
+/// Builds a regex matching the same attributes as [RE], and also the given attribute names,
+/// like the `tailwindAttributes` option of prettier-plugin-tailwindcss. The Vue (`:name`,
+/// `v-bind:name`) and Angular (`[name]`) bindings of the names match too.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::defaults::attributes_regex;
+///
+/// let regex = attributes_regex(["tw", "activeClass"]);
+///
+/// assert!(regex.is_match(r#"<a tw = 'flex p-4'></a>"#));
+/// assert!(regex.is_match(r#"<a [activeClass]="flex p-4"></a>"#));
+/// assert!(!regex.is_match(r#"<a data-tw="flex p-4"></a>"#));
+/// ```
+pub fn attributes_regex<S: AsRef<str>>(attributes: impl IntoIterator<Item = S>) -> Regex {
+    class_regex(attributes, std::iter::empty::<&str>())
+}
+
+/// Like [attributes_regex], also matching the calls to the given functions and the templates
+/// tagged with them, like the `tailwindFunctions` option of prettier-plugin-tailwindcss. Every
+/// string literal in the arguments of a call is sorted, through the [ARGUMENTS_GROUP] group.
+/// Templates with `${...}` expressions are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::defaults::class_regex;
+///
+/// let regex = class_regex(["tw"], ["clsx", "tw.div"]);
+///
+/// assert!(regex.is_match(r#"clsx("flex p-4", isActive && "block")"#));
+/// assert!(regex.is_match("const Button = tw.div`flex p-4`"));
+/// assert!(!regex.is_match(r#"myclsx("flex p-4")"#));
+/// ```
+pub fn class_regex<S: AsRef<str>, T: AsRef<str>>(
+    attributes: impl IntoIterator<Item = S>,
+    functions: impl IntoIterator<Item = T>,
+) -> Regex {
+    let attributes = escaped_names(attributes);
+    let functions = escaped_names(functions);
+
+    if attributes.is_empty() && functions.is_empty() {
+        return RE.clone();
+    }
+
+    let mut regex = String::from(r"\bclass(?:Name)?");
+    if !attributes.is_empty() {
+        regex = format!(r"(?:{regex}|(?:^|[^\w-])(?:{attributes})\]?)");
+    }
+    regex.push_str(QUOTED_VALUE);
+
+    if !functions.is_empty() {
+        regex = format!(r#"{regex}|(?:^|[^\w$.])(?:{functions})(?:{CALL_ARGUMENTS}|`([^`$]+)`)"#);
+    }
+
+    Regex::new(&regex).expect("escaped names always form a valid regex")
+}
+
+fn escaped_names<S: AsRef<str>>(names: impl IntoIterator<Item = S>) -> String {
+    names
+        .into_iter()
+        .map(|name| regex::escape(name.as_ref()))
+        .collect::<Vec<_>>()
+        .join("|")
+}
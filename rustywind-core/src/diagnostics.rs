@@ -0,0 +1,472 @@
+//! Diagnostics for classes and variants the pattern sorter does not recognize
+//!
+//! Typos like `itmes-center` or `hvoer:bg-red-500` are not errors for the sorter:
+//! unknown classes are simply moved to the front of the class list. This module
+//! finds those classes and suggests the closest known utilities or variants, so
+//! that they can be reported to the user.
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::diagnostics::{suggest_utilities, suggest_variants};
+//!
+//! assert_eq!(suggest_utilities("itmes-center"), vec!["items-center"]);
+//! assert_eq!(suggest_variants("hvoer"), vec!["hover"]);
+//! ```
+
+use ahash::AHashSet as HashSet;
+use std::sync::LazyLock;
+
+use crate::tailwind_v3;
+use crate::tailwind_version::TailwindVersion;
+use crate::utility_map::UTILITY_MAP;
+use crate::variant_order::{VARIANT_ORDER, VariantInfo, VariantOrder};
+
+/// Maximum number of suggestions returned for a single unknown class or variant.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// First segments of every static utility.
+///
+/// Parameterized bases like `leading` or `mt` have no static utility, they are found
+/// by trying the single edits of an unknown base against the [`UTILITY_MAP`].
+static UTILITY_BASES: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
+    let bases: HashSet<&'static str> = UTILITY_MAP
+        .exact_utilities()
+        .filter_map(|utility| utility.split_once('-').map(|(base, _)| base))
+        .collect();
+
+    let mut bases: Vec<_> = bases.into_iter().collect();
+    bases.sort_unstable();
+    bases
+});
+
+/// What the sorter did not recognize about a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnknownKind {
+    /// The utility part of the class is unknown, the class has no sort key.
+    Utility,
+    /// The class uses a variant that is not in the variant order.
+    Variant(String),
+}
+
+/// A class the sorter does not recognize, with its location in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownClass {
+    /// The class as written in the source
+    pub class: String,
+    /// Whether the utility or one of the variants is unknown
+    pub kind: UnknownKind,
+    /// Byte offset of the class in the source
+    pub offset: usize,
+    /// 1-based line of the class in the source
+    pub line: usize,
+    /// 1-based column (in characters) of the class in the source
+    pub column: usize,
+    /// Closest known classes, best match first
+    pub suggestions: Vec<String>,
+}
+
+/// Classes and variants that are intentionally unknown and should not be reported.
+///
+/// Entries match a class, its utility part or a variant exactly. An entry ending
+/// in `*` matches anything starting with the text before the `*`.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::diagnostics::UnknownAllowlist;
+///
+/// let allowlist = UnknownAllowlist::new(["btn", "js-*"]);
+/// assert!(allowlist.allows("btn"));
+/// assert!(allowlist.allows("js-toggle"));
+/// assert!(!allowlist.allows("card"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UnknownAllowlist {
+    exact: HashSet<String>,
+    prefixes: Vec<String>,
+}
+
+impl UnknownAllowlist {
+    pub fn new<T: AsRef<str>>(entries: impl IntoIterator<Item = T>) -> Self {
+        let mut allowlist = Self::default();
+
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            match entry.strip_suffix('*') {
+                Some(prefix) => allowlist.prefixes.push(prefix.to_string()),
+                None if !entry.is_empty() => {
+                    allowlist.exact.insert(entry.to_string());
+                }
+                None => {}
+            }
+        }
+
+        allowlist
+    }
+
+    /// Returns `true` if the given class, utility or variant is allowed to be unknown.
+    pub fn allows(&self, name: &str) -> bool {
+        self.exact.contains(name)
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.prefixes.is_empty()
+    }
+}
+
+/// Returns `true` if every part of the variant is in the variant order.
+///
+/// Arbitrary variants (`[&>*]`) are always considered known. Compound variants
+/// (`group-hover`, `not-first`) are known only if their modifier is known too.
+pub fn is_known_variant(variant: &str) -> bool {
+    is_known_variant_with(variant, &VariantOrder::default())
+}
+
+/// Like [`is_known_variant`], but also knows the variants of the plugins enabled in
+/// the variant order.
+pub fn is_known_variant_with(variant: &str, order: &VariantOrder) -> bool {
+    if variant.starts_with('[') {
+        return true;
+    }
+
+    is_known_variant_info(&order.parse(variant))
+}
+
+fn is_known_variant_info(variant: &VariantInfo) -> bool {
+    if variant.index().is_none() {
+        return false;
+    }
+
+    match &variant.modifier {
+        Some(modifier) if modifier.base.starts_with('[') => true,
+        Some(modifier) => is_known_variant_info(modifier),
+        None => true,
+    }
+}
+
+/// Suggest the closest known utilities for an unknown utility, best match first.
+///
+/// Suggestions come from the static utilities of the [`UTILITY_MAP`], and from
+/// replacing the base of a parameterized utility (`bgg-red-500` → `bg-red-500`).
+pub fn suggest_utilities(utility: &str) -> Vec<String> {
+    let (negative, unsigned) = utility
+        .strip_prefix('-')
+        .map_or(("", utility), |rest| ("-", rest));
+    let max_distance = max_distance(unsigned);
+    let mut scored: Vec<(usize, String)> = vec![];
+
+    for known in UTILITY_MAP.exact_utilities() {
+        let distance = edit_distance(unsigned, known);
+        if distance <= max_distance {
+            scored.push((distance, known.to_string()));
+        }
+    }
+
+    if let Some((base, value)) = unsigned.split_once('-') {
+        let max_base_distance = max_distance.min(base.len().div_ceil(2));
+        let static_bases = UTILITY_BASES
+            .iter()
+            .map(|known_base| (edit_distance(base, known_base), known_base.to_string()));
+        let edited_bases = single_edits(base).into_iter().map(|edited| (1, edited));
+
+        for (distance, known_base) in static_bases.chain(edited_bases) {
+            if distance == 0 || distance > max_base_distance {
+                continue;
+            }
+
+            let candidate = format!("{negative}{known_base}-{value}");
+            if UTILITY_MAP.get_properties(&candidate).is_some() {
+                scored.push((distance, candidate.trim_start_matches('-').to_string()));
+            }
+        }
+    }
+
+    best_suggestions(scored)
+        .into_iter()
+        .map(|suggestion| format!("{negative}{suggestion}"))
+        .collect()
+}
+
+/// Suggest the closest known variants for an unknown variant, best match first.
+pub fn suggest_variants(variant: &str) -> Vec<String> {
+    suggest_variants_with(variant, &VariantOrder::default(), TailwindVersion::V4)
+}
+
+/// Like [`suggest_variants`], suggesting the variants of the Tailwind version, and for
+/// v4 the variants of the plugins enabled in the variant order.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::diagnostics::suggest_variants_with;
+/// use rustywind_core::tailwind_version::TailwindVersion;
+/// use rustywind_core::variant_order::VariantOrder;
+///
+/// let order = VariantOrder::default();
+/// assert_eq!(suggest_variants_with("read-writ", &order, TailwindVersion::V4), vec!["read-write"]);
+/// assert!(suggest_variants_with("read-writ", &order, TailwindVersion::V3).is_empty());
+/// ```
+pub fn suggest_variants_with(
+    variant: &str,
+    order: &VariantOrder,
+    tailwind_version: TailwindVersion,
+) -> Vec<String> {
+    let max_distance = max_distance(variant);
+    let closest = |known_variants: &mut dyn Iterator<Item = &str>| {
+        let scored = known_variants
+            .filter(|known| !known.contains("[]"))
+            .map(|known| (edit_distance(variant, known), known.to_string()))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+
+        best_suggestions(scored)
+    };
+
+    if tailwind_version == TailwindVersion::V3 {
+        return closest(&mut tailwind_v3::VARIANT_ORDER.iter().copied());
+    }
+
+    let parsed = order.parse(variant);
+
+    // for compound variants with a known base only the modifier needs fixing
+    if let Some(modifier) = &parsed.modifier
+        && order.variant_index(&parsed.base).is_some()
+    {
+        let modifier_str = &variant[parsed.base.len() + 1..];
+        if modifier.base.starts_with('[') {
+            return vec![];
+        }
+
+        return suggest_variants_with(modifier_str, order, tailwind_version)
+            .into_iter()
+            .map(|modifier| format!("{}-{modifier}", parsed.base))
+            .collect();
+    }
+
+    let plugin_variants = order.plugin_variants();
+    closest(
+        &mut VARIANT_ORDER
+            .iter()
+            .copied()
+            .chain(plugin_variants.iter().map(String::as_str)),
+    )
+}
+
+/// Keep only the closest suggestions, a farther one is rarely what was meant.
+fn best_suggestions(mut scored: Vec<(usize, String)>) -> Vec<String> {
+    scored.sort();
+    scored.dedup_by(|a, z| a.1 == z.1);
+
+    let best_distance = scored.first().map(|(distance, _)| *distance);
+    scored
+        .into_iter()
+        .take_while(|(distance, _)| Some(*distance) == best_distance)
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, suggestion)| suggestion)
+        .collect()
+}
+
+/// Every word one deletion, transposition, substitution or insertion of a lowercase
+/// letter away from `word`.
+fn single_edits(word: &str) -> HashSet<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut edits = HashSet::new();
+
+    for i in 0..=chars.len() {
+        let (before, after) = chars.split_at(i);
+        let before: String = before.iter().collect();
+
+        if let Some((&current, rest)) = after.split_first() {
+            let rest_string: String = rest.iter().collect();
+            edits.insert(format!("{before}{rest_string}"));
+
+            if let Some((&next, rest)) = rest.split_first() {
+                let rest: String = rest.iter().collect();
+                edits.insert(format!("{before}{next}{current}{rest}"));
+            }
+
+            for letter in ('a'..='z').filter(|&letter| letter != current) {
+                edits.insert(format!("{before}{letter}{rest_string}"));
+            }
+        }
+
+        let after: String = after.iter().collect();
+        for letter in 'a'..='z' {
+            edits.insert(format!("{before}{letter}{after}"));
+        }
+    }
+
+    edits.remove(word);
+    edits
+}
+
+fn max_distance(name: &str) -> usize {
+    (name.chars().count() / 4).clamp(1, 3)
+}
+
+/// Optimal string alignment distance: Levenshtein distance where swapping two
+/// adjacent characters counts as a single edit (`hvoer` → `hover` is 1).
+pub fn edit_distance(a: &str, z: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let z: Vec<char> = z.chars().collect();
+
+    if a.is_empty() || z.is_empty() {
+        return a.len().max(z.len());
+    }
+
+    let width = z.len() + 1;
+    let mut distances = vec![0usize; (a.len() + 1) * width];
+
+    for (i, row) in distances.chunks_mut(width).enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in distances[..width].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=z.len() {
+            let cost = usize::from(a[i - 1] != z[j - 1]);
+            let mut distance = (distances[(i - 1) * width + j] + 1)
+                .min(distances[i * width + j - 1] + 1)
+                .min(distances[(i - 1) * width + j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == z[j - 2] && a[i - 2] == z[j - 1] {
+                distance = distance.min(distances[(i - 2) * width + j - 2] + 1);
+            }
+
+            distances[i * width + j] = distance;
+        }
+    }
+
+    distances[a.len() * width + z.len()]
+}
+
+/// Convert a byte offset into a 1-based line and 1-based character column.
+pub fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case("hvoer", "hover", 1 ; "transposition")]
+    #[test_case("justfy", "justify", 1 ; "deletion")]
+    #[test_case("flex", "flex", 0 ; "equal")]
+    #[test_case("", "abc", 3 ; "empty")]
+    #[test_case("kitten", "sitting", 3 ; "classic")]
+    fn test_edit_distance(a: &str, z: &str, distance: usize) {
+        assert_eq!(edit_distance(a, z), distance);
+    }
+
+    #[test_case("itmes-center", &["items-center"] ; "transposed utility")]
+    #[test_case("justfy-between", &["justify-between"] ; "missing letter")]
+    #[test_case("bgg-red-500", &["bg-red-500"] ; "parameterized base")]
+    #[test_case("-mtt-4", &["-mt-4"] ; "negative parameterized base")]
+    #[test_case("leadign-6", &["leading-6"] ; "transposed parameterized base")]
+    #[test_case("durtion-300", &["duration-300"] ; "parameterized base missing a letter")]
+    #[test_case("trackingg-tight", &["tracking-tight"] ; "parameterized base with an extra letter")]
+    fn test_suggest_utilities(utility: &str, expected: &[&str]) {
+        let suggestions = suggest_utilities(utility);
+        for expected in expected {
+            assert!(
+                suggestions.iter().any(|suggestion| suggestion == expected),
+                "{utility} should suggest {expected}, got {suggestions:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_suggestions_for_unrelated_names() {
+        assert!(suggest_utilities("my-company-card").is_empty());
+        assert!(suggest_variants("something").is_empty());
+    }
+
+    #[test_case("hvoer", "hover" ; "simple variant")]
+    #[test_case("group-hovr", "group-hover" ; "compound variant")]
+    #[test_case("drak", "dark" ; "dark mode")]
+    fn test_suggest_variants(variant: &str, expected: &str) {
+        assert_eq!(suggest_variants(variant)[0], expected);
+    }
+
+    #[test]
+    fn test_suggest_variants_of_the_active_table() {
+        let order = VariantOrder::default();
+
+        assert_eq!(
+            suggest_variants_with("not-dakr", &order, TailwindVersion::V4),
+            vec!["not-dark"]
+        );
+        // v3 has no `not-*` variants
+        assert!(
+            !suggest_variants_with("not-dakr", &order, TailwindVersion::V3)
+                .iter()
+                .any(|suggestion| suggestion.starts_with("not-"))
+        );
+        assert_eq!(
+            suggest_variants_with("hvoer", &order, TailwindVersion::V3),
+            vec!["hover"]
+        );
+    }
+
+    #[cfg(feature = "plugin-typography")]
+    #[test]
+    fn test_suggest_plugin_variants() {
+        use crate::plugins::Plugin;
+        use crate::utility_extensions::UtilityExtensions;
+
+        let mut extensions = UtilityExtensions::new();
+        extensions.add_plugin(Plugin::Typography);
+        let order = VariantOrder::new(Some(&extensions));
+
+        assert!(suggest_variants("prose-haedings").is_empty());
+        assert_eq!(
+            suggest_variants_with("prose-haedings", &order, TailwindVersion::V4),
+            vec!["prose-headings"]
+        );
+    }
+
+    #[test_case("hover", true)]
+    #[test_case("group-hover", true)]
+    #[test_case("group-hover/item", true)]
+    #[test_case("[&>*]", true)]
+    #[test_case("data-[state=open]", true)]
+    #[test_case("hvoer", false)]
+    #[test_case("group-hvoer", false)]
+    fn test_is_known_variant(variant: &str, known: bool) {
+        assert_eq!(is_known_variant(variant), known);
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist = UnknownAllowlist::new(["btn", "js-*", ""]);
+
+        assert!(allowlist.allows("btn"));
+        assert!(allowlist.allows("js-"));
+        assert!(allowlist.allows("js-menu"));
+        assert!(!allowlist.allows("btn-primary"));
+        assert!(!UnknownAllowlist::default().allows("btn"));
+    }
+
+    #[test]
+    fn test_line_column() {
+        let contents = "one\ntwo\n  thrée x";
+
+        assert_eq!(line_column(contents, 0), (1, 1));
+        assert_eq!(line_column(contents, 5), (2, 2));
+        assert_eq!(line_column(contents, contents.len() - 1), (3, 9));
+    }
+}
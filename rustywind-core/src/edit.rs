@@ -0,0 +1,177 @@
+//! Describes the changes sorting makes to the class lists of a file
+//!
+//! [RustyWind::analyze](crate::app::RustyWind::analyze) returns a [ClassListEdit] for
+//! each class list that is not sorted, with its location and what happens to each of its
+//! classes, so that it can be reported or applied with [apply_edits].
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::RustyWind;
+//!
+//! let edits = RustyWind::default().analyze("<div>\n  <a class=\"p-4 flex\"></a>");
+//!
+//! assert_eq!(edits.len(), 1);
+//! assert_eq!((edits[0].line, edits[0].column), (2, 13));
+//! assert_eq!(edits[0].replacement, "flex p-4");
+//! assert!(edits[0].classes.iter().all(|class| class.moved));
+//! ```
+//!
+//! A [SortRange] limits sorting to the class lists overlapping part of a file, e.g. a
+//! selection in an editor or the changed lines of a review:
+//!
+//! ```
+//! use rustywind_core::RustyWind;
+//! use rustywind_core::edit::SortRange;
+//!
+//! let contents = "<a class=\"p-4 flex\"></a>\n<b class=\"p-4 flex\"></b>";
+//! let sorted = RustyWind::default().sort_file_contents_in_range(contents, &SortRange::Lines(2..=2));
+//!
+//! assert_eq!(sorted, "<a class=\"p-4 flex\"></a>\n<b class=\"flex p-4\"></b>");
+//! ```
+
+use std::borrow::Cow;
+use std::ops::{Range, RangeInclusive};
+
+/// A class list that sorting changes, with its location in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassListEdit {
+    /// Byte range of the class list in the source
+    pub range: Range<usize>,
+    /// 1-based line of the start of the class list
+    pub line: usize,
+    /// 1-based column (in characters) of the start of the class list
+    pub column: usize,
+    /// 1-based line of the end of the class list
+    pub end_line: usize,
+    /// 1-based column (in characters) just after the end of the class list
+    pub end_column: usize,
+    /// The class list as written in the source
+    pub original: String,
+    /// The sorted class list replacing it
+    pub replacement: String,
+    /// The classes of the original class list, in the source order
+    pub classes: Vec<ClassEdit>,
+}
+
+impl ClassListEdit {
+    /// Whether the class list overlaps the byte range. An empty range, like a cursor,
+    /// overlaps the class list it is in, including at its ends.
+    pub fn overlaps(&self, range: &Range<usize>) -> bool {
+        overlaps(&self.range, range)
+    }
+}
+
+/// What sorting does to a class of a [ClassListEdit].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassEdit {
+    /// The class as written in the source
+    pub class: String,
+    /// Byte range of the class in the source
+    pub range: Range<usize>,
+    /// Position of the class in the sorted class list, `None` when it is removed
+    pub new_index: Option<usize>,
+    /// Whether sorting changes the position of the class among the classes that are kept
+    pub moved: bool,
+    /// Whether the class is removed because it repeats another class of the list
+    pub duplicate_removed: bool,
+    /// Whether the pattern sorter does not recognize the class or one of its variants
+    pub unknown: bool,
+}
+
+/// Replaces the class lists of the edits in the file contents. The edits must be in the
+/// order they appear in the file and not overlap, like [RustyWind::analyze] returns them.
+///
+/// [RustyWind::analyze]: crate::app::RustyWind::analyze
+pub fn apply_edits<'a>(file_contents: &'a str, edits: &[ClassListEdit]) -> Cow<'a, str> {
+    if edits.is_empty() {
+        return Cow::Borrowed(file_contents);
+    }
+
+    let mut edited = String::with_capacity(file_contents.len());
+    let mut last_end = 0;
+
+    for edit in edits {
+        edited.push_str(&file_contents[last_end..edit.range.start]);
+        edited.push_str(&edit.replacement);
+        last_end = edit.range.end;
+    }
+
+    edited.push_str(&file_contents[last_end..]);
+    Cow::Owned(edited)
+}
+
+/// The part of a file to sort. Only the class lists overlapping it are sorted, every other
+/// class list is left as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortRange {
+    /// A byte range of the file
+    Bytes(Range<usize>),
+    /// 1-based lines of the file, inclusive
+    Lines(RangeInclusive<usize>),
+}
+
+impl SortRange {
+    /// The byte range of the file contents. Lines cover their contents without the line
+    /// break, and parts past the end of the file are at its end.
+    pub fn byte_range(&self, file_contents: &str) -> Range<usize> {
+        match self {
+            SortRange::Bytes(range) => {
+                range.start.min(file_contents.len())..range.end.min(file_contents.len())
+            }
+            SortRange::Lines(lines) => {
+                let line_start = |line: usize| {
+                    if line <= 1 {
+                        return Some(0);
+                    }
+
+                    file_contents
+                        .match_indices('\n')
+                        .nth(line - 2)
+                        .map(|(index, _)| index + 1)
+                };
+
+                let start = line_start(*lines.start()).unwrap_or(file_contents.len());
+                let end = line_start(lines.end() + 1)
+                    .map_or(file_contents.len(), |next_line_start| next_line_start - 1);
+
+                start..end.max(start)
+            }
+        }
+    }
+}
+
+pub(crate) fn overlaps(class_list: &Range<usize>, range: &Range<usize>) -> bool {
+    if range.is_empty() {
+        class_list.start <= range.start && range.start <= class_list.end
+    } else {
+        class_list.start < range.end && range.start < class_list.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(SortRange::Bytes(2..5), 2..5 ; "bytes")]
+    #[test_case(SortRange::Bytes(8..40), 8..11 ; "bytes past the end")]
+    #[test_case(SortRange::Lines(1..=1), 0..3 ; "first line")]
+    #[test_case(SortRange::Lines(2..=3), 4..11 ; "last lines")]
+    #[test_case(SortRange::Lines(3..=9), 8..11 ; "lines past the end")]
+    #[test_case(SortRange::Lines(7..=9), 11..11 ; "lines after the end")]
+    fn test_sort_range_byte_range(range: SortRange, expected: Range<usize>) {
+        assert_eq!(range.byte_range("abc\ndef\nghi"), expected);
+    }
+
+    #[test_case(4..4, true ; "cursor at the start")]
+    #[test_case(8..8, true ; "cursor at the end")]
+    #[test_case(0..4, false ; "range ending at the start")]
+    #[test_case(8..10, false ; "range starting at the end")]
+    #[test_case(7..9, true ; "range overlapping the end")]
+    #[test_case(0..20, true ; "range containing the class list")]
+    fn test_overlaps(range: Range<usize>, expected: bool) {
+        assert_eq!(overlaps(&(4..8), &range), expected);
+    }
+}
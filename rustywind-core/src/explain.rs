@@ -0,0 +1,212 @@
+//! Explanations of why classes sort the way they do
+//!
+//! When the order RustyWind produces disagrees with what someone expects, the
+//! [`SortKey`] of every class and the comparison step that decided each adjacent
+//! pair show where the order comes from.
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::explain::{PairDecision, explain_classes};
+//! use rustywind_core::hybrid_sorter::HybridSorter;
+//! use rustywind_core::pattern_sorter::ComparisonStep;
+//!
+//! let explanation = explain_classes(&HybridSorter::new(), &["p-4", "m-4"]);
+//!
+//! assert_eq!(explanation.sorted_classes(), vec!["m-4", "p-4"]);
+//! assert_eq!(
+//!     explanation.pairs[0].decision,
+//!     PairDecision::Step(ComparisonStep::PropertyIndices)
+//! );
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::hybrid_sorter::HybridSorter;
+use crate::pattern_sorter::{ComparisonStep, SortKey};
+use crate::tailwind_v3;
+use crate::tailwind_version::TailwindVersion;
+use crate::variant_order::{ARBITRARY_VARIANT_BIT, variant_at};
+
+/// The sort key of a single class, along with the names behind its indices.
+#[derive(Debug, Clone)]
+pub struct ClassExplanation {
+    /// The class as written
+    pub class: String,
+    /// The sort key, `None` when the class is unknown and sorted to the front
+    pub sort_key: Option<SortKey>,
+    /// The CSS properties of the class with their property order indices, in comparison order
+    pub properties: Vec<(String, usize)>,
+}
+
+impl ClassExplanation {
+    /// The known variants whose bits are set in `variant_order`, as `(bit, variant)`.
+    pub fn variant_bits(&self) -> Vec<(usize, &'static str)> {
+        let Some(key) = &self.sort_key else {
+            return Vec::new();
+        };
+
+        let variant_at = match key.tailwind_version {
+            TailwindVersion::V3 => tailwind_v3::variant_at,
+            TailwindVersion::V4 => variant_at,
+        };
+
+        (0..ARBITRARY_VARIANT_BIT.trailing_zeros() as usize)
+            .filter(|bit| key.variant_order & (1u128 << bit) != 0)
+            .filter_map(|bit| Some((bit, variant_at(bit)?)))
+            .collect()
+    }
+
+    /// Whether the arbitrary variant bit is set in `variant_order`.
+    pub fn has_arbitrary_variant(&self) -> bool {
+        self.sort_key
+            .as_ref()
+            .is_some_and(|key| key.variant_order & ARBITRARY_VARIANT_BIT != 0)
+    }
+}
+
+/// What decided the order of two adjacent classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PairDecision {
+    /// Both classes are known, the step of [`SortKey::compare_for_sort_with_step`] that
+    /// decided
+    Step(ComparisonStep),
+    /// At least one class is unknown, unknown classes keep their relative order at the front
+    /// and `...` is always placed last
+    UnknownClass,
+}
+
+impl PairDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PairDecision::Step(step) => step.as_str(),
+            PairDecision::UnknownClass => "unknown class",
+        }
+    }
+}
+
+/// Why one class sorts before the next one.
+#[derive(Debug, Clone)]
+pub struct PairExplanation {
+    /// The class sorted first
+    pub left: String,
+    /// The class sorted right after `left`
+    pub right: String,
+    /// How `left` compares to `right`, `Equal` when nothing tells them apart
+    pub ordering: Ordering,
+    pub decision: PairDecision,
+}
+
+/// Explanation of the order of a class list.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// Every class in sorted order
+    pub classes: Vec<ClassExplanation>,
+    /// One entry for each adjacent pair of `classes`
+    pub pairs: Vec<PairExplanation>,
+}
+
+impl Explanation {
+    /// The classes in the order the sorter places them.
+    pub fn sorted_classes(&self) -> Vec<&str> {
+        self.classes.iter().map(|c| c.class.as_str()).collect()
+    }
+}
+
+/// Sort the classes with the given sorter and explain the resulting order.
+pub fn explain_classes(sorter: &HybridSorter, classes: &[&str]) -> Explanation {
+    let classes: Vec<ClassExplanation> = sorter
+        .sort_classes(classes)
+        .into_iter()
+        .map(|class| {
+            let sort_key = sorter.get_sort_key(class);
+            let properties = sort_key
+                .iter()
+                .flat_map(|key| match key.tailwind_version {
+                    // v3 keys follow the plugin index with the utility index in the plugin
+                    TailwindVersion::V3 => &key.property_indices[..1],
+                    TailwindVersion::V4 => &key.property_indices[..],
+                })
+                .filter_map(|&index| Some((sorter.property_at(index)?.to_string(), index)))
+                .collect();
+
+            ClassExplanation {
+                class: class.to_string(),
+                sort_key,
+                properties,
+            }
+        })
+        .collect();
+
+    let pairs = classes
+        .windows(2)
+        .map(|pair| {
+            let (left, right) = (&pair[0], &pair[1]);
+            let (ordering, decision) = match (&left.sort_key, &right.sort_key) {
+                (Some(a), Some(z)) => {
+                    let (ordering, step) = a.compare_for_sort_with_step(z);
+                    (ordering, PairDecision::Step(step))
+                }
+                (None, None) => (Ordering::Equal, PairDecision::UnknownClass),
+                _ => (Ordering::Less, PairDecision::UnknownClass),
+            };
+
+            PairExplanation {
+                left: left.class.clone(),
+                right: right.class.clone(),
+                ordering,
+                decision,
+            }
+        })
+        .collect();
+
+    Explanation { classes, pairs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case("p-4 m-4", ComparisonStep::PropertyIndices ; "property order")]
+    #[test_case("md:flex flex", ComparisonStep::BaseClass ; "base before variant")]
+    #[test_case("md:flex sm:flex", ComparisonStep::VariantMask ; "variant order")]
+    #[test_case("-rotate-1 rotate-1", ComparisonStep::Negative ; "negative first")]
+    #[test_case("p-8 p-4", ComparisonStep::NumericValue ; "numeric value")]
+    #[test_case("bg-red-500 bg-blue-500", ComparisonStep::ColorName ; "color name")]
+    fn test_pair_decision(classes: &str, step: ComparisonStep) {
+        let classes: Vec<&str> = classes.split_whitespace().collect();
+        let explanation = explain_classes(&HybridSorter::new(), &classes);
+
+        assert_eq!(explanation.pairs.len(), 1);
+        assert_eq!(explanation.pairs[0].ordering, Ordering::Less);
+        assert_eq!(explanation.pairs[0].decision, PairDecision::Step(step));
+    }
+
+    #[test]
+    fn test_unknown_class_pair() {
+        let explanation = explain_classes(&HybridSorter::new(), &["flex", "my-card"]);
+
+        assert_eq!(explanation.sorted_classes(), vec!["my-card", "flex"]);
+        assert!(explanation.classes[0].sort_key.is_none());
+        assert_eq!(explanation.pairs[0].decision, PairDecision::UnknownClass);
+    }
+
+    #[test]
+    fn test_class_explanation_fields() {
+        let explanation = explain_classes(&HybridSorter::new(), &["hover:-mt-4", "[&.x]:flex"]);
+        let margin = &explanation.classes[0];
+        let arbitrary = &explanation.classes[1];
+
+        assert_eq!(margin.class, "hover:-mt-4");
+        assert_eq!(margin.variant_bits().len(), 1);
+        assert_eq!(margin.variant_bits()[0].1, "hover");
+        assert!(!margin.has_arbitrary_variant());
+        assert_eq!(margin.properties[0].0, "margin-top");
+        assert!(margin.sort_key.as_ref().unwrap().is_negative);
+
+        assert!(arbitrary.has_arbitrary_variant());
+        assert_eq!(arbitrary.properties[0].0, "display");
+    }
+}
@@ -24,6 +24,8 @@ use std::sync::Arc;
 use quick_cache::sync::Cache;
 
 use crate::pattern_sorter::{PatternSorter, SortKey};
+use crate::tailwind_version::TailwindVersion;
+use crate::utility_extensions::UtilityExtensions;
 
 pub const DEFAULT_CACHE_SIZE: usize = 7500;
 
@@ -82,6 +84,47 @@ impl HybridSorter {
         }
     }
 
+    /// Use the given utility extensions on top of the built-in utility and property tables.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::hybrid_sorter::HybridSorter;
+    /// use rustywind_core::utility_extensions::UtilityExtensions;
+    /// use std::sync::Arc;
+    ///
+    /// let mut extensions = UtilityExtensions::new();
+    /// extensions.add_utility("btn", ["display"]);
+    ///
+    /// let sorter = HybridSorter::new().with_extensions(Some(Arc::new(extensions)));
+    /// assert_eq!(sorter.sort_classes(&["p-4", "btn"]), vec!["btn", "p-4"]);
+    /// ```
+    pub fn with_extensions(mut self, extensions: Option<Arc<UtilityExtensions>>) -> Self {
+        self.pattern_sorter = self.pattern_sorter.with_extensions(extensions);
+        self.cache.clear();
+        self
+    }
+
+    /// Follow the class order of the given Tailwind version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::hybrid_sorter::HybridSorter;
+    /// use rustywind_core::tailwind_version::TailwindVersion;
+    ///
+    /// let sorter = HybridSorter::new().with_tailwind_version(TailwindVersion::V3);
+    /// assert_eq!(
+    ///     sorter.sort_classes(&["hover:p-4", "bg-opacity-50", "m-4"]),
+    ///     vec!["m-4", "bg-opacity-50", "hover:p-4"]
+    /// );
+    /// ```
+    pub fn with_tailwind_version(mut self, tailwind_version: TailwindVersion) -> Self {
+        self.pattern_sorter = self.pattern_sorter.with_tailwind_version(tailwind_version);
+        self.cache.clear();
+        self
+    }
+
     /// Get the sort key for a class string
     ///
     /// Uses two-tier lookup:
@@ -164,7 +207,7 @@ impl HybridSorter {
         // classes with valid keys come after (sorted by key)
         // this matches prettier-plugin-tailwindcss behavior where unknown classes sort first
         with_keys.sort_by(|(a_key, a_class), (z_key, z_class)| match (a_key, z_key) {
-            (Some(a), Some(z)) => a.cmp(z),
+            (Some(a), Some(z)) => a.compare_for_sort(z),
             (Some(_), None) if is_ellipsis_class(z_class) => Ordering::Less,
             (Some(_), None) => Ordering::Greater, // Known classes after unknown
             (None, Some(_)) if is_ellipsis_class(a_class) => Ordering::Greater,
@@ -184,6 +227,12 @@ impl HybridSorter {
         result
     }
 
+    /// Get the CSS property at the given index of the property order, including any
+    /// properties added by the utility extensions.
+    pub fn property_at(&self, index: usize) -> Option<&str> {
+        self.pattern_sorter.property_at(index)
+    }
+
     /// Get cache statistics
     ///
     /// Returns (entries, capacity) for monitoring cache performance
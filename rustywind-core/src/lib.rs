@@ -2,22 +2,30 @@
 //! Call [`sorter::sort_file_contents`] with the file contents and the sorter options.
 //!
 //! The [`parser`] module contains the functions to parse the classes from a file.
-//! The [`parser::parse_classes_from_file`] function will return a `HashMap<String, usize>` with the classes and their order.
+//! The [`parser::parse_classes_from_file`] function will return a `HashMap<String, usize>` with the classes and their order,
+//! [`parser::find_class_occurrences`] returns each class with its position and the class list containing it.
 //!
 //! You can use this to create a custom sorter. Using this customer sorter you can call [`sorter::sort_file_contents`].
 pub mod app;
 pub mod class_wrapping;
 pub mod consts;
 pub mod defaults;
+pub mod diagnostics;
+pub mod edit;
+pub mod explain;
 pub mod parser;
 pub mod sorter;
 pub mod tailwind_prefix;
+pub mod tailwind_version;
 
 // Pattern-based sorting modules
 pub mod class_parser;
 pub mod hybrid_sorter;
 pub mod pattern_sorter;
+pub mod plugins;
 pub mod property_order;
+pub mod tailwind_v3;
+pub mod utility_extensions;
 pub mod utility_map;
 pub mod variant_order;
 
@@ -1,2 +1,11 @@
-//! Create a sorter from a CSS file to sort classes in the order that they appear in the file
+//! Parse the classes of source files and stylesheets
+//!
+//! [parse_classes_from_file] and [find_class_occurrences] find the classes of a source
+//! file with a [FinderRegex](crate::sorter::FinderRegex), the [css] module creates a
+//! sorter from a CSS file to sort classes in the order that they appear in the file.
+pub mod css;
 pub mod regex;
+
+pub use self::regex::{
+    ClassCount, ClassOccurrence, class_counts, find_class_occurrences, parse_classes_from_file,
+};
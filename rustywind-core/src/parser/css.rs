@@ -1 +1,284 @@
+//! Extract class names from a CSS file
+//!
+//! The extractor tokenizes the stylesheet instead of matching it line by line, so it
+//! works on minified CSS, selector lists (`.a,.b{}`), rules nested in `@media`,
+//! `@layer` or `@supports` blocks, CSS nesting and selectors with pseudo-classes
+//! (`.hover\:bg-red-500:hover`). Class names are unescaped following the CSS syntax,
+//! so `.w-1\/2`, `.bg-\[\#fff\]` and `.\32xl\:block` become `w-1/2`, `bg-[#fff]` and
+//! `2xl:block`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::parser::css::{extract_classes, strip_variants};
+//!
+//! let css = r".flex{display:flex}@media (min-width:768px){.md\:flex,.md\:block:hover{x:y}}";
+//!
+//! assert_eq!(extract_classes(css), vec!["flex", "md:flex", "md:block"]);
+//! assert_eq!(strip_variants("md:block"), "block");
+//! ```
 
+use ahash::AHashSet as HashSet;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::tailwind_prefix::utility_start;
+
+/// Extract the class names used in the selectors of a stylesheet, in order of first
+/// appearance and without duplicates.
+///
+/// Classes are taken from every selector of every style rule, including the ones in
+/// functional pseudo-classes like `:where(.dark, .dark *)`. At-rule preludes,
+/// declarations, comments and strings are skipped.
+pub fn extract_classes(css: &str) -> Vec<String> {
+    let mut classes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut prelude_start = 0;
+    let mut chars = css.char_indices().peekable();
+
+    while let Some((index, character)) = chars.next() {
+        match character {
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '*') => {
+                chars.next();
+                skip_comment(&mut chars);
+
+                // comments inside a prelude are skipped by the selector parser
+                if css[prelude_start..index].trim().is_empty() {
+                    prelude_start = next_index(&mut chars, css);
+                }
+            }
+            '"' | '\'' => skip_string(&mut chars, character),
+            '\\' => {
+                chars.next();
+            }
+            '{' => {
+                let prelude = css[prelude_start..index].trim();
+
+                if !prelude.starts_with('@') {
+                    for class in selector_classes(prelude) {
+                        if seen.insert(class.clone()) {
+                            classes.push(class);
+                        }
+                    }
+                }
+
+                prelude_start = index + 1;
+            }
+            '}' | ';' => prelude_start = index + 1,
+            _ => {}
+        }
+    }
+
+    classes
+}
+
+/// Strip the variants from a class name, leaving the utility.
+///
+/// Colons inside arbitrary values and variants don't separate variants.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::parser::css::strip_variants;
+///
+/// assert_eq!(strip_variants("dark:md:hover:text-white"), "text-white");
+/// assert_eq!(strip_variants("[&:hover]:flex"), "flex");
+/// assert_eq!(strip_variants("bg-[url(a:b)]"), "bg-[url(a:b)]");
+/// ```
+pub fn strip_variants(class: &str) -> &str {
+    &class[utility_start(class)..]
+}
+
+/// The class names in a selector list, unescaped and in order.
+fn selector_classes(selector: &str) -> Vec<String> {
+    let mut classes = Vec::new();
+    let mut chars = selector.char_indices().peekable();
+
+    while let Some((_, character)) = chars.next() {
+        match character {
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '*') => {
+                chars.next();
+                skip_comment(&mut chars);
+            }
+            '.' => {
+                if let Some(class) = read_ident(&mut chars) {
+                    classes.push(class);
+                }
+            }
+            '[' => skip_attribute(&mut chars),
+            '"' | '\'' => skip_string(&mut chars, character),
+            '\\' => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+
+    classes
+}
+
+/// Read a CSS identifier, resolving escapes.
+///
+/// Returns `None` when the characters after the `.` don't start an identifier, e.g.
+/// in the keyframe selector `12.5%`.
+fn read_ident(chars: &mut Peekable<CharIndices>) -> Option<String> {
+    let mut ident = String::new();
+
+    while let Some(&(_, character)) = chars.peek() {
+        match character {
+            '\\' => {
+                chars.next();
+                ident.push(read_escape(chars)?);
+            }
+            '0'..='9' if ident.is_empty() => return None,
+            character if is_name_char(character) => {
+                chars.next();
+                ident.push(character);
+            }
+            _ => break,
+        }
+    }
+
+    (!ident.is_empty()).then_some(ident)
+}
+
+/// Resolve an escape after the backslash: up to six hex digits followed by an optional
+/// whitespace character, or any other character taken literally.
+fn read_escape(chars: &mut Peekable<CharIndices>) -> Option<char> {
+    let &(_, first) = chars.peek()?;
+
+    if first == '\n' || first == '\r' || first == '\x0c' {
+        return None;
+    }
+
+    if !first.is_ascii_hexdigit() {
+        chars.next();
+        return Some(first);
+    }
+
+    let mut code_point = 0;
+    let mut digits = 0;
+
+    while let Some(&(_, character)) = chars.peek() {
+        match character.to_digit(16) {
+            Some(digit) if digits < 6 => {
+                chars.next();
+                code_point = code_point * 16 + digit;
+                digits += 1;
+            }
+            _ => break,
+        }
+    }
+
+    if chars
+        .peek()
+        .is_some_and(|&(_, character)| character.is_ascii_whitespace())
+    {
+        chars.next();
+    }
+
+    match char::from_u32(code_point) {
+        Some('\0') | None => Some(char::REPLACEMENT_CHARACTER),
+        Some(character) => Some(character),
+    }
+}
+
+fn is_name_char(character: char) -> bool {
+    character.is_ascii_alphanumeric()
+        || character == '-'
+        || character == '_'
+        || !character.is_ascii()
+}
+
+fn skip_comment(chars: &mut Peekable<CharIndices>) {
+    while let Some((_, character)) = chars.next() {
+        if character == '*' && chars.peek().is_some_and(|&(_, next)| next == '/') {
+            chars.next();
+            return;
+        }
+    }
+}
+
+fn skip_string(chars: &mut Peekable<CharIndices>, quote: char) {
+    while let Some((_, character)) = chars.next() {
+        match character {
+            '\\' => {
+                chars.next();
+            }
+            character if character == quote => return,
+            _ => {}
+        }
+    }
+}
+
+fn skip_attribute(chars: &mut Peekable<CharIndices>) {
+    while let Some((_, character)) = chars.next() {
+        match character {
+            '"' | '\'' => skip_string(chars, character),
+            '\\' => {
+                chars.next();
+            }
+            ']' => return,
+            _ => {}
+        }
+    }
+}
+
+fn next_index(chars: &mut Peekable<CharIndices>, css: &str) -> usize {
+    chars.peek().map_or(css.len(), |&(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test_case(".a{x:y}.b{x:y}", &["a", "b"] ; "minified")]
+    #[test_case(".a,.b{x:y}", &["a", "b"] ; "selector list")]
+    #[test_case(".a, .b ,\n.c {}", &["a", "b", "c"] ; "selector list with whitespace")]
+    #[test_case(".no-space{x:y}", &["no-space"] ; "no space before brace")]
+    #[test_case("@media (min-width:640px){.sm\\:flex{x:y}}", &["sm:flex"] ; "media")]
+    #[test_case(
+        "@layer utilities{@media (hover:hover){.a{x:y}}}.b{x:y}",
+        &["a", "b"]
+        ; "nested at-rules"
+    )]
+    #[test_case(".a{color:red;&:hover{.b &{x:y}}}", &["a", "b"] ; "css nesting")]
+    #[test_case(".hover\\:bg-red:hover{x:y}", &["hover:bg-red"] ; "pseudo class")]
+    #[test_case(".group:hover .group-hover\\:flex{x:y}", &["group", "group-hover:flex"] ; "descendant")]
+    #[test_case(
+        ".dark\\:flex:where(.dark, .dark *){x:y}",
+        &["dark:flex", "dark"]
+        ; "functional pseudo class"
+    )]
+    #[test_case(".prose :where([class~=\"a.b\"]){x:y}", &["prose"] ; "attribute selector")]
+    #[test_case("/* .comment{} */.a{content:\".b{\"}", &["a"] ; "comments and strings")]
+    #[test_case("@keyframes x{0%{x:y}12.5%{x:y}}", &[] ; "keyframes")]
+    #[test_case(".a /* .b */ .c{x:y}", &["a", "c"] ; "comment in selector")]
+    #[test_case("@import \"a.css\";.a{x:y}", &["a"] ; "import")]
+    #[test_case(".a{x:y}.a:hover{x:y}", &["a"] ; "duplicates")]
+    fn test_extract_classes(css: &str, classes: &[&str]) {
+        assert_eq!(extract_classes(css), classes);
+    }
+
+    #[test_case(r".w-1\/2{}", "w-1/2" ; "slash")]
+    #[test_case(r".w-\[500px\]{}", "w-[500px]" ; "brackets")]
+    #[test_case(r".bg-\[\#1da1f2\]{}", "bg-[#1da1f2]" ; "hash")]
+    #[test_case(r".mr-0\.5{}", "mr-0.5" ; "dot")]
+    #[test_case(r".\32xl\:block{}", "2xl:block" ; "hex escape")]
+    #[test_case(r".\32 xl\:block{}", "2xl:block" ; "hex escape with whitespace")]
+    #[test_case(r".\!mt-2{}", "!mt-2" ; "important")]
+    #[test_case(r".\000031{}", "1" ; "six hex digits")]
+    fn test_unescape(css: &str, class: &str) {
+        assert_eq!(extract_classes(css), vec![class]);
+    }
+
+    #[test_case("flex", "flex")]
+    #[test_case("md:flex", "flex")]
+    #[test_case("dark:md:hover:text-white", "text-white")]
+    #[test_case("[&>*:last-child]:rounded", "rounded")]
+    fn test_strip_variants(class: &str, utility: &str) {
+        assert_eq!(strip_variants(class), utility);
+    }
+}
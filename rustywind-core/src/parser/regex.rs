@@ -1 +1,379 @@
+//! Extract the classes of a source file with a [FinderRegex]
+//!
+//! [find_class_occurrences] returns every class of every class list the regex finds,
+//! with its location and the class list containing it. [class_counts] aggregates the
+//! occurrences by class, in the order the classes are first seen, and
+//! [parse_classes_from_file] turns that order into a map that can be used as a custom
+//! sorter.
+//!
+//! [RustyWind::find_class_occurrences](crate::app::RustyWind::find_class_occurrences)
+//! finds the occurrences with the extraction patterns and class wrapping of a
+//! [RustyWind](crate::app::RustyWind).
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::parser::{class_counts, find_class_occurrences, parse_classes_from_file};
+//! use rustywind_core::sorter::FinderRegex;
+//!
+//! let contents = "<div class=\"flex p-4\">\n  <a class=\"p-4 mt-2\"></a>\n</div>";
+//! let occurrences = find_class_occurrences(contents, &FinderRegex::DefaultRegex);
+//!
+//! assert_eq!(occurrences.len(), 4);
+//! assert_eq!(occurrences[2].class, "p-4");
+//! assert_eq!((occurrences[2].line, occurrences[2].column), (2, 13));
+//! assert_eq!(&contents[occurrences[2].class_list.clone()], "p-4 mt-2");
+//!
+//! let counts = class_counts(&occurrences);
+//! assert_eq!((counts[1].class.as_str(), counts[1].count), ("p-4", 2));
+//!
+//! let order = parse_classes_from_file(contents, &FinderRegex::DefaultRegex);
+//! assert_eq!(order["flex"], 0);
+//! assert_eq!(order["mt-2"], 2);
+//! ```
 
+use std::collections::hash_map::Entry;
+use std::ops::Range;
+
+use ahash::AHashMap as HashMap;
+use regex::{Match, Regex};
+
+use crate::class_wrapping::ClassWrapping;
+use crate::defaults::{ARGUMENTS_GROUP, STRING_LITERAL_RE};
+use crate::sorter::FinderRegex;
+
+/// A class found in a source file, with its location and the class list containing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassOccurrence {
+    /// The class as written in the source
+    pub class: String,
+    /// Byte range of the class in the source
+    pub range: Range<usize>,
+    /// 1-based line of the class
+    pub line: usize,
+    /// 1-based column (in characters) of the class
+    pub column: usize,
+    /// Byte range of the class list containing the class
+    pub class_list: Range<usize>,
+    /// Position of the class in its class list
+    pub index: usize,
+}
+
+/// How many times a class occurs, and where it occurs first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassCount {
+    pub class: String,
+    pub count: usize,
+    /// The first occurrence of the class
+    pub first: ClassOccurrence,
+}
+
+/// Finds every class of the class lists `regex` finds in the file contents, in the order
+/// they appear. Every named capture group of the regex is a class list, a regex without
+/// named groups uses the first group that matched.
+pub fn find_class_occurrences(file_contents: &str, regex: &FinderRegex) -> Vec<ClassOccurrence> {
+    find_occurrences(&[(regex, ClassWrapping::NoWrapping)], file_contents)
+}
+
+/// The classes of the occurrences with the number of times they occur, in the order they
+/// are first seen.
+pub fn class_counts(occurrences: &[ClassOccurrence]) -> Vec<ClassCount> {
+    let mut counts: Vec<ClassCount> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+
+    for occurrence in occurrences {
+        match positions.entry(&occurrence.class) {
+            Entry::Occupied(entry) => counts[*entry.get()].count += 1,
+            Entry::Vacant(entry) => {
+                entry.insert(counts.len());
+                counts.push(ClassCount {
+                    class: occurrence.class.clone(),
+                    count: 1,
+                    first: occurrence.clone(),
+                });
+            }
+        }
+    }
+
+    counts
+}
+
+/// Parses the classes of the file contents, returning each class with the order it is first
+/// seen in. The map can be used as a custom sorter with [Sorter::new](crate::sorter::Sorter::new).
+pub fn parse_classes_from_file(file_contents: &str, regex: &FinderRegex) -> HashMap<String, usize> {
+    class_counts(&find_class_occurrences(file_contents, regex))
+        .into_iter()
+        .enumerate()
+        .map(|(order, count)| (count.class, order))
+        .collect()
+}
+
+pub(crate) fn find_occurrences(
+    patterns: &[(&Regex, ClassWrapping)],
+    file_contents: &str,
+) -> Vec<ClassOccurrence> {
+    let mut cursor = LineCursor::default();
+    let mut occurrences = vec![];
+
+    for span in find_class_spans(patterns, file_contents) {
+        let class_list = span.classes.as_str();
+        let list_start = span.classes.start();
+
+        for (index, class) in unwrap_wrapped_classes(class_list, span.class_wrapping)
+            .into_iter()
+            .enumerate()
+        {
+            let start = list_start + substring_offset(class_list, class);
+            let (line, column) = cursor.line_column(file_contents, start);
+
+            occurrences.push(ClassOccurrence {
+                class: class.to_string(),
+                range: start..start + class.len(),
+                line,
+                column,
+                class_list: span.classes.range(),
+                index,
+            });
+        }
+    }
+
+    occurrences
+}
+
+/// Converts increasing byte offsets into 1-based lines and character columns, without
+/// counting the lines from the start of the file each time.
+#[derive(Default)]
+pub(crate) struct LineCursor {
+    offset: usize,
+    line: usize,
+    line_start: usize,
+}
+
+impl LineCursor {
+    pub(crate) fn line_column(&mut self, contents: &str, offset: usize) -> (usize, usize) {
+        let skipped = &contents[self.offset..offset];
+
+        if let Some(last_newline) = skipped.rfind('\n') {
+            self.line += skipped.matches('\n').count();
+            self.line_start = self.offset + last_newline + 1;
+        }
+        self.offset = offset;
+
+        let column = contents[self.line_start..offset].chars().count() + 1;
+        (self.line + 1, column)
+    }
+}
+
+/// A capture group holding classes, with the way they are wrapped.
+pub(crate) struct ClassSpan<'h> {
+    pub(crate) classes: Match<'h>,
+    pub(crate) class_wrapping: ClassWrapping,
+    pattern: usize,
+}
+
+/// Finds the capture groups holding classes for each pattern: every named group, or the
+/// first group that matched when the regex has no named groups. Overlapping groups are
+/// dropped, keeping the one that starts first, then the one of the earlier pattern.
+pub(crate) fn find_class_spans<'h>(
+    patterns: &[(&Regex, ClassWrapping)],
+    file_contents: &'h str,
+) -> Vec<ClassSpan<'h>> {
+    let mut spans = vec![];
+
+    for (pattern, (regex, class_wrapping)) in patterns.iter().enumerate() {
+        let arguments_group = regex
+            .capture_names()
+            .position(|name| name == Some(ARGUMENTS_GROUP));
+        let named_groups: Vec<usize> = regex
+            .capture_names()
+            .enumerate()
+            .filter_map(|(index, name)| name.filter(|&name| name != ARGUMENTS_GROUP).map(|_| index))
+            .collect();
+
+        for caps in regex.captures_iter(file_contents) {
+            let groups = if named_groups.is_empty() {
+                caps.iter()
+                    .enumerate()
+                    .skip(1)
+                    .filter(|(index, _group)| Some(*index) != arguments_group)
+                    .find_map(|(_index, group)| group)
+                    .into_iter()
+                    .collect()
+            } else {
+                named_groups
+                    .iter()
+                    .filter_map(|index| caps.get(*index))
+                    .collect::<Vec<_>>()
+            };
+
+            let arguments = arguments_group
+                .and_then(|index| caps.get(index))
+                .into_iter()
+                .flat_map(|arguments| string_literals(file_contents, arguments));
+
+            spans.extend(
+                groups
+                    .into_iter()
+                    .chain(arguments)
+                    .map(|classes| ClassSpan {
+                        classes,
+                        class_wrapping: *class_wrapping,
+                        pattern,
+                    }),
+            );
+        }
+    }
+
+    // stable, so the groups of a pattern starting at the same offset keep their order
+    spans.sort_by_key(|span| (span.classes.start(), span.pattern));
+
+    let mut last_end = 0;
+    spans.retain(|span| {
+        let keep = span.classes.start() >= last_end;
+        if keep {
+            last_end = span.classes.end();
+        }
+        keep
+    });
+
+    spans
+}
+
+/// The contents of each string literal in the arguments of a call.
+fn string_literals<'h>(
+    file_contents: &'h str,
+    arguments: Match<'h>,
+) -> impl Iterator<Item = Match<'h>> {
+    // keeps the offsets of the matches relative to the whole file
+    let haystack = &file_contents[..arguments.end()];
+    let mut start = arguments.start();
+
+    std::iter::from_fn(move || {
+        loop {
+            let caps = STRING_LITERAL_RE.captures_at(haystack, start)?;
+            start = caps.get(0)?.end();
+
+            if let Some(classes) = caps.iter().skip(1).flatten().next() {
+                return Some(classes);
+            }
+        }
+    })
+}
+
+pub(crate) fn unwrap_wrapped_classes(
+    class_string: &str,
+    class_wrapping: ClassWrapping,
+) -> Vec<&str> {
+    match class_wrapping {
+        ClassWrapping::NoWrapping => split_class_tokens(class_string),
+        ClassWrapping::CommaSingleQuotes => class_string
+            .split(',')
+            .flat_map(split_class_tokens)
+            .map(|class| class.trim_matches('\''))
+            .collect(),
+        ClassWrapping::CommaDoubleQuotes => class_string
+            .split(',')
+            .flat_map(split_class_tokens)
+            .map(|class| class.trim_matches('"'))
+            .collect(),
+    }
+}
+
+fn split_class_tokens(class_string: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut bracket_depth: u32 = 0;
+
+    for (index, character) in class_string.char_indices() {
+        match character {
+            '[' => bracket_depth += 1,
+            ']' => bracket_depth = bracket_depth.saturating_sub(1),
+            _ => {}
+        }
+
+        if character.is_ascii_whitespace() && bracket_depth == 0 {
+            if let Some(token_start) = start.take() {
+                tokens.push(&class_string[token_start..index]);
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+
+    if let Some(token_start) = start {
+        tokens.push(&class_string[token_start..]);
+    }
+
+    tokens
+}
+
+/// Byte offset of `inner` in `outer`, where `inner` must be a subslice of `outer`.
+pub(crate) fn substring_offset(outer: &str, inner: &str) -> usize {
+    inner.as_ptr() as usize - outer.as_ptr() as usize
+}
+
+pub(crate) fn is_ellipsis_placeholder(class: &str) -> bool {
+    class == "..." || class == "…"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_find_class_occurrences_with_named_groups() {
+        let regex = FinderRegex::CustomRegex(
+            Regex::new(r#"base: "(?P<base>[^"]*)", hover: "(?P<hover>[^"]*)""#).unwrap(),
+        );
+        let contents = r#"cva({ base: "p-4 flex", hover: "underline" })"#;
+
+        let occurrences: Vec<_> = find_class_occurrences(contents, &regex)
+            .into_iter()
+            .map(|occurrence| {
+                (
+                    occurrence.class,
+                    occurrence.range,
+                    occurrence.class_list,
+                    occurrence.index,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            occurrences,
+            vec![
+                ("p-4".to_string(), 13..16, 13..21, 0),
+                ("flex".to_string(), 17..21, 13..21, 1),
+                ("underline".to_string(), 32..41, 32..41, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_class_counts_and_order() {
+        let contents = r#"<a class="mt-2 flex"></a><b class="flex p-4 flex"></b>"#;
+        let counts: Vec<_> = class_counts(&find_class_occurrences(
+            contents,
+            &FinderRegex::DefaultRegex,
+        ))
+        .into_iter()
+        .map(|count| (count.class, count.count, count.first.range.start))
+        .collect();
+
+        assert_eq!(
+            counts,
+            vec![
+                ("mt-2".to_string(), 1, 10),
+                ("flex".to_string(), 3, 15),
+                ("p-4".to_string(), 1, 40),
+            ]
+        );
+
+        let order = parse_classes_from_file(contents, &FinderRegex::DefaultRegex);
+        assert_eq!(order.len(), 3);
+        assert_eq!(order["mt-2"], 0);
+        assert_eq!(order["flex"], 1);
+        assert_eq!(order["p-4"], 2);
+    }
+}
@@ -27,13 +27,17 @@
 //! ```
 
 use std::cmp::Ordering;
+use std::sync::Arc;
 
-use crate::class_parser::parse_class;
-use crate::property_order::get_property_index;
+use crate::class_parser::{ParsedClass, parse_class};
+use crate::property_order::{PROPERTY_ORDER, get_property_index};
 use crate::tailwind_prefix::{normalize_tailwind_prefix, normalize_tailwind_prefix_value};
+use crate::tailwind_v3;
+use crate::tailwind_version::TailwindVersion;
+use crate::utility_extensions::UtilityExtensions;
 use crate::variant_order::{
-    ARBITRARY_VARIANT_BIT, VariantInfo, calculate_variant_order, compare_variant_lists,
-    parse_variants,
+    ARBITRARY_VARIANT_BIT, VariantInfo, VariantOrder, calculate_variant_order,
+    compare_variant_lists,
 };
 
 /// Check if a variant chain contains bare group/peer variants (without modifiers).
@@ -548,6 +552,11 @@ pub struct SortKey {
     /// Whether this class is unparseable (e.g., bare group:/peer: without modifiers)
     /// Unparseable classes sort first (matching Prettier's behavior)
     pub is_unparseable: bool,
+
+    /// The Tailwind version whose ordering rules compare this key
+    /// For v3, `variant_order` uses the v3 variant bits and `property_indices` holds
+    /// the index of the v3 core plugin, then the index of the utility in the plugin
+    pub tailwind_version: TailwindVersion,
 }
 
 impl Eq for SortKey {}
@@ -794,8 +803,216 @@ fn get_utility_prefix_priority(utility: &str) -> u32 {
     100 // default for other utilities
 }
 
-impl Ord for SortKey {
-    /// Compare sort keys using Tailwind's exact algorithm with value-based sub-sorting.
+/// The comparison step of [`SortKey`]'s ordering that decided the order of two classes.
+///
+/// Steps are listed in the order they are applied, see [`SortKey::compare_with_step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComparisonStep {
+    /// Keys of different Tailwind versions, v3 keys sort before v4 keys
+    TailwindVersion,
+    /// Unparseable classes (bare `group:`/`peer:`) sort before everything else
+    Unparseable,
+    /// Base classes (no variants) sort before classes with variants
+    BaseClass,
+    /// Classes without arbitrary variants sort before classes with arbitrary variants
+    ArbitraryVariantPresence,
+    /// Arbitrary variant selectors compared by kind, then lexicographically
+    ArbitraryVariantSelector,
+    /// Known variant bits (`variant_order`) compared numerically
+    VariantOrder,
+    /// Structured variant chains compared variant by variant
+    VariantChain,
+    /// Dynamic selector variants (`data-*`, `aria-*`, `has-*`, `nth-*`) compared by value
+    SelectorDynamicVariant,
+    /// The concrete variant masks compared from the highest variant down
+    VariantMask,
+    /// CSS property indices from `PROPERTY_ORDER`, compared in order
+    PropertyIndices,
+    /// Utility prefix priority (`space-*` before `gap-*`)
+    UtilityPriority,
+    /// Declaration count, utilities with more declarations sort first
+    DeclarationCount,
+    /// Color names compared alphabetically for color utilities
+    ColorName,
+    /// Negative values sort before positive values
+    Negative,
+    /// Arbitrary vs non-arbitrary values, then numeric values
+    NumericValue,
+    /// Alphanumeric comparison of classes that both have numeric values
+    Alphanumeric,
+    /// Base names without size modifiers (`rounded-t` for `rounded-t-lg`)
+    BaseName,
+    /// Full class name, the final tiebreaker
+    ClassName,
+}
+
+impl ComparisonStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComparisonStep::TailwindVersion => "tailwind version",
+            ComparisonStep::Unparseable => "unparseable",
+            ComparisonStep::BaseClass => "base class",
+            ComparisonStep::ArbitraryVariantPresence => "arbitrary variant presence",
+            ComparisonStep::ArbitraryVariantSelector => "arbitrary variant selector",
+            ComparisonStep::VariantOrder => "variant order",
+            ComparisonStep::VariantChain => "variant chain",
+            ComparisonStep::SelectorDynamicVariant => "selector dynamic variant",
+            ComparisonStep::VariantMask => "variant mask",
+            ComparisonStep::PropertyIndices => "property indices",
+            ComparisonStep::UtilityPriority => "utility priority",
+            ComparisonStep::DeclarationCount => "declaration count",
+            ComparisonStep::ColorName => "color name",
+            ComparisonStep::Negative => "negative value",
+            ComparisonStep::NumericValue => "numeric value",
+            ComparisonStep::Alphanumeric => "alphanumeric",
+            ComparisonStep::BaseName => "base name",
+            ComparisonStep::ClassName => "class name",
+        }
+    }
+}
+
+type ComparisonFn = fn(&SortKey, &SortKey) -> Ordering;
+
+/// Steps used when both classes are unparseable.
+const UNPARSEABLE_STEPS: &[(ComparisonStep, ComparisonFn)] = &[
+    (ComparisonStep::VariantOrder, |a, z| {
+        a.variant_order.cmp(&z.variant_order)
+    }),
+    (ComparisonStep::VariantChain, |a, z| {
+        compare_variant_lists(&a.variant_chain, &z.variant_chain)
+    }),
+    (
+        ComparisonStep::PropertyIndices,
+        SortKey::compare_property_indices,
+    ),
+    (ComparisonStep::ClassName, |a, z| {
+        compare_alphanumeric(&a.class, &z.class)
+    }),
+];
+
+/// Steps used once the variants of both classes compare equal.
+const UTILITY_STEPS: &[(ComparisonStep, ComparisonFn)] = &[
+    (
+        ComparisonStep::PropertyIndices,
+        SortKey::compare_property_indices,
+    ),
+    // CRITICAL FIX: when property indices match, check utility prefix priority
+    // this fixes space-x vs gap-y ordering (both map to row-gap, but space-* has priority)
+    // must happen BEFORE numeric value comparison to prevent gap-y-0 sorting before space-x-4
+    (ComparisonStep::UtilityPriority, |a, z| {
+        // only apply prefix priority when property indices are identical
+        if a.property_indices == z.property_indices {
+            return get_utility_prefix_priority(&a.class)
+                .cmp(&get_utility_prefix_priority(&z.class));
+        }
+        Ordering::Equal
+    }),
+    // then by property count (MORE properties = earlier, matching Tailwind v4)
+    // Tailwind's: zSorting.properties.count - aSorting.properties.count
+    // means if z (other) has MORE properties, result is positive, so a (self) comes first
+    // therefore: compare other.count vs self.count (reversed)
+    (ComparisonStep::DeclarationCount, |a, z| {
+        z.property_count.cmp(&a.property_count)
+    }),
+    // then by color name alphabetically (when both are color utilities)
+    // this ensures bg-blue-500 comes before bg-red-50 (blue < red alphabetically)
+    // rather than sorting by shade number (50 < 500)
+    (ComparisonStep::ColorName, |a, z| {
+        match (extract_color_name(&a.class), extract_color_name(&z.class)) {
+            // both are color utilities - compare by color name first
+            (Some(a_color), Some(z_color)) => a_color.cmp(z_color),
+            _ => Ordering::Equal, // at least one is not a color utility, continue
+        }
+    }),
+    // then handle negative value priority
+    // negative values (-rotate-1, -skew-y-3) should sort BEFORE positive values
+    (ComparisonStep::Negative, |a, z| {
+        match (a.is_negative, z.is_negative) {
+            (true, false) => Ordering::Less,    // negative before positive
+            (false, true) => Ordering::Greater, // positive after negative
+            _ => Ordering::Equal, // both negative or both positive, continue to numeric comparison
+        }
+    }),
+    (
+        ComparisonStep::NumericValue,
+        SortKey::compare_numeric_values,
+    ),
+    // then by alphanumeric comparison for utilities with numeric values
+    // (space-* prefix priority is handled here)
+    (ComparisonStep::Alphanumeric, |a, z| {
+        match (a.numeric_value, z.numeric_value) {
+            (Some(_), Some(_)) => {
+                // first check prefix priority (space-* before gap-*)
+                let prefix_cmp = get_utility_prefix_priority(&a.class)
+                    .cmp(&get_utility_prefix_priority(&z.class));
+                if prefix_cmp != Ordering::Equal {
+                    return prefix_cmp;
+                }
+                // then use alphanumeric comparison of full class names
+                compare_alphanumeric(&a.class, &z.class)
+            }
+            // if only one has a numeric value, no preference (continue to next comparison)
+            _ => Ordering::Equal,
+        }
+    }),
+    // then by utility prefix priority (space-* before gap-* when properties match)
+    (ComparisonStep::UtilityPriority, |a, z| {
+        get_utility_prefix_priority(&a.class).cmp(&get_utility_prefix_priority(&z.class))
+    }),
+    // compare base names (extracts modifiers)
+    (ComparisonStep::BaseName, |a, z| {
+        extract_base_name(&a.class).cmp(extract_base_name(&z.class))
+    }),
+    // finally alphabetically on full name
+    (ComparisonStep::ClassName, |a, z| a.class.cmp(&z.class)),
+];
+
+/// Steps used by Tailwind v3 once the base class check passes. Values of the same
+/// utility are only told apart by the class name, the last step, which
+/// [`SortKey::compare_for_sort`] skips so they keep the order they were written in.
+const V3_STEPS: &[(ComparisonStep, ComparisonFn)] = &[
+    (ComparisonStep::VariantOrder, |a, z| {
+        a.variant_order.cmp(&z.variant_order)
+    }),
+    (ComparisonStep::ArbitraryVariantSelector, |a, z| {
+        let a_selectors = a
+            .arbitrary_variants
+            .iter()
+            .map(|s| arbitrary_variant_key(s));
+        let z_selectors = z
+            .arbitrary_variants
+            .iter()
+            .map(|s| arbitrary_variant_key(s));
+        a_selectors.cmp(z_selectors)
+    }),
+    (
+        ComparisonStep::PropertyIndices,
+        SortKey::compare_property_indices,
+    ),
+    (ComparisonStep::ClassName, |a, z| a.class.cmp(&z.class)),
+];
+
+fn apply_steps(
+    a: &SortKey,
+    z: &SortKey,
+    steps: &[(ComparisonStep, ComparisonFn)],
+) -> (Ordering, ComparisonStep) {
+    let mut last_step = ComparisonStep::ClassName;
+
+    for &(step, compare) in steps {
+        last_step = step;
+        match compare(a, z) {
+            Ordering::Equal => continue,
+            ordering => return (ordering, step),
+        }
+    }
+
+    (Ordering::Equal, last_step)
+}
+
+impl SortKey {
+    /// Compare sort keys using Tailwind's exact algorithm with value-based sub-sorting,
+    /// and return the comparison step that decided the order.
     ///
     /// Order of comparison:
     /// 1. Unparseable classes (bare group:/peer:) sort first
@@ -808,62 +1025,123 @@ impl Ord for SortKey {
     /// 8. Negative value priority (negatives before positives)
     /// 9. Numeric value (when both present - lower value first, e.g., p-4 before p-8)
     /// 10. Alphabetical (final tiebreaker)
-    fn cmp(&self, other: &Self) -> Ordering {
+    ///
+    /// Tailwind v3 keys compare by variant mask, core plugin and the utility within
+    /// the plugin after step 2, then by class name. Keys of different Tailwind
+    /// versions compare by version first.
+    ///
+    /// When the keys are equal, the last step applied is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::pattern_sorter::{ComparisonStep, PatternSorter};
+    /// use std::cmp::Ordering;
+    ///
+    /// let sorter = PatternSorter::new();
+    /// let margin = sorter.get_sort_key("m-4").unwrap();
+    /// let padding = sorter.get_sort_key("p-4").unwrap();
+    ///
+    /// assert_eq!(
+    ///     margin.compare_with_step(&padding),
+    ///     (Ordering::Less, ComparisonStep::PropertyIndices)
+    /// );
+    /// ```
+    pub fn compare_with_step(&self, other: &Self) -> (Ordering, ComparisonStep) {
+        self.compare_steps(other, V3_STEPS)
+    }
+
+    /// Compare sort keys the way the sorters order classes with a stable sort.
+    ///
+    /// The same as [`Ord`], except that Tailwind v3 keys of the same utility compare
+    /// equal, so the classes keep the order they were written in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::pattern_sorter::PatternSorter;
+    /// use rustywind_core::tailwind_version::TailwindVersion;
+    /// use std::cmp::Ordering;
+    ///
+    /// let sorter = PatternSorter::new().with_tailwind_version(TailwindVersion::V3);
+    /// let p4 = sorter.get_sort_key("p-4").unwrap();
+    /// let p2 = sorter.get_sort_key("p-2").unwrap();
+    ///
+    /// assert_eq!(p4.cmp(&p2), Ordering::Greater);
+    /// assert_eq!(p4.compare_for_sort(&p2), Ordering::Equal);
+    /// ```
+    pub fn compare_for_sort(&self, other: &Self) -> Ordering {
+        self.compare_for_sort_with_step(other).0
+    }
+
+    /// Like [`SortKey::compare_for_sort`], returning the comparison step that decided
+    /// the order.
+    pub fn compare_for_sort_with_step(&self, other: &Self) -> (Ordering, ComparisonStep) {
+        // the class name tiebreak is the last v3 step
+        self.compare_steps(other, &V3_STEPS[..V3_STEPS.len() - 1])
+    }
+
+    fn compare_steps(
+        &self,
+        other: &Self,
+        v3_steps: &[(ComparisonStep, ComparisonFn)],
+    ) -> (Ordering, ComparisonStep) {
+        if self.tailwind_version != other.tailwind_version {
+            return (
+                self.tailwind_version.cmp(&other.tailwind_version),
+                ComparisonStep::TailwindVersion,
+            );
+        }
+
         // 1. unparseable classes sort FIRST (before everything else)
         //    when BOTH are unparseable, continue with normal comparison but skip base class check
         match (self.is_unparseable, other.is_unparseable) {
-            (true, false) => return Ordering::Less, // unparseable before parseable
-            (false, true) => return Ordering::Greater, // parseable after unparseable
-            (true, true) => {
-                // both unparseable: use normal comparison (variant_order, then variant_chain, then properties)
-                // this replaces the previous alphabetical comparison
-                return self
-                    .variant_order
-                    .cmp(&other.variant_order)
-                    .then_with(|| compare_variant_lists(&self.variant_chain, &other.variant_chain))
-                    .then_with(|| {
-                        for (a_idx, b_idx) in self
-                            .property_indices
-                            .iter()
-                            .zip(other.property_indices.iter())
-                        {
-                            match a_idx.cmp(b_idx) {
-                                Ordering::Equal => continue,
-                                other => return other,
-                            }
-                        }
-                        other
-                            .property_indices
-                            .len()
-                            .cmp(&self.property_indices.len())
-                    })
-                    .then_with(|| compare_alphanumeric(&self.class, &other.class));
-            }
+            // unparseable before parseable
+            (true, false) => return (Ordering::Less, ComparisonStep::Unparseable),
+            // parseable after unparseable
+            (false, true) => return (Ordering::Greater, ComparisonStep::Unparseable),
+            // both unparseable: use normal comparison (variant_order, then variant_chain, then properties)
+            // this replaces the previous alphabetical comparison
+            (true, true) => return apply_steps(self, other, UNPARSEABLE_STEPS),
             (false, false) => {} // both parseable, continue with normal comparison
         }
 
         // 2. base classes (variant_order=0) come first
         match (self.variant_order == 0, other.variant_order == 0) {
-            (true, false) => return Ordering::Less, // base class before variant
-            (false, true) => return Ordering::Greater, // variant after base class
+            // base class before variant
+            (true, false) => return (Ordering::Less, ComparisonStep::BaseClass),
+            // variant after base class
+            (false, true) => return (Ordering::Greater, ComparisonStep::BaseClass),
             (true, true) => {} // both base classes, continue to property comparison
             (false, false) => {
                 // both have variants - continue with comparison below
             }
         }
 
+        if self.tailwind_version == TailwindVersion::V3 {
+            return apply_steps(self, other, v3_steps);
+        }
+
+        if let Some(decided) = self.compare_variants(other) {
+            return decided;
+        }
+
+        // 3. when variant masks are equivalent, Tailwind compares utility output
+        // before the final candidate-name tiebreaker
+        apply_steps(self, other, UTILITY_STEPS)
+    }
+
+    fn compare_variants(&self, other: &Self) -> Option<(Ordering, ComparisonStep)> {
         let self_has_arbitrary = self.variant_order & ARBITRARY_VARIANT_BIT != 0;
         let other_has_arbitrary = other.variant_order & ARBITRARY_VARIANT_BIT != 0;
-        let selector_dynamic_cmp =
-            compare_selector_dynamic_sequences(&self.variant_chain, &other.variant_chain);
-        let variant_mask_cmp = compare_variant_masks(&self.variant_chain, &other.variant_chain);
 
         // 2. compare by arbitrary variant presence and selectors
         // classes without arbitrary variants sort BEFORE classes with arbitrary variants
         // when both have arbitrary, compare selectors FIRST, then known variant bits
         match (self_has_arbitrary, other_has_arbitrary) {
-            (false, true) => return Ordering::Less, // no arbitrary before arbitrary
-            (true, false) => return Ordering::Greater,
+            // no arbitrary before arbitrary
+            (false, true) => Some((Ordering::Less, ComparisonStep::ArbitraryVariantPresence)),
+            (true, false) => Some((Ordering::Greater, ComparisonStep::ArbitraryVariantPresence)),
             (true, true) => {
                 // both have arbitrary variants - compare selectors FIRST
                 let a: Vec<_> = self
@@ -883,250 +1161,190 @@ impl Ord for SortKey {
                         let self_known = self.variant_order & !ARBITRARY_VARIANT_BIT;
                         let other_known = other.variant_order & !ARBITRARY_VARIANT_BIT;
                         if self_known != other_known {
-                            return self_known.cmp(&other_known);
+                            return Some((
+                                self_known.cmp(&other_known),
+                                ComparisonStep::VariantOrder,
+                            ));
                         }
                         // fall through to fine-grained comparison
+                        None
                     }
-                    other => return other,
+                    other => Some((other, ComparisonStep::ArbitraryVariantSelector)),
                 }
             }
             (false, false) => {
+                let selector_dynamic_cmp =
+                    compare_selector_dynamic_sequences(&self.variant_chain, &other.variant_chain);
                 if selector_dynamic_cmp != Ordering::Equal {
-                    return selector_dynamic_cmp;
+                    return Some((selector_dynamic_cmp, ComparisonStep::SelectorDynamicVariant));
                 }
 
                 // neither has arbitrary - compare by the concrete variant mask
+                let variant_mask_cmp =
+                    compare_variant_masks(&self.variant_chain, &other.variant_chain);
                 if variant_mask_cmp != Ordering::Equal {
-                    return variant_mask_cmp;
+                    return Some((variant_mask_cmp, ComparisonStep::VariantMask));
                 }
                 // fall through to utility output comparison
+                None
             }
         }
+    }
 
-        let property_cmp = || {
-            for (a_idx, b_idx) in self
-                .property_indices
-                .iter()
-                .zip(other.property_indices.iter())
-            {
-                match a_idx.cmp(b_idx) {
-                    Ordering::Equal => continue, // tie on this property, check next
-                    other => return other,       // found difference
-                }
+    fn compare_property_indices(&self, other: &Self) -> Ordering {
+        for (a_idx, b_idx) in self
+            .property_indices
+            .iter()
+            .zip(other.property_indices.iter())
+        {
+            match a_idx.cmp(b_idx) {
+                Ordering::Equal => continue, // tie on this property, check next
+                other => return other,       // found difference
             }
+        }
 
-            // if one has more properties, the one with MORE properties comes first
-            // this matches Tailwind's behavior where utilities with more generated CSS
-            // declarations sort before simpler utilities
-            other
-                .property_indices
-                .len()
-                .cmp(&self.property_indices.len())
-        };
+        // if one has more properties, the one with MORE properties comes first
+        // this matches Tailwind's behavior where utilities with more generated CSS
+        // declarations sort before simpler utilities
+        other
+            .property_indices
+            .len()
+            .cmp(&self.property_indices.len())
+    }
 
-        // 3. when variant masks are equivalent, Tailwind compares utility output
-        // before the final candidate-name tiebreaker
-        let ordering = property_cmp();
-
-        ordering
-            // CRITICAL FIX: when property indices match, check utility prefix priority
-            // this fixes space-x vs gap-y ordering (both map to row-gap, but space-* has priority)
-            // must happen BEFORE numeric value comparison to prevent gap-y-0 sorting before space-x-4
-            .then_with(|| {
-                // only apply prefix priority when property indices are identical
-                if self.property_indices == other.property_indices {
-                    return get_utility_prefix_priority(&self.class)
-                        .cmp(&get_utility_prefix_priority(&other.class));
-                }
-                Ordering::Equal
-            })
-            // then by property count (MORE properties = earlier, matching Tailwind v4)
-            // Tailwind's: zSorting.properties.count - aSorting.properties.count
-            // means if z (other) has MORE properties, result is positive, so a (self) comes first
-            // therefore: compare other.count vs self.count (reversed)
-            .then(other.property_count.cmp(&self.property_count))
-            // then by color name alphabetically (when both are color utilities)
-            // this ensures bg-blue-500 comes before bg-red-50 (blue < red alphabetically)
-            // rather than sorting by shade number (50 < 500)
-            .then_with(|| {
-                match (
-                    extract_color_name(&self.class),
-                    extract_color_name(&other.class),
-                ) {
-                    (Some(self_color), Some(other_color)) => {
-                        // both are color utilities - compare by color name first
-                        self_color.cmp(other_color)
+    /// Numeric and arbitrary value comparison.
+    ///
+    /// CRITICAL FIX: check arbitrary status FIRST, before numeric comparison!
+    /// this fixes the fraction vs arbitrary ordering issue (Issue 2 from FAILURE_ANALYSIS.md)
+    ///
+    /// ordering rules:
+    /// 1. non-arbitrary numerics/fractions (w-1/2, w-4) come BEFORE arbitrary values (w-[50px])
+    /// 2. arbitrary values come before/after keywords based on property (should_arbitrary_come_first)
+    /// 3. within non-arbitrary numerics/fractions, sort by numeric value (w-0 < w-1/2 < w-4)
+    /// 4. within arbitrary values, sort by extracted numeric value (w-[10px] < w-[50px])
+    ///
+    /// examples:
+    /// - w-1/2 w-4 → w-1/2 w-4 (both non-arbitrary, compare numerically: 0.5 < 4)
+    /// - w-4 w-[50px] → w-4 w-[50px] (non-arbitrary before arbitrary, even though 4 < 50)
+    /// - w-2/3 w-[50px] → w-2/3 w-[50px] (fraction before arbitrary)
+    /// - z-40 z-[-1] → z-40 z-[-1] (non-arbitrary before arbitrary)
+    /// - w-full w-[50px] → w-[50px] w-full (for w-*, arbitrary before keyword)
+    fn compare_numeric_values(&self, other: &Self) -> Ordering {
+        // check arbitrary and opacity status
+        let self_has_arbitrary = has_arbitrary_value(&self.class);
+        let other_has_arbitrary = has_arbitrary_value(&other.class);
+        let self_has_opacity = has_opacity_syntax(&self.class);
+        let other_has_opacity = has_opacity_syntax(&other.class);
+
+        // FIRST: check arbitrary vs non-arbitrary status
+        // fractions (w-1/2) are NOT arbitrary (no brackets)
+        // numerics (w-4) are NOT arbitrary
+        // arbitrary values (w-[50px]) ARE arbitrary (have brackets)
+        match (self_has_arbitrary, other_has_arbitrary) {
+            (true, false) => {
+                // self is arbitrary, other is not
+                if other.numeric_value.is_some() {
+                    // other has numeric value (fraction or numeric like w-4, w-1/2)
+                    // non-arbitrary numerics/fractions ALWAYS come before arbitrary
+                    return Ordering::Greater; // arbitrary AFTER non-arbitrary numeric
+                } else {
+                    // other is a keyword (w-full, w-auto, etc.)
+                    // use property-specific rule for arbitrary vs keyword ordering
+                    if should_arbitrary_come_first(&self.class) {
+                        return Ordering::Less; // arbitrary BEFORE keyword (e.g., w-[50px] before w-full)
+                    } else {
+                        return Ordering::Greater; // arbitrary AFTER keyword
                     }
-                    _ => Ordering::Equal, // at least one is not a color utility, continue
                 }
-            })
-            // then handle negative value priority
-            // negative values (-rotate-1, -skew-y-3) should sort BEFORE positive values
-            .then_with(|| {
-                match (self.is_negative, other.is_negative) {
-                    (true, false) => Ordering::Less,    // negative before positive
-                    (false, true) => Ordering::Greater, // positive after negative
-                    _ => Ordering::Equal, // both negative or both positive, continue to numeric comparison
-                }
-            })
-            // then handle numeric and arbitrary value comparison
-            // CRITICAL FIX: check arbitrary status FIRST, before numeric comparison!
-            // this fixes the fraction vs arbitrary ordering issue (Issue 2 from FAILURE_ANALYSIS.md)
-            //
-            // ordering rules:
-            // 1. non-arbitrary numerics/fractions (w-1/2, w-4) come BEFORE arbitrary values (w-[50px])
-            // 2. arbitrary values come before/after keywords based on property (should_arbitrary_come_first)
-            // 3. within non-arbitrary numerics/fractions, sort by numeric value (w-0 < w-1/2 < w-4)
-            // 4. within arbitrary values, sort by extracted numeric value (w-[10px] < w-[50px])
-            //
-            // examples:
-            // - w-1/2 w-4 → w-1/2 w-4 (both non-arbitrary, compare numerically: 0.5 < 4)
-            // - w-4 w-[50px] → w-4 w-[50px] (non-arbitrary before arbitrary, even though 4 < 50)
-            // - w-2/3 w-[50px] → w-2/3 w-[50px] (fraction before arbitrary)
-            // - z-40 z-[-1] → z-40 z-[-1] (non-arbitrary before arbitrary)
-            // - w-full w-[50px] → w-[50px] w-full (for w-*, arbitrary before keyword)
-            .then_with(|| {
-                // check arbitrary and opacity status
-                let self_has_arbitrary = has_arbitrary_value(&self.class);
-                let other_has_arbitrary = has_arbitrary_value(&other.class);
-                let self_has_opacity = has_opacity_syntax(&self.class);
-                let other_has_opacity = has_opacity_syntax(&other.class);
-
-                // FIRST: check arbitrary vs non-arbitrary status
-                // fractions (w-1/2) are NOT arbitrary (no brackets)
-                // numerics (w-4) are NOT arbitrary
-                // arbitrary values (w-[50px]) ARE arbitrary (have brackets)
-                match (self_has_arbitrary, other_has_arbitrary) {
-                    (true, false) => {
-                        // self is arbitrary, other is not
-                        if other.numeric_value.is_some() {
-                            // other has numeric value (fraction or numeric like w-4, w-1/2)
-                            // non-arbitrary numerics/fractions ALWAYS come before arbitrary
-                            return Ordering::Greater; // arbitrary AFTER non-arbitrary numeric
-                        } else {
-                            // other is a keyword (w-full, w-auto, etc.)
-                            // use property-specific rule for arbitrary vs keyword ordering
-                            if should_arbitrary_come_first(&self.class) {
-                                return Ordering::Less; // arbitrary BEFORE keyword (e.g., w-[50px] before w-full)
-                            } else {
-                                return Ordering::Greater; // arbitrary AFTER keyword
-                            }
-                        }
-                    }
-                    (false, true) => {
-                        // other is arbitrary, self is not
-                        if self.numeric_value.is_some() {
-                            // self has numeric value (fraction or numeric)
-                            // non-arbitrary numerics/fractions ALWAYS come before arbitrary
-                            return Ordering::Less; // non-arbitrary numeric BEFORE arbitrary
-                        } else {
-                            // self is a keyword
-                            // use property-specific rule for keyword vs arbitrary ordering
-                            if should_arbitrary_come_first(&other.class) {
-                                return Ordering::Greater; // keyword AFTER arbitrary
-                            } else {
-                                return Ordering::Less; // keyword BEFORE arbitrary
-                            }
-                        }
-                    }
-                    _ => {
-                        // both arbitrary OR both non-arbitrary - continue to numeric comparison
+            }
+            (false, true) => {
+                // other is arbitrary, self is not
+                if self.numeric_value.is_some() {
+                    // self has numeric value (fraction or numeric)
+                    // non-arbitrary numerics/fractions ALWAYS come before arbitrary
+                    return Ordering::Less; // non-arbitrary numeric BEFORE arbitrary
+                } else {
+                    // self is a keyword
+                    // use property-specific rule for keyword vs arbitrary ordering
+                    if should_arbitrary_come_first(&other.class) {
+                        return Ordering::Greater; // keyword AFTER arbitrary
+                    } else {
+                        return Ordering::Less; // keyword BEFORE arbitrary
                     }
                 }
+            }
+            _ => {
+                // both arbitrary OR both non-arbitrary - continue to numeric comparison
+            }
+        }
 
-                // SECOND: compare numeric values (for same arbitrary status)
-                // this applies to:
-                // 1. both non-arbitrary: fractions and numerics compared together (w-1/2 vs w-4)
-                // 2. both arbitrary: compare extracted numeric values (w-[50px] vs w-[100px])
-                // DON'T compare numerically if one has opacity syntax and the other doesn't
-                match (self.numeric_value, other.numeric_value) {
-                    (Some(a), Some(b)) if self_has_opacity == other_has_opacity => {
-                        // check if both are width/height utilities with base numbers
-                        let self_base = extract_base_number(&self.class);
-                        let other_base = extract_base_number(&other.class);
-
-                        match (self_base, other_base) {
-                            (
-                                Some((self_base_num, self_denom)),
-                                Some((other_base_num, other_denom)),
-                            ) => {
-                                // both have base numbers (w-1, w-1/2, w-2, etc.)
-                                // rule 1: compare by base number first (ascending)
-                                // example: w-1/3 (base 1) before w-2 (base 2)
-                                if self_base_num != other_base_num {
-                                    return self_base_num.cmp(&other_base_num);
-                                }
+        // SECOND: compare numeric values (for same arbitrary status)
+        // this applies to:
+        // 1. both non-arbitrary: fractions and numerics compared together (w-1/2 vs w-4)
+        // 2. both arbitrary: compare extracted numeric values (w-[50px] vs w-[100px])
+        // DON'T compare numerically if one has opacity syntax and the other doesn't
+        match (self.numeric_value, other.numeric_value) {
+            (Some(a), Some(b)) if self_has_opacity == other_has_opacity => {
+                // check if both are width/height utilities with base numbers
+                let self_base = extract_base_number(&self.class);
+                let other_base = extract_base_number(&other.class);
+
+                match (self_base, other_base) {
+                    (Some((self_base_num, self_denom)), Some((other_base_num, other_denom))) => {
+                        // both have base numbers (w-1, w-1/2, w-2, etc.)
+                        // rule 1: compare by base number first (ascending)
+                        // example: w-1/3 (base 1) before w-2 (base 2)
+                        if self_base_num != other_base_num {
+                            return self_base_num.cmp(&other_base_num);
+                        }
 
-                                // rule 2: within same base number, whole numbers before fractions
-                                // example: w-1 before w-1/2
-                                match (self_denom, other_denom) {
-                                    (None, Some(_)) => return Ordering::Less, // whole before fraction
-                                    (Some(_), None) => return Ordering::Greater, // fraction after whole
-                                    (Some(self_d), Some(other_d)) => {
-                                        // rule 3: both fractions with same numerator, sort by denominator ascending
-                                        // example: w-1/2 (denom 2) before w-1/3 (denom 3)
-                                        // smaller denominator = larger fraction value = comes first
-                                        if self_d != other_d {
-                                            return self_d.cmp(&other_d);
-                                        }
-                                    }
-                                    (None, None) => {
-                                        // both whole numbers with same base, equal
-                                    }
+                        // rule 2: within same base number, whole numbers before fractions
+                        // example: w-1 before w-1/2
+                        match (self_denom, other_denom) {
+                            (None, Some(_)) => return Ordering::Less, // whole before fraction
+                            (Some(_), None) => return Ordering::Greater, // fraction after whole
+                            (Some(self_d), Some(other_d)) => {
+                                // rule 3: both fractions with same numerator, sort by denominator ascending
+                                // example: w-1/2 (denom 2) before w-1/3 (denom 3)
+                                // smaller denominator = larger fraction value = comes first
+                                if self_d != other_d {
+                                    return self_d.cmp(&other_d);
                                 }
                             }
-                            _ => {
-                                // at least one doesn't have a base number, fall back to standard numeric comparison
-                                match a.partial_cmp(&b).unwrap_or(Ordering::Equal) {
-                                    Ordering::Equal => {
-                                        // numeric values are equal, continue to next tier
-                                    }
-                                    ordering => return ordering, // different numeric values
-                                }
+                            (None, None) => {
+                                // both whole numbers with same base, equal
                             }
                         }
                     }
-                    (Some(_), Some(_)) => {
-                        // different opacity status, continue to next tier
-                    }
                     _ => {
-                        // at least one doesn't have a numeric value, continue
-                    }
-                }
-
-                Ordering::Equal // fall through to next comparison tier
-            })
-            // then by alphanumeric comparison for utilities with numeric values
-            // (space-* prefix priority is handled here)
-            .then_with(|| {
-                match (self.numeric_value, other.numeric_value) {
-                    (Some(_), Some(_)) => {
-                        // first check prefix priority (space-* before gap-*)
-                        let prefix_cmp = get_utility_prefix_priority(&self.class)
-                            .cmp(&get_utility_prefix_priority(&other.class));
-                        if prefix_cmp != Ordering::Equal {
-                            return prefix_cmp;
+                        // at least one doesn't have a base number, fall back to standard numeric comparison
+                        match a.partial_cmp(&b).unwrap_or(Ordering::Equal) {
+                            Ordering::Equal => {
+                                // numeric values are equal, continue to next tier
+                            }
+                            ordering => return ordering, // different numeric values
                         }
-                        // then use alphanumeric comparison of full class names
-                        compare_alphanumeric(&self.class, &other.class)
                     }
-                    // if only one has a numeric value, no preference (continue to next comparison)
-                    _ => Ordering::Equal,
                 }
-            })
-            // then by utility prefix priority (space-* before gap-* when properties match)
-            .then_with(|| {
-                get_utility_prefix_priority(&self.class)
-                    .cmp(&get_utility_prefix_priority(&other.class))
-            })
-            // compare base names (extracts modifiers)
-            .then_with(|| {
-                let base_self = extract_base_name(&self.class);
-                let base_other = extract_base_name(&other.class);
-                base_self.cmp(base_other)
-            })
-            // finally alphabetically on full name
-            .then(self.class.cmp(&other.class))
+            }
+            (Some(_), Some(_)) => {
+                // different opacity status, continue to next tier
+            }
+            _ => {
+                // at least one doesn't have a numeric value, continue
+            }
+        }
+
+        Ordering::Equal // fall through to next comparison tier
+    }
+}
+
+impl Ord for SortKey {
+    /// Compare sort keys using Tailwind's exact algorithm with value-based sub-sorting.
+    ///
+    /// See [`SortKey::compare_with_step`] for the order of comparison.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare_with_step(other).0
     }
 }
 
@@ -1142,6 +1360,8 @@ impl PartialOrd for SortKey {
 /// collections of classes according to Tailwind's canonical ordering.
 pub struct PatternSorter {
     tailwind_prefix: Option<compact_str::CompactString>,
+    extensions: Option<Arc<UtilityExtensions>>,
+    tailwind_version: TailwindVersion,
 }
 
 impl PatternSorter {
@@ -1149,6 +1369,8 @@ impl PatternSorter {
     pub fn new() -> Self {
         Self {
             tailwind_prefix: None,
+            extensions: None,
+            tailwind_version: TailwindVersion::V4,
         }
     }
 
@@ -1158,9 +1380,43 @@ impl PatternSorter {
             tailwind_prefix: tailwind_prefix
                 .and_then(normalize_tailwind_prefix_value)
                 .map(compact_str::CompactString::new),
+            extensions: None,
+            tailwind_version: TailwindVersion::V4,
+        }
+    }
+
+    /// Use the given utility extensions on top of the built-in utility and property tables.
+    pub fn with_extensions(mut self, extensions: Option<Arc<UtilityExtensions>>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Follow the class order of the given Tailwind version.
+    pub fn with_tailwind_version(mut self, tailwind_version: TailwindVersion) -> Self {
+        self.tailwind_version = tailwind_version;
+        self
+    }
+
+    /// Get the CSS property at the given index of the property order, including any
+    /// properties added by the utility extensions.
+    ///
+    /// For Tailwind v3 this is the core plugin at the given index of
+    /// [`tailwind_v3::PLUGIN_ORDER`].
+    pub fn property_at(&self, index: usize) -> Option<&str> {
+        match (self.tailwind_version, &self.extensions) {
+            (TailwindVersion::V3, _) => tailwind_v3::PLUGIN_ORDER.get(index).copied(),
+            (TailwindVersion::V4, Some(extensions)) => {
+                extensions.property_order().get(index).map(String::as_str)
+            }
+            (TailwindVersion::V4, None) => PROPERTY_ORDER.get(index).copied(),
         }
     }
 
+    /// The variant order, knowing the variants of the enabled plugins.
+    fn variant_order(&self) -> VariantOrder<'_> {
+        VariantOrder::new(self.extensions.as_deref())
+    }
+
     /// Get the sort key for a class string.
     ///
     /// Returns `None` if the class cannot be parsed or its properties are unknown.
@@ -1187,12 +1443,23 @@ impl PatternSorter {
         let parsed = parse_class(&sort_class)?;
 
         // calculate variant order using bitwise flags
-        let variant_order = calculate_variant_order(&parsed.variants);
+        let variant_order = match self.tailwind_version {
+            TailwindVersion::V3 => {
+                // v3 only knows the important modifier as a prefix
+                if sort_class.ends_with('!') {
+                    return None;
+                }
+                tailwind_v3::calculate_variant_order(&parsed.variants)?
+            }
+            TailwindVersion::V4 => self
+                .variant_order()
+                .calculate_variant_order(&parsed.variants),
+        };
 
         let variants_left_to_right: Vec<&str> = parsed.variants.iter().rev().copied().collect();
 
         // parse variants into structured form for recursive comparison
-        let variant_chain = parse_variants(&variants_left_to_right);
+        let variant_chain = self.variant_order().parse_variants(&variants_left_to_right);
 
         // extract arbitrary variants for lexicographic tiebreaking
         // these are variants that start with '[' (e.g., [&.htmx-request], [&>*])
@@ -1202,17 +1469,21 @@ impl PatternSorter {
             .map(|v| compact_str::CompactString::new(*v))
             .collect();
 
-        // get the CSS properties this utility generates
-        let properties = parsed.get_properties()?;
-
         // get ALL property indices (not just minimum) for proper multi-property tiebreaking
         // this is crucial for utilities like rounded-t vs rounded-l that share the first property
         // but differ on the second property (e.g., border-top-left-radius ties, but
         // border-top-right-radius (190) < border-bottom-left-radius (192))
-        let property_indices: Vec<usize> = properties
-            .iter()
-            .filter_map(|&prop| get_property_index(prop))
-            .collect();
+        let property_indices: Vec<usize> = match (self.tailwind_version, &self.extensions) {
+            (TailwindVersion::V3, _) => self.get_v3_plugin_indices(&parsed)?,
+            (TailwindVersion::V4, Some(extensions)) => {
+                extensions.get_property_indices(&parsed.full_utility())?
+            }
+            (TailwindVersion::V4, None) => parsed
+                .get_properties()?
+                .iter()
+                .filter_map(|&prop| get_property_index(prop))
+                .collect(),
+        };
 
         // ensure we have at least one valid property index
         if property_indices.is_empty() {
@@ -1221,7 +1492,10 @@ impl PatternSorter {
 
         // count how many CSS declarations this utility generates
         // use the real declaration count from Tailwind (not just property count)
-        let property_count = crate::utility_map::get_declaration_count(&sort_class);
+        let property_count = match &self.extensions {
+            Some(extensions) => extensions.get_declaration_count(&sort_class),
+            None => crate::utility_map::get_declaration_count(&sort_class),
+        };
 
         // extract numeric value for value-based sub-sorting
         let numeric_value = extract_numeric_value(&sort_class);
@@ -1246,8 +1520,38 @@ impl PatternSorter {
             property_count,
             class: class_compact,
             is_unparseable,
+            tailwind_version: self.tailwind_version,
         })
     }
+
+    /// Get the index of the v3 core plugin generating the utility of a class, from the
+    /// v3 specific utilities first and then from the CSS properties of the utility,
+    /// followed by the index of the utility within the plugin.
+    fn get_v3_plugin_indices(&self, parsed: &ParsedClass) -> Option<Vec<usize>> {
+        let utility = parsed.full_utility();
+
+        let plugin = match tailwind_v3::get_utility_plugin(utility.trim_start_matches('-')) {
+            Some(plugin) => plugin,
+            None => match self
+                .extensions
+                .as_ref()
+                .and_then(|extensions| extensions.get_properties(&utility))
+            {
+                Some(properties) => properties
+                    .iter()
+                    .find_map(|property| tailwind_v3::get_property_plugin(property))?,
+                None => parsed
+                    .get_properties()?
+                    .iter()
+                    .find_map(|property| tailwind_v3::get_property_plugin(property))?,
+            },
+        };
+
+        Some(vec![
+            tailwind_v3::get_plugin_index(plugin)?,
+            tailwind_v3::get_utility_index(plugin, &utility),
+        ])
+    }
 }
 
 impl Default for PatternSorter {
@@ -1320,7 +1624,7 @@ pub fn sort_classes<'a>(classes: &[&'a str]) -> Vec<&'a str> {
     with_keys.sort_by(
         |(a_key, a_variant_order, a_class), (z_key, z_variant_order, z_class)| {
             match (a_key, z_key) {
-                (Some(a), Some(z)) => a.cmp(z),
+                (Some(a), Some(z)) => a.compare_for_sort(z),
                 (Some(_), None) if is_ellipsis_class(z_class) => Ordering::Less,
                 (Some(_), None) => Ordering::Greater, // known classes after unknown
                 (None, Some(_)) if is_ellipsis_class(a_class) => Ordering::Greater,
@@ -1353,6 +1657,7 @@ fn is_ellipsis_class(class: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::variant_order::parse_variants;
 
     #[test]
     fn test_base_classes_before_variants() {
@@ -1546,6 +1851,7 @@ mod tests {
             property_count: 1,
             class: "flex".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         let key2 = SortKey {
@@ -1558,6 +1864,7 @@ mod tests {
             property_count: 1,
             class: "md:flex".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         // base class (variant_order=0) should come before variant class
@@ -1576,6 +1883,7 @@ mod tests {
             property_count: 1,
             class: "a".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         let key2 = SortKey {
@@ -1588,6 +1896,7 @@ mod tests {
             property_count: 1,
             class: "b".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         // lower property index comes first
@@ -1606,6 +1915,7 @@ mod tests {
             property_count: 1,
             class: "a".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         let key2 = SortKey {
@@ -1618,6 +1928,7 @@ mod tests {
             property_count: 2,
             class: "b".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         // more properties come first (key2 has 2, key1 has 1, so key2 < key1)
@@ -1636,6 +1947,7 @@ mod tests {
             property_count: 1,
             class: "aaa".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         let key2 = SortKey {
@@ -1648,6 +1960,7 @@ mod tests {
             property_count: 1,
             class: "bbb".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
 
         // alphabetical tiebreaker
@@ -1781,6 +2094,7 @@ mod tests {
             property_count: 1,
             class: "p-4".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
         let key2 = SortKey {
             variant_order: 0,
@@ -1792,6 +2106,7 @@ mod tests {
             property_count: 1,
             class: "p-8".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
         assert!(key1 < key2);
 
@@ -1806,6 +2121,7 @@ mod tests {
             property_count: 1,
             class: "scale-50".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
         let key4 = SortKey {
             variant_order: 0,
@@ -1817,6 +2133,7 @@ mod tests {
             property_count: 1,
             class: "scale-110".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
         assert!(key3 < key4);
 
@@ -1831,6 +2148,7 @@ mod tests {
             property_count: 1,
             class: "p-4".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
         let key6 = SortKey {
             variant_order: 0,
@@ -1842,6 +2160,7 @@ mod tests {
             property_count: 1,
             class: "p-auto".into(),
             is_unparseable: false,
+            tailwind_version: TailwindVersion::V4,
         };
         // They should differ only by alphabetical order
         assert!(key5 < key6); // "p-4" < "p-auto" alphabetically
@@ -2222,4 +2541,30 @@ mod tests {
             "group-* variants should sort by modifier index when all at group's position"
         );
     }
+
+    #[test]
+    fn test_v3_ord_is_consistent_with_eq() {
+        let v3 = PatternSorter::new().with_tailwind_version(TailwindVersion::V3);
+        let p4 = v3.get_sort_key("p-4").unwrap();
+        let p2 = v3.get_sort_key("p-2").unwrap();
+
+        // different classes never compare equal, only the stable sort keeps their order
+        assert_ne!(p4, p2);
+        assert_eq!(p4.cmp(&p2), Ordering::Greater);
+        assert_eq!(p2.cmp(&p4), Ordering::Less);
+        assert_eq!(p4.cmp(&p4.clone()), Ordering::Equal);
+        assert_eq!(p4.compare_for_sort(&p2), Ordering::Equal);
+        let hybrid =
+            crate::hybrid_sorter::HybridSorter::new().with_tailwind_version(TailwindVersion::V3);
+        assert_eq!(hybrid.sort_classes(&["p-4", "p-2"]), vec!["p-4", "p-2"]);
+
+        // keys of different versions compare by version both ways
+        let v4_p2 = PatternSorter::new().get_sort_key("p-2").unwrap();
+        assert_eq!(p4.cmp(&v4_p2), Ordering::Less);
+        assert_eq!(v4_p2.cmp(&p4), Ordering::Greater);
+        assert_eq!(
+            v4_p2.compare_with_step(&p4).1,
+            ComparisonStep::TailwindVersion
+        );
+    }
 }
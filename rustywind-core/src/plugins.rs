@@ -0,0 +1,569 @@
+//! Knowledge packs for official Tailwind plugins
+//!
+//! Plugin utilities like `prose` or `form-input` and plugin variants like
+//! `prose-headings:` are unknown to the built-in tables, so without a pack they are
+//! sorted to the front like custom classes. Each pack is behind a cargo feature
+//! (`plugin-typography`, `plugin-forms`, `plugin-aspect-ratio` and
+//! `plugin-container-queries`, or `plugins` for all of them) and is selected at
+//! runtime with [`UtilityExtensions::add_plugin`].
+//!
+//! Plugins register their variants after the core variants, so plugin variants sort
+//! after every core variant, the same way prettier-plugin-tailwindcss orders them when
+//! the plugins are loaded.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "plugin-typography")]
+//! # {
+//! use rustywind_core::hybrid_sorter::HybridSorter;
+//! use rustywind_core::plugins::Plugin;
+//! use rustywind_core::utility_extensions::UtilityExtensions;
+//! use std::sync::Arc;
+//!
+//! let mut extensions = UtilityExtensions::new();
+//! extensions.add_plugin(Plugin::Typography);
+//!
+//! let sorter = HybridSorter::new().with_extensions(Some(Arc::new(extensions)));
+//! assert_eq!(
+//!     sorter.sort_classes(&["dark:prose-invert", "prose-lg", "prose"]),
+//!     vec!["prose", "prose-lg", "dark:prose-invert"]
+//! );
+//! # }
+//! ```
+
+use crate::utility_extensions::UtilityExtensions;
+use crate::variant_order::VARIANT_ORDER;
+
+/// An official Tailwind plugin with a knowledge pack compiled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Plugin {
+    /// `@tailwindcss/typography`: `prose`, `prose-lg`, `prose-invert`, `not-prose` and
+    /// the `prose-headings:` style element variants
+    #[cfg(feature = "plugin-typography")]
+    Typography,
+    /// `@tailwindcss/forms`: `form-input`, `form-checkbox` and the other form classes
+    #[cfg(feature = "plugin-forms")]
+    Forms,
+    /// `@tailwindcss/aspect-ratio`: `aspect-w-*`, `aspect-h-*` and `aspect-none`
+    #[cfg(feature = "plugin-aspect-ratio")]
+    AspectRatio,
+    /// `@tailwindcss/container-queries`: `@container` and the `@md:` style variants
+    #[cfg(feature = "plugin-container-queries")]
+    ContainerQueries,
+}
+
+impl Plugin {
+    /// Every plugin compiled into this build.
+    pub const ALL: &'static [Plugin] = &[
+        #[cfg(feature = "plugin-typography")]
+        Plugin::Typography,
+        #[cfg(feature = "plugin-forms")]
+        Plugin::Forms,
+        #[cfg(feature = "plugin-aspect-ratio")]
+        Plugin::AspectRatio,
+        #[cfg(feature = "plugin-container-queries")]
+        Plugin::ContainerQueries,
+    ];
+
+    /// The name used to select the plugin, e.g. in configuration.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "plugin-typography")]
+            Plugin::Typography => "typography",
+            #[cfg(feature = "plugin-forms")]
+            Plugin::Forms => "forms",
+            #[cfg(feature = "plugin-aspect-ratio")]
+            Plugin::AspectRatio => "aspect-ratio",
+            #[cfg(feature = "plugin-container-queries")]
+            Plugin::ContainerQueries => "container-queries",
+        }
+    }
+
+    /// Find a compiled in plugin by its [`name`](Self::name).
+    pub fn from_name(name: &str) -> Option<Plugin> {
+        Plugin::ALL
+            .iter()
+            .copied()
+            .find(|plugin| plugin.name() == name)
+    }
+
+    /// The named variants of the plugin, e.g. `prose-headings` or `@md`.
+    pub fn variants(&self) -> Vec<String> {
+        match *self {
+            #[cfg(feature = "plugin-typography")]
+            Plugin::Typography => typography::variants(),
+            #[cfg(feature = "plugin-forms")]
+            Plugin::Forms => vec![],
+            #[cfg(feature = "plugin-aspect-ratio")]
+            Plugin::AspectRatio => vec![],
+            #[cfg(feature = "plugin-container-queries")]
+            Plugin::ContainerQueries => container_queries::variants(),
+        }
+    }
+
+    /// The slot in [`PLUGIN_VARIANT_SLOTS`] of the plugin's variants.
+    fn variant_slot(&self) -> Option<&'static str> {
+        match *self {
+            #[cfg(feature = "plugin-typography")]
+            Plugin::Typography => Some("prose-*"),
+            #[cfg(feature = "plugin-forms")]
+            Plugin::Forms => None,
+            #[cfg(feature = "plugin-aspect-ratio")]
+            Plugin::AspectRatio => None,
+            #[cfg(feature = "plugin-container-queries")]
+            Plugin::ContainerQueries => Some("@*"),
+        }
+    }
+
+    /// Add the plugin's utilities and properties to the extensions.
+    #[allow(unused_variables)]
+    pub(crate) fn extend(&self, extensions: &mut UtilityExtensions) {
+        match *self {
+            #[cfg(feature = "plugin-typography")]
+            Plugin::Typography => typography::extend(extensions),
+            #[cfg(feature = "plugin-forms")]
+            Plugin::Forms => forms::extend(extensions),
+            #[cfg(feature = "plugin-aspect-ratio")]
+            Plugin::AspectRatio => aspect_ratio::extend(extensions),
+            #[cfg(feature = "plugin-container-queries")]
+            Plugin::ContainerQueries => container_queries::extend(extensions),
+        }
+    }
+}
+
+/// Names of the variant slots plugins add after [`VARIANT_ORDER`](crate::variant_order::VARIANT_ORDER),
+/// in order. Each slot covers a family of variants sorted by [`plugin_variant_sort_key`].
+pub(crate) const PLUGIN_VARIANT_SLOTS: &[&str] = &[
+    #[cfg(feature = "plugin-typography")]
+    "prose-*",
+    #[cfg(feature = "plugin-container-queries")]
+    "@*",
+];
+
+/// The plugin a variant belongs to, if it is a plugin variant compiled into this build.
+#[allow(unused_variables)]
+pub fn variant_plugin(variant: &str) -> Option<Plugin> {
+    let variant = variant.split_once('/').map_or(variant, |(base, _)| base);
+
+    #[cfg(feature = "plugin-typography")]
+    if typography::element_rank(variant).is_some() {
+        return Some(Plugin::Typography);
+    }
+
+    #[cfg(feature = "plugin-container-queries")]
+    if container_queries::size_key(variant).is_some() {
+        return Some(Plugin::ContainerQueries);
+    }
+
+    None
+}
+
+/// The variant order index of the variants of a plugin, after all core variants.
+pub(crate) fn plugin_variant_index(plugin: Plugin) -> Option<usize> {
+    let slot = plugin.variant_slot()?;
+
+    PLUGIN_VARIANT_SLOTS
+        .iter()
+        .position(|&s| s == slot)
+        .map(|position| VARIANT_ORDER.len() + position)
+}
+
+/// Sort key of the variants of a plugin sharing its slot, e.g. `@sm` before `@md`.
+#[allow(unused_variables)]
+pub(crate) fn plugin_variant_sort_key(plugin: Plugin, variant: &str) -> Option<(u8, &str)> {
+    match plugin {
+        #[cfg(feature = "plugin-typography")]
+        Plugin::Typography => typography::element_rank(variant).map(|rank| (rank, "")),
+        #[cfg(feature = "plugin-container-queries")]
+        Plugin::ContainerQueries => container_queries::size_key(variant),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Add a property that sorts before every built-in property.
+#[cfg(feature = "plugin-typography")]
+fn place_first(extensions: &mut UtilityExtensions, property: &str) {
+    use crate::utility_extensions::PropertyPosition;
+
+    let first = extensions.property_order()[0].clone();
+    extensions
+        .place_property(property, PropertyPosition::Before(first))
+        .expect("first property should be in the property order");
+}
+
+/// Add a property that sorts after every built-in property, used for utilities that
+/// only set custom properties. Tailwind sorts those after utilities with properties.
+#[cfg(feature = "plugin-aspect-ratio")]
+fn place_last(extensions: &mut UtilityExtensions, property: &str) {
+    use crate::utility_extensions::PropertyPosition;
+
+    let last = extensions
+        .property_order()
+        .last()
+        .expect("property order should not be empty")
+        .clone();
+    extensions
+        .place_property(property, PropertyPosition::After(last))
+        .expect("last property should be in the property order");
+}
+
+#[cfg(feature = "plugin-typography")]
+mod typography {
+    use super::place_first;
+    use crate::utility_extensions::UtilityExtensions;
+
+    /// Element variants in the order the plugin registers them.
+    const ELEMENTS: &[&str] = &[
+        "headings",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "p",
+        "a",
+        "blockquote",
+        "figure",
+        "figcaption",
+        "strong",
+        "em",
+        "kbd",
+        "code",
+        "pre",
+        "ol",
+        "ul",
+        "li",
+        "table",
+        "thead",
+        "tr",
+        "th",
+        "td",
+        "img",
+        "video",
+        "hr",
+        "lead",
+    ];
+
+    /// Color themes, which like `prose-invert` only set `--tw-prose-*` custom properties.
+    const COLORS: &[&str] = &["gray", "slate", "zinc", "neutral", "stone"];
+
+    pub(super) fn extend(extensions: &mut UtilityExtensions) {
+        // `prose` and its size modifiers are part of the built-in utility map
+
+        // `not-prose` is only used in the plugin's selectors and generates no CSS, so
+        // Tailwind doesn't know it. Keep it in front of every known utility
+        place_first(extensions, "--tw-not-prose");
+        extensions.add_utility("not-prose", ["--tw-not-prose"]);
+
+        for color in COLORS {
+            extensions.add_utility(&format!("prose-{color}"), ["--tw-prose-invert"]);
+        }
+    }
+
+    pub(super) fn variants() -> Vec<String> {
+        ELEMENTS
+            .iter()
+            .map(|element| format!("prose-{element}"))
+            .collect()
+    }
+
+    pub(super) fn element_rank(variant: &str) -> Option<u8> {
+        let element = variant.strip_prefix("prose-")?;
+        ELEMENTS
+            .iter()
+            .position(|&e| e == element)
+            .map(|rank| rank as u8)
+    }
+}
+
+#[cfg(feature = "plugin-forms")]
+mod forms {
+    use crate::utility_extensions::UtilityExtensions;
+
+    const INPUT_PROPERTIES: &[&str] = &[
+        "appearance",
+        "border-radius",
+        "border-width",
+        "border-color",
+        "background-color",
+        "padding-top",
+        "padding-right",
+        "padding-bottom",
+        "padding-left",
+        "font-size",
+        "line-height",
+        "color",
+        "opacity",
+        "box-shadow",
+        "outline",
+        "outline-offset",
+    ];
+
+    const SELECT_PROPERTIES: &[&str] = &[
+        "appearance",
+        "border-radius",
+        "border-width",
+        "border-color",
+        "background-color",
+        "background-image",
+        "background-size",
+        "background-position",
+        "background-repeat",
+        "padding-top",
+        "padding-right",
+        "padding-bottom",
+        "padding-left",
+        "font-size",
+        "line-height",
+        "box-shadow",
+        "outline",
+        "outline-offset",
+    ];
+
+    const CHECKBOX_PROPERTIES: &[&str] = &[
+        "display",
+        "height",
+        "width",
+        "flex-shrink",
+        "appearance",
+        "border-radius",
+        "border-width",
+        "border-color",
+        "background-color",
+        "background-image",
+        "background-size",
+        "background-position",
+        "background-repeat",
+        "background-origin",
+        "padding",
+        "vertical-align",
+        "color",
+        "box-shadow",
+        "outline",
+        "outline-offset",
+        "user-select",
+    ];
+
+    pub(super) fn extend(extensions: &mut UtilityExtensions) {
+        for utility in ["form-input", "form-textarea", "form-multiselect"] {
+            extensions.add_utility(utility, INPUT_PROPERTIES.iter().copied());
+        }
+
+        extensions.add_utility("form-select", SELECT_PROPERTIES.iter().copied());
+
+        for utility in ["form-checkbox", "form-radio"] {
+            extensions.add_utility(utility, CHECKBOX_PROPERTIES.iter().copied());
+        }
+    }
+}
+
+#[cfg(feature = "plugin-aspect-ratio")]
+mod aspect_ratio {
+    use super::place_last;
+    use crate::utility_extensions::UtilityExtensions;
+
+    pub(super) fn extend(extensions: &mut UtilityExtensions) {
+        place_last(extensions, "--tw-aspect-h");
+
+        extensions.add_utility(
+            "aspect-w-*",
+            [
+                "position",
+                "top",
+                "right",
+                "bottom",
+                "left",
+                "height",
+                "width",
+                "padding-bottom",
+            ],
+        );
+        // `aspect-h-*` only sets `--tw-aspect-h`
+        extensions.add_utility("aspect-h-*", ["--tw-aspect-h"]);
+        extensions.add_utility(
+            "aspect-none",
+            ["position", "inset", "height", "width", "padding-bottom"],
+        );
+    }
+}
+
+#[cfg(feature = "plugin-container-queries")]
+mod container_queries {
+    use crate::utility_extensions::UtilityExtensions;
+
+    /// Container sizes in the order the plugin sorts them, smallest first.
+    const SIZES: &[&str] = &[
+        "xs", "sm", "md", "lg", "xl", "2xl", "3xl", "4xl", "5xl", "6xl", "7xl",
+    ];
+
+    pub(super) fn extend(extensions: &mut UtilityExtensions) {
+        extensions.add_utility("@container", ["container-type"]);
+        extensions.add_utility("@container/*", ["container-type"]);
+        extensions.add_utility("@container-normal", ["container-type"]);
+        extensions.add_utility("@container-normal/*", ["container-type"]);
+    }
+
+    pub(super) fn variants() -> Vec<String> {
+        SIZES.iter().map(|size| format!("@{size}")).collect()
+    }
+
+    /// Named sizes sort by size, arbitrary sizes after them.
+    pub(super) fn size_key(variant: &str) -> Option<(u8, &str)> {
+        let size = variant.strip_prefix('@')?;
+
+        if let Some(inner) = size.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            return Some((u8::MAX, inner));
+        }
+
+        SIZES
+            .iter()
+            .position(|&s| s == size)
+            .map(|rank| (rank as u8, ""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::hybrid_sorter::HybridSorter;
+    #[allow(unused_imports)]
+    use pretty_assertions::assert_eq;
+    #[allow(unused_imports)]
+    use std::sync::Arc;
+
+    #[allow(dead_code)]
+    fn sorter_with(plugin: Plugin) -> HybridSorter {
+        let mut extensions = UtilityExtensions::new();
+        extensions.add_plugin(plugin);
+        HybridSorter::new().with_extensions(Some(Arc::new(extensions)))
+    }
+
+    #[allow(dead_code)]
+    fn sort(sorter: &HybridSorter, classes: &str) -> String {
+        let classes: Vec<&str> = classes.split_whitespace().collect();
+        sorter.sort_classes(&classes).join(" ")
+    }
+
+    #[test]
+    fn test_plugin_names_round_trip() {
+        for plugin in Plugin::ALL {
+            assert_eq!(Plugin::from_name(plugin.name()), Some(*plugin));
+        }
+        assert_eq!(Plugin::from_name("not-a-plugin"), None);
+    }
+
+    #[cfg(feature = "plugin-typography")]
+    #[test]
+    fn test_typography() {
+        let sorter = sorter_with(Plugin::Typography);
+
+        assert_eq!(
+            sort(
+                &sorter,
+                "lg:prose-xl dark:prose-invert prose-a:text-blue-600 prose-slate prose custom not-prose p-4 prose-headings:underline"
+            ),
+            "custom not-prose p-4 prose prose-slate lg:prose-xl dark:prose-invert prose-headings:underline prose-a:text-blue-600"
+        );
+    }
+
+    #[cfg(feature = "plugin-typography")]
+    #[test]
+    fn test_typography_requires_plugin() {
+        let sorter = HybridSorter::new();
+
+        assert!(sorter.get_sort_key("not-prose").is_none());
+
+        let sorter = sorter_with(Plugin::Typography);
+        assert!(sorter.get_sort_key("not-prose").is_some());
+    }
+
+    #[test]
+    fn test_plugin_variants_require_plugin() {
+        let sorter = HybridSorter::new();
+
+        assert_eq!(
+            sort(
+                &sorter,
+                "p-4 hover:flex @md:flex prose-headings:underline [&.x]:flex"
+            ),
+            "p-4 hover:flex @md:flex prose-headings:underline [&.x]:flex"
+        );
+        assert_eq!(
+            sort(&sorter, "@lg:block prose-a:underline"),
+            "@lg:block prose-a:underline"
+        );
+    }
+
+    #[cfg(all(feature = "plugin-typography", feature = "plugin-container-queries"))]
+    #[test]
+    fn test_plugin_variants_only_of_enabled_plugins() {
+        let sorter = sorter_with(Plugin::Typography);
+
+        assert_eq!(
+            sort(&sorter, "@lg:block prose-a:underline"),
+            "prose-a:underline @lg:block"
+        );
+
+        let sorter = sorter_with(Plugin::ContainerQueries);
+
+        assert_eq!(
+            sort(&sorter, "prose-a:underline @lg:block"),
+            "@lg:block prose-a:underline"
+        );
+    }
+
+    #[cfg(feature = "plugin-forms")]
+    #[test]
+    fn test_forms() {
+        let sorter = sorter_with(Plugin::Forms);
+
+        assert_eq!(
+            sort(&sorter, "rounded p-2 form-checkbox block"),
+            "form-checkbox block rounded p-2"
+        );
+        for utility in [
+            "form-input",
+            "form-textarea",
+            "form-select",
+            "form-multiselect",
+            "form-radio",
+        ] {
+            assert!(sorter.get_sort_key(utility).is_some(), "{utility}");
+        }
+    }
+
+    #[cfg(feature = "plugin-aspect-ratio")]
+    #[test]
+    fn test_aspect_ratio() {
+        let sorter = sorter_with(Plugin::AspectRatio);
+
+        assert_eq!(
+            sort(&sorter, "aspect-h-9 p-4 aspect-w-16"),
+            "aspect-w-16 p-4 aspect-h-9"
+        );
+        assert!(sorter.get_sort_key("aspect-none").is_some());
+    }
+
+    #[cfg(feature = "plugin-container-queries")]
+    #[test]
+    fn test_container_queries() {
+        let sorter = sorter_with(Plugin::ContainerQueries);
+
+        assert_eq!(
+            sort(
+                &sorter,
+                "@[400px]:flex @lg:grid dark:block @sm:flex @container"
+            ),
+            "@container dark:block @sm:flex @lg:grid @[400px]:flex"
+        );
+        assert!(sorter.get_sort_key("@container/main").is_some());
+        assert!(sorter.get_sort_key("@md/main:flex").is_some());
+        assert!(HybridSorter::new().get_sort_key("@container").is_none());
+    }
+}
@@ -1,19 +1,27 @@
 //! The module that sorts the classes in the file contents.
+mod custom_order;
+
 use std::collections::hash_map::Entry;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead as _, BufReader, Read};
+use std::io::{BufReader, Read, Write};
 use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
 
 use ahash::AHashMap as HashMap;
 
 use regex::Regex;
-use std::sync::LazyLock;
-
-use crate::defaults::RE;
-use eyre::Result;
 
-pub(crate) static SORTER_EXTRACTOR_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^\s*(\.[^\s]+)[ ]").unwrap());
+use crate::app::PATTERN_SORTER;
+use crate::class_wrapping::ClassWrapping;
+use crate::defaults::{CLASS_KEY_RE, RE};
+use crate::hybrid_sorter::HybridSorter;
+use crate::parser::css::{extract_classes, strip_variants};
+use crate::pattern_sorter::SortKey;
+use custom_order::{CustomOrder, CustomOrderWithPatternFallback};
+use eyre::{Result, WrapErr, eyre};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 
 /// Use either our default regex in [crate::defaults::RE] or a custom regex.
 #[derive(Debug, Clone)]
@@ -33,25 +41,261 @@ impl Deref for FinderRegex {
     }
 }
 
-/// Use either pattern-based sorting or a custom sorter from a CSS file.
+/// A regex finding classes, with the way the classes it finds are wrapped.
+#[derive(Debug, Clone)]
+pub struct ExtractionPattern {
+    pub regex: FinderRegex,
+    pub class_wrapping: ClassWrapping,
+}
+
+impl ExtractionPattern {
+    pub fn new(regex: FinderRegex, class_wrapping: ClassWrapping) -> Self {
+        Self {
+            regex,
+            class_wrapping,
+        }
+    }
+}
+
+/// The extraction patterns to use for each file type.
+///
+/// Each rule lists file globs and the patterns used for the files they match, the first
+/// matching rule wins. A glob without a `/` matches the file name, e.g. `*.rb`, other
+/// globs match the end of the path, e.g. `app/components/**/*.rb`. `*` matches within
+/// a path segment, `**` matches any number of segments and `?` matches one character,
+/// see [globset](https://docs.rs/globset) for the rest of the syntax.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use rustywind_core::class_wrapping::ClassWrapping;
+/// use rustywind_core::sorter::{ExtractionPattern, FinderRegex, FinderRegexSet};
+///
+/// let mut regex_set = FinderRegexSet::new();
+/// regex_set
+///     .add_rule(
+///         ["*.vue"],
+///         vec![ExtractionPattern::new(FinderRegex::DefaultRegex, ClassWrapping::NoWrapping)],
+///     )
+///     .unwrap();
+///
+/// assert!(regex_set.patterns_for(Path::new("src/App.vue")).is_some());
+/// assert!(regex_set.patterns_for(Path::new("src/main.ts")).is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FinderRegexSet {
+    rules: Vec<ExtractionRule>,
+    fallback_patterns: Vec<ExtractionPattern>,
+}
+
+#[derive(Debug, Clone)]
+struct ExtractionRule {
+    globs: GlobSet,
+    patterns: Vec<ExtractionPattern>,
+}
+
+impl FinderRegexSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in rules, which also look for `class: "..."` in Ruby and Elixir files.
+    pub fn with_defaults() -> Self {
+        let mut regex_set = Self::new();
+        regex_set.add_default_rules();
+        regex_set
+    }
+
+    /// Adds the built-in rules after the existing rules.
+    pub fn add_default_rules(&mut self) {
+        self.add_default_rules_with_regex(FinderRegex::DefaultRegex, ClassWrapping::NoWrapping);
+    }
+
+    /// Adds the built-in rules after the existing rules, using `regex` in place of the
+    /// default regex, e.g. one built with [crate::defaults::attributes_regex], and
+    /// `class_wrapping` for the classes they find.
+    pub fn add_default_rules_with_regex(
+        &mut self,
+        regex: FinderRegex,
+        class_wrapping: ClassWrapping,
+    ) {
+        self.add_rule(
+            ["*.rb", "*.erb", "*.haml", "*.ex", "*.exs", "*.heex"],
+            vec![
+                ExtractionPattern::new(regex, class_wrapping),
+                ExtractionPattern::new(
+                    FinderRegex::CustomRegex(CLASS_KEY_RE.clone()),
+                    class_wrapping,
+                ),
+            ],
+        )
+        .expect("the built-in globs should be valid");
+    }
+
+    /// Adds a rule after the existing rules.
+    ///
+    /// Returns an error if one of the globs is invalid.
+    pub fn add_rule<T: AsRef<str>>(
+        &mut self,
+        globs: impl IntoIterator<Item = T>,
+        patterns: Vec<ExtractionPattern>,
+    ) -> Result<()> {
+        let mut builder = GlobSetBuilder::new();
+
+        for glob in globs {
+            let glob = glob.as_ref();
+            // match the file name, or the end of the path
+            let anchored = format!(
+                "**/{}",
+                glob.trim_start_matches("**/").trim_start_matches('/')
+            );
+            let compiled = GlobBuilder::new(&anchored)
+                .literal_separator(true)
+                .build()
+                .wrap_err_with(|| format!("Invalid glob `{glob}`"))?;
+            builder.add(compiled);
+        }
+
+        self.rules.push(ExtractionRule {
+            globs: builder
+                .build()
+                .wrap_err("Unable to build the globs of an extraction rule")?,
+            patterns,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the patterns used for the files no rule matches, and for file contents
+    /// without a path. Without them, those use the regex of [RustyWind](crate::RustyWind).
+    pub fn set_fallback_patterns(&mut self, patterns: Vec<ExtractionPattern>) {
+        self.fallback_patterns = patterns;
+    }
+
+    pub fn fallback_patterns(&self) -> Option<&[ExtractionPattern]> {
+        (!self.fallback_patterns.is_empty()).then_some(self.fallback_patterns.as_slice())
+    }
+
+    /// The patterns of the first rule matching the path, or the fallback patterns.
+    pub fn patterns_for(&self, path: &Path) -> Option<&[ExtractionPattern]> {
+        self.rules
+            .iter()
+            .find(|rule| rule.globs.is_match(path))
+            .map(|rule| rule.patterns.as_slice())
+            .or_else(|| self.fallback_patterns())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.fallback_patterns.is_empty()
+    }
+}
+
+/// A strategy for sorting a list of classes, to plug into [RustyWind] with
+/// [Sorter::from_class_sorter].
+///
+/// [RustyWind] takes care of finding, unwrapping and deduplicating the classes, the
+/// sorter only has to put them in order. The built-in sorters implement it too.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::RustyWind;
+/// use rustywind_core::sorter::{ClassSorter, Sorter};
+///
+/// struct Alphabetical;
+///
+/// impl ClassSorter for Alphabetical {
+///     fn sort_classes<'a>(&self, classes: &[&'a str]) -> Vec<&'a str> {
+///         let mut sorted = classes.to_vec();
+///         sorted.sort();
+///         sorted
+///     }
+/// }
+///
+/// let rustywind = RustyWind::default().with_sorter(Sorter::from_class_sorter(Alphabetical));
+/// assert_eq!(rustywind.sort_classes("p-4 flex m-4"), "flex m-4 p-4");
+/// ```
+///
+/// [RustyWind]: crate::RustyWind
+pub trait ClassSorter: Send + Sync {
+    /// Returns the classes in sorted order.
+    fn sort_classes<'a>(&self, classes: &[&'a str]) -> Vec<&'a str>;
+
+    /// The rank of a class in this sorter's order, or `None` if the sorter doesn't know
+    /// the class, see [Sorter::rank]. A lower rank sorts first.
+    ///
+    /// Doesn't rank any class by default.
+    fn rank(&self, _class: &str) -> Option<Rank> {
+        None
+    }
+}
+
+impl fmt::Debug for dyn ClassSorter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClassSorter")
+    }
+}
+
+impl ClassSorter for HybridSorter {
+    fn sort_classes<'a>(&self, classes: &[&'a str]) -> Vec<&'a str> {
+        HybridSorter::sort_classes(self, classes)
+    }
+
+    fn rank(&self, class: &str) -> Option<Rank> {
+        self.get_sort_key(class).map(Rank::SortKey)
+    }
+}
+
+/// The position of a class in a [Sorter]'s order, see [Sorter::rank].
+///
+/// Ranks from the same sorter can be compared, a lower rank sorts first. Classes
+/// listed in a custom sort order rank before the classes ranked by the pattern sorter.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    /// Position in a custom sort order
+    Index(usize),
+    /// Position of the variant in [VARIANTS], then position of the class without the
+    /// variant in a custom sort order. [Sorter::CustomSorter] sorts the classes with a
+    /// variant after the listed classes, grouped by variant.
+    ///
+    /// [VARIANTS]: crate::consts::VARIANTS
+    VariantIndex(usize, usize),
+    /// Sort key from the pattern sorter
+    SortKey(SortKey),
+}
+
+/// Use either pattern-based sorting, a custom sorter from a CSS file or a custom
+/// sorting strategy.
 #[derive(Debug, Clone)]
 pub enum Sorter {
     /// Pattern-based sorting matching Tailwind CSS v4's canonical algorithm
     PatternSorter,
     /// Custom sorter loaded from a CSS file
     CustomSorter(HashMap<String, usize>),
+    /// Custom sort order for the classes it lists, pattern-based sorting for every
+    /// other class.
+    ///
+    /// Unknown classes come first in their original order, then the listed classes in
+    /// the custom order, then the remaining classes in the pattern sorter's order.
+    CustomWithPatternFallback(HashMap<String, usize>),
+    /// A sorting strategy implementing [ClassSorter]
+    ClassSorter(Arc<dyn ClassSorter>),
 }
 
+/// Deprecated: use [crate::RustyWind::rank], [Sorter::rank] or [Sorter::sort_order] instead,
+/// which don't panic. Goes through [Sorter::custom_sort_order], which warns when called
+/// directly.
+///
+/// # Panics
+///
+/// Panics when used with [Sorter::PatternSorter] or [Sorter::ClassSorter].
 impl Deref for Sorter {
     type Target = HashMap<String, usize>;
 
     fn deref(&self) -> &Self::Target {
-        match &self {
-            Self::PatternSorter => {
-                panic!("PatternSorter should not be used with HashMap-based sorting")
-            }
-            Self::CustomSorter(sorter) => sorter,
-        }
+        #[allow(deprecated)]
+        self.custom_sort_order()
     }
 }
 
@@ -60,6 +304,153 @@ impl Sorter {
         Self::CustomSorter(sorter)
     }
 
+    /// Use a custom sorting strategy.
+    pub fn from_class_sorter(sorter: impl ClassSorter + 'static) -> Self {
+        Self::ClassSorter(Arc::new(sorter))
+    }
+
+    /// The custom sort order, what [Deref] gives access to.
+    ///
+    /// # Panics
+    ///
+    /// Panics for [Sorter::PatternSorter] and [Sorter::ClassSorter].
+    #[deprecated(note = "use `Sorter::sort_order` or `Sorter::rank` instead, which don't panic")]
+    pub fn custom_sort_order(&self) -> &HashMap<String, usize> {
+        match self {
+            Self::PatternSorter => {
+                panic!("PatternSorter should not be used with HashMap-based sorting")
+            }
+            Self::ClassSorter(_) => {
+                panic!("ClassSorter should not be used with HashMap-based sorting")
+            }
+            Self::CustomSorter(sorter) | Self::CustomWithPatternFallback(sorter) => sorter,
+        }
+    }
+
+    /// The custom sort order, or `None` for [Sorter::PatternSorter] and
+    /// [Sorter::ClassSorter].
+    pub fn sort_order(&self) -> Option<&HashMap<String, usize>> {
+        match self {
+            Self::PatternSorter | Self::ClassSorter(_) => None,
+            Self::CustomSorter(sorter) | Self::CustomWithPatternFallback(sorter) => Some(sorter),
+        }
+    }
+
+    /// The classes of the custom sort order in order, or `None` for
+    /// [Sorter::PatternSorter] and [Sorter::ClassSorter].
+    pub fn sorted_classes(&self) -> Option<Vec<&str>> {
+        let mut classes: Vec<(&str, usize)> = self
+            .sort_order()?
+            .iter()
+            .map(|(class, &index)| (class.as_str(), index))
+            .collect();
+
+        classes.sort_unstable_by(|(a_class, a_index), (z_class, z_index)| {
+            a_index.cmp(z_index).then_with(|| a_class.cmp(z_class))
+        });
+
+        Some(classes.into_iter().map(|(class, _index)| class).collect())
+    }
+
+    /// Writes the custom sort order as JSON, in the `{ "sortOrder": [...] }` format the
+    /// CLI's `--config-file` option reads.
+    ///
+    /// Returns an error for [Sorter::PatternSorter] and [Sorter::ClassSorter], which
+    /// have no list of classes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::sorter::Sorter;
+    ///
+    /// let sorter = Sorter::new([("flex".to_string(), 0), ("block".to_string(), 1)].into());
+    /// let mut json = Vec::new();
+    /// sorter.write_sort_order(&mut json).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(json).unwrap(),
+    ///     "{\n  \"sortOrder\": [\n    \"flex\",\n    \"block\"\n  ]\n}\n"
+    /// );
+    /// ```
+    pub fn write_sort_order(&self, mut writer: impl Write) -> Result<()> {
+        let classes = self
+            .sorted_classes()
+            .ok_or_else(|| eyre!("only a custom sort order has a list of classes to write"))?;
+
+        writeln!(writer, "{{")?;
+        writeln!(writer, "  \"sortOrder\": [")?;
+
+        for (index, class) in classes.iter().enumerate() {
+            let separator = if index + 1 < classes.len() { "," } else { "" };
+            writeln!(writer, "    {}{separator}", json_string(class))?;
+        }
+
+        writeln!(writer, "  ]")?;
+        writeln!(writer, "}}")?;
+
+        Ok(())
+    }
+
+    /// The rank of a class in this sorter's order, or `None` if the sorter doesn't know
+    /// the class. A lower rank sorts first.
+    ///
+    /// [Sorter::PatternSorter] and the classes missing from a
+    /// [Sorter::CustomWithPatternFallback] order are ranked by the default pattern sorter,
+    /// following Tailwind v4 without a prefix. [RustyWind::rank] uses the pattern sorter
+    /// matching its options instead. A [Sorter::CustomSorter] only ranks the classes it
+    /// lists, and a [Sorter::ClassSorter] the classes its [ClassSorter::rank] ranks.
+    ///
+    /// [RustyWind::rank]: crate::RustyWind::rank
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rustywind_core::sorter::{Rank, Sorter};
+    ///
+    /// let sorter = Sorter::new([("btn".to_string(), 0)].into_iter().collect());
+    /// assert_eq!(sorter.rank("btn"), Some(Rank::Index(0)));
+    /// assert_eq!(sorter.rank("p-4"), None);
+    ///
+    /// let sorter = sorter.with_pattern_fallback();
+    /// assert!(sorter.rank("btn") < sorter.rank("p-4"));
+    /// ```
+    pub fn rank(&self, class: &str) -> Option<Rank> {
+        self.with_class_sorter(None, &PATTERN_SORTER, |sorter| sorter.rank(class))
+    }
+
+    /// Runs `f` with the [ClassSorter] of this sorter, using `pattern_sorter` for
+    /// [Sorter::PatternSorter] and the classes missing from a
+    /// [Sorter::CustomWithPatternFallback] order.
+    pub(crate) fn with_class_sorter<R>(
+        &self,
+        tailwind_prefix: Option<&str>,
+        pattern_sorter: &HybridSorter,
+        f: impl FnOnce(&dyn ClassSorter) -> R,
+    ) -> R {
+        match self {
+            Self::PatternSorter => f(pattern_sorter),
+            Self::CustomSorter(sort_order) => f(&CustomOrder {
+                sort_order,
+                tailwind_prefix,
+            }),
+            Self::CustomWithPatternFallback(sort_order) => f(&CustomOrderWithPatternFallback {
+                sort_order,
+                tailwind_prefix,
+                pattern_sorter,
+            }),
+            Self::ClassSorter(sorter) => f(sorter.as_ref()),
+        }
+    }
+
+    /// Use the pattern sorter for the classes missing from the custom sort order,
+    /// instead of the legacy variant buckets. Leaves [Sorter::PatternSorter] as is.
+    pub fn with_pattern_fallback(self) -> Self {
+        match self {
+            Self::CustomSorter(sorter) => Self::CustomWithPatternFallback(sorter),
+            sorter => sorter,
+        }
+    }
+
     /// Create the sorter from a [File]
     pub fn new_from_file(css_file: File) -> Result<Self> {
         let css_reader = BufReader::new(css_file);
@@ -67,15 +458,22 @@ impl Sorter {
     }
 
     /// Create the sorter from any [BufReader]
-    pub fn new_from_reader<T: Read>(css_file: BufReader<T>) -> Result<Self> {
-        let css_reader = BufReader::new(css_file);
-        let mut classes: HashMap<String, usize> = HashMap::new();
+    ///
+    /// Classes are ordered by their first appearance in the CSS, see
+    /// [`extract_classes`] for the CSS that is understood. A class with variants also
+    /// adds its variant-stripped utility, unless the utility appeared before, so
+    /// CSS that only contains `hover:bg-red-500` still knows where `bg-red-500` goes.
+    pub fn new_from_reader<T: Read>(mut css_file: BufReader<T>) -> Result<Self> {
+        let mut css = String::new();
+        css_file.read_to_string(&mut css)?;
 
+        let mut classes: HashMap<String, usize> = HashMap::new();
         let mut index = 0_usize;
-        for line in css_reader.lines() {
-            if let Some(captures) = SORTER_EXTRACTOR_RE.captures(&line?) {
-                let class = captures[1].trim_start_matches('.').replace('\\', "");
 
+        for class in extract_classes(&css) {
+            let utility = strip_variants(&class).to_string();
+
+            for class in [class, utility] {
                 if let Entry::Vacant(entry) = classes.entry(class) {
                     entry.insert(index);
                     index += 1;
@@ -87,19 +485,44 @@ impl Sorter {
     }
 }
 
+/// Quote and escape a string for JSON.
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if character.is_control() => {
+                json.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => json.push(character),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
     use std::io::BufReader;
+    use test_case::test_case;
 
     #[test]
     fn extracts_all_classes() {
         let css_file = std::fs::File::open("tests/fixtures/tailwind.css").unwrap();
         let classes = Sorter::new_from_file(css_file).unwrap();
 
+        // 304 classes in the fixture and 49 utilities only used with variants
         assert_eq!(classes.get("container"), Some(&0));
-        assert_eq!(classes.len(), 305);
+        assert_eq!(classes.len(), 353);
     }
 
     #[test]
@@ -236,7 +659,7 @@ mod tests {
     }
 
     #[test]
-    fn ignores_classes_without_space_before_brace() {
+    fn extracts_classes_without_space_before_brace() {
         let css_content = r#".with-space {
             color: red;
         }
@@ -247,10 +670,24 @@ mod tests {
         let reader = BufReader::new(css_content.as_bytes());
         let classes = Sorter::new_from_reader(reader).unwrap();
 
-        // only class with space should be extracted
         assert_eq!(classes.get("with-space"), Some(&0));
-        assert_eq!(classes.get("no-space"), None);
-        assert_eq!(classes.len(), 1);
+        assert_eq!(classes.get("no-space"), Some(&1));
+        assert_eq!(classes.len(), 2);
+    }
+
+    #[test]
+    fn extracts_classes_from_minified_css() {
+        let css_content = r".flex{display:flex}.mx-auto,.my-2{margin:0}@layer utilities{@media (min-width:640px){.sm\:hover\:flex:hover{display:flex}}}.p-4{padding:1rem}";
+
+        let reader = BufReader::new(css_content.as_bytes());
+        let classes = Sorter::new_from_reader(reader).unwrap();
+
+        assert_eq!(classes.get("flex"), Some(&0));
+        assert_eq!(classes.get("mx-auto"), Some(&1));
+        assert_eq!(classes.get("my-2"), Some(&2));
+        assert_eq!(classes.get("sm:hover:flex"), Some(&3));
+        assert_eq!(classes.get("p-4"), Some(&4));
+        assert_eq!(classes.len(), 5);
     }
 
     #[test]
@@ -268,11 +705,15 @@ mod tests {
         let reader = BufReader::new(css_content.as_bytes());
         let classes = Sorter::new_from_reader(reader).unwrap();
 
-        // escaped colons should have backslashes removed
+        // escaped colons should have backslashes removed, and each class is followed
+        // by its utility without variants
         assert_eq!(classes.get("hover:bg-blue-500"), Some(&0));
-        assert_eq!(classes.get("sm:text-lg"), Some(&1));
-        assert_eq!(classes.get("dark:md:hover:text-white"), Some(&2));
-        assert_eq!(classes.len(), 3);
+        assert_eq!(classes.get("bg-blue-500"), Some(&1));
+        assert_eq!(classes.get("sm:text-lg"), Some(&2));
+        assert_eq!(classes.get("text-lg"), Some(&3));
+        assert_eq!(classes.get("dark:md:hover:text-white"), Some(&4));
+        assert_eq!(classes.get("text-white"), Some(&5));
+        assert_eq!(classes.len(), 6);
     }
 
     #[test]
@@ -298,14 +739,6 @@ mod tests {
         let css_file = std::fs::File::open("tests/fixtures/tailwind-v4.css").unwrap();
         let classes = Sorter::new_from_file(css_file).unwrap();
 
-        // debug: print all classes containing "2xl" or "32xl"
-        println!("\nClasses containing '2xl' or '32xl':");
-        for key in classes.keys() {
-            if key.contains("2xl") || key.contains("32xl") {
-                println!("  - {}", key);
-            }
-        }
-
         // verify that all classes are extracted from Tailwind v4 CSS
         // test core utility classes
         assert!(
@@ -331,10 +764,10 @@ mod tests {
             "Should extract xl:hidden"
         );
 
-        // note: CSS escape \32 for digit '2' becomes '32' when backslash is removed
+        // CSS escape \32 is the digit '2'
         assert!(
-            classes.contains_key("32xl:block"),
-            "Should extract 32xl:block (CSS escape \\32xl becomes 32xl)"
+            classes.contains_key("2xl:block"),
+            "Should extract 2xl:block (CSS escape \\32xl becomes 2xl)"
         );
 
         // test state variants
@@ -433,14 +866,171 @@ mod tests {
         // - Responsive breakpoints (sm, md, lg, xl, 2xl)
         // - State variants (hover, focus, active, disabled, checked)
         // - Dark mode, group variants, and arbitrary values
+        // and 20 utilities that are only used with variants
         println!(
             "Total classes extracted from Tailwind v4: {}",
             classes.len()
         );
         assert_eq!(
             classes.len(),
-            152,
-            "Should extract exactly 152 classes from Tailwind v4 fixture"
+            172,
+            "Should extract exactly 152 classes and 20 utilities from Tailwind v4 fixture"
         );
     }
+
+    #[test]
+    fn ranks_classes_with_every_sorter() {
+        let sort_order: HashMap<String, usize> = [("btn".to_string(), 0), ("p-4".to_string(), 1)]
+            .into_iter()
+            .collect();
+
+        let rank = |sorter: &Sorter, class| sorter.rank(class);
+
+        let custom = Sorter::new(sort_order);
+        assert_eq!(rank(&custom, "p-4"), Some(Rank::Index(1)));
+        assert_eq!(rank(&custom, "m-4"), None);
+        assert!(rank(&custom, "p-4") < rank(&custom, "hover:btn"));
+        assert!(rank(&custom, "hover:p-4") < rank(&custom, "focus:btn"));
+        assert_eq!(rank(&custom, "hover:m-4"), None);
+
+        // the same order as sort_classes, with and without the prefix
+        let prefixed = |class| {
+            custom.with_class_sorter(Some("tw"), &PATTERN_SORTER, |sorter| sorter.rank(class))
+        };
+        assert_eq!(prefixed("tw:btn"), Some(Rank::Index(0)));
+        assert_eq!(prefixed("tw-p-4"), Some(Rank::Index(1)));
+        assert!(prefixed("tw:hover:p-4") < prefixed("tw:focus:btn"));
+
+        let classes = [
+            "tw:focus:btn",
+            "tw:hover:p-4",
+            "tw-p-4",
+            "tw:btn",
+            "tw:hover:m-4",
+        ];
+        let mut ranked = classes.to_vec();
+        ranked.sort_by_key(|class| prefixed(class).is_none());
+        ranked[..4].sort_by_key(|class| prefixed(class));
+        let sorted = custom.with_class_sorter(Some("tw"), &PATTERN_SORTER, |sorter| {
+            sorter.sort_classes(&classes)
+        });
+        assert_eq!(sorted, ranked);
+
+        let pattern = Sorter::PatternSorter;
+        assert!(matches!(rank(&pattern, "m-4"), Some(Rank::SortKey(_))));
+        assert!(rank(&pattern, "m-4") < rank(&pattern, "p-4"));
+        assert_eq!(rank(&pattern, "btn"), None);
+
+        let fallback = custom.with_pattern_fallback();
+        assert_eq!(rank(&fallback, "p-4"), Some(Rank::Index(1)));
+        let prefixed = |class| {
+            fallback.with_class_sorter(Some("tw"), &PATTERN_SORTER, |sorter| sorter.rank(class))
+        };
+        assert_eq!(prefixed("tw:btn"), Some(Rank::Index(0)));
+        assert_eq!(prefixed("tw-p-4"), Some(Rank::Index(1)));
+        assert!(rank(&fallback, "p-4") < rank(&fallback, "m-4"));
+        assert_eq!(rank(&fallback, "not-a-class"), None);
+        assert_eq!(
+            fallback.sort_order().map(|sort_order| sort_order.len()),
+            Some(2)
+        );
+        assert_eq!(pattern.sort_order(), None);
+
+        let class_sorter = Sorter::from_class_sorter(HybridSorter::new());
+        assert_eq!(rank(&class_sorter, "m-4"), rank(&pattern, "m-4"));
+        assert_eq!(rank(&class_sorter, "btn"), None);
+    }
+
+    #[test]
+    fn writes_sort_order_as_json() {
+        let css =
+            r#".flex{} .w-\[calc\(100\%-1rem\)\]{} .content-\[\'\"\'\]{} .hover\:flex:hover{}"#;
+        let sorter = Sorter::new_from_reader(BufReader::new(css.as_bytes())).unwrap();
+
+        let mut json = Vec::new();
+        sorter.write_sort_order(&mut json).unwrap();
+
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            r#"{
+  "sortOrder": [
+    "flex",
+    "w-[calc(100%-1rem)]",
+    "content-['\"']",
+    "hover:flex"
+  ]
+}
+"#
+        );
+        assert!(Sorter::PatternSorter.write_sort_order(Vec::new()).is_err());
+    }
+
+    #[test_case("*.rb", "app/views/show.rb", true ; "file name")]
+    #[test_case("*.rb", "app/views/show.erb", false ; "other extension")]
+    #[test_case("show.?b", "show.rb", true ; "single character")]
+    #[test_case("views/*.rb", "app/views/show.rb", true ; "path suffix")]
+    #[test_case("views/*.rb", "app/views/users/show.rb", false ; "star stays in a segment")]
+    #[test_case("app/**/*.rb", "app/views/users/show.rb", true ; "any segments")]
+    #[test_case("app/**/*.rb", "app/show.rb", true ; "no segments")]
+    #[test_case("components/**", "src/components/button/index.ts", true ; "trailing any segments")]
+    #[test_case("*.{rb,erb}", "app/views/show.erb", true ; "alternatives")]
+    #[test_case("/app/*.rb", "src/app/show.rb", true ; "leading slash")]
+    fn test_glob_matches(glob: &str, path: &str, matches: bool) {
+        let mut regex_set = FinderRegexSet::new();
+        regex_set.add_rule([glob], vec![]).unwrap();
+
+        assert_eq!(regex_set.patterns_for(Path::new(path)).is_some(), matches);
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let pattern =
+            |class_wrapping| ExtractionPattern::new(FinderRegex::DefaultRegex, class_wrapping);
+
+        let mut regex_set = FinderRegexSet::new();
+        regex_set
+            .add_rule(
+                ["special/*.rb"],
+                vec![pattern(ClassWrapping::CommaSingleQuotes)],
+            )
+            .unwrap();
+        regex_set.add_default_rules();
+
+        let patterns = regex_set.patterns_for(Path::new("special/a.rb")).unwrap();
+        assert!(matches!(
+            patterns,
+            [ExtractionPattern {
+                class_wrapping: ClassWrapping::CommaSingleQuotes,
+                ..
+            }]
+        ));
+
+        let patterns = regex_set.patterns_for(Path::new("other/a.rb")).unwrap();
+        assert_eq!(patterns.len(), 2);
+    }
+
+    #[test]
+    fn default_rules_use_the_class_wrapping() {
+        let mut regex_set = FinderRegexSet::new();
+        regex_set.add_default_rules_with_regex(
+            FinderRegex::DefaultRegex,
+            ClassWrapping::CommaSingleQuotes,
+        );
+
+        let patterns = regex_set.patterns_for(Path::new("app/a.erb")).unwrap();
+        assert!(
+            patterns
+                .iter()
+                .all(|pattern| matches!(pattern.class_wrapping, ClassWrapping::CommaSingleQuotes))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_globs() {
+        let error = FinderRegexSet::new()
+            .add_rule(["*.{rb"], vec![])
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Invalid glob `*.{rb`");
+    }
 }
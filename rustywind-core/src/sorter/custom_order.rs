@@ -0,0 +1,241 @@
+//! The [ClassSorter]s behind [Sorter::CustomSorter] and
+//! [Sorter::CustomWithPatternFallback].
+//!
+//! [Sorter::CustomSorter]: super::Sorter::CustomSorter
+//! [Sorter::CustomWithPatternFallback]: super::Sorter::CustomWithPatternFallback
+use std::borrow::Cow;
+
+use ahash::AHashMap as HashMap;
+use aho_corasick::{Anchored, Input};
+
+use super::{ClassSorter, Rank};
+use crate::consts::{VARIANT_SEARCHER, VARIANTS};
+use crate::hybrid_sorter::HybridSorter;
+use crate::parser::regex::is_ellipsis_placeholder;
+use crate::tailwind_prefix::{normalize_tailwind_prefix, normalize_tailwind_prefix_value};
+
+struct SortCandidate<'a> {
+    original: &'a str,
+    lookup: Cow<'a, str>,
+}
+
+/// Sorts the classes a custom sort order lists by their position in it. Classes with a
+/// variant are sorted after the listed classes, grouped by variant, and the classes
+/// missing from the sort order come last.
+pub(crate) struct CustomOrder<'a> {
+    pub(crate) sort_order: &'a HashMap<String, usize>,
+    pub(crate) tailwind_prefix: Option<&'a str>,
+}
+
+impl ClassSorter for CustomOrder<'_> {
+    fn sort_classes<'a>(&self, classes: &[&'a str]) -> Vec<&'a str> {
+        let candidates = classes.iter().map(|&class| SortCandidate {
+            original: class,
+            lookup: normalize_tailwind_prefix(class, self.tailwind_prefix),
+        });
+
+        let mut tailwind_classes: Vec<(&str, &usize)> = vec![];
+        let mut custom_classes: Vec<&str> = vec![];
+        let mut variants: HashMap<&str, Vec<SortCandidate>> = HashMap::new();
+
+        for candidate in candidates {
+            match self.listed_placement(&candidate) {
+                Some(size) => tailwind_classes.push((candidate.original, size)),
+                None => {
+                    let input = Input::new(candidate.lookup.as_ref()).anchored(Anchored::Yes);
+                    match VARIANT_SEARCHER.find(input) {
+                        Some(prefix_match) => {
+                            let prefix = VARIANTS[prefix_match.pattern()];
+                            variants.entry(prefix).or_default().push(candidate)
+                        }
+                        None => custom_classes.push(candidate.original),
+                    }
+                }
+            }
+        }
+
+        tailwind_classes.sort_by_key(|&(_class, class_placement)| class_placement);
+
+        let sorted_tailwind_classes: Vec<&str> = tailwind_classes
+            .iter()
+            .map(|(class, _index)| *class)
+            .collect();
+
+        let mut sorted_variant_classes = vec![];
+
+        for key in VARIANTS.iter() {
+            let (mut sorted_classes, new_custom_classes) = self.sort_variant_classes(
+                variants.remove(key).unwrap_or_default(),
+                custom_classes,
+                key.len() + 1,
+            );
+
+            sorted_variant_classes.append(&mut sorted_classes);
+            custom_classes = new_custom_classes
+        }
+
+        [
+            &sorted_tailwind_classes[..],
+            &sorted_variant_classes[..],
+            &custom_classes[..],
+        ]
+        .concat()
+    }
+
+    fn rank(&self, class: &str) -> Option<Rank> {
+        let candidate = SortCandidate {
+            original: class,
+            lookup: normalize_tailwind_prefix(class, self.tailwind_prefix),
+        };
+
+        if let Some(&index) = self.listed_placement(&candidate) {
+            return Some(Rank::Index(index));
+        }
+
+        let input = Input::new(candidate.lookup.as_ref()).anchored(Anchored::Yes);
+        let variant = VARIANT_SEARCHER.find(input)?.pattern().as_usize();
+        let class_after = VARIANTS[variant].len() + 1;
+
+        self.variant_class_placement(&candidate, class_after)
+            .map(|&index| Rank::VariantIndex(variant, index))
+    }
+}
+
+impl CustomOrder<'_> {
+    /// The position of a class in the sort order, as written or without the prefix.
+    fn listed_placement(&self, candidate: &SortCandidate) -> Option<&usize> {
+        self.sort_order
+            .get(candidate.original)
+            .or_else(|| self.sort_order.get(candidate.lookup.as_ref()))
+    }
+
+    /// The position in the sort order of a class with a variant, looked up without the
+    /// variant that ends at `class_after`.
+    fn variant_class_placement(
+        &self,
+        candidate: &SortCandidate,
+        class_after: usize,
+    ) -> Option<&usize> {
+        let normalized_remainder = candidate.lookup.get(class_after..);
+        let v4_original_remainder = self
+            .tailwind_prefix
+            .and_then(normalize_tailwind_prefix_value)
+            .and_then(|prefix| {
+                candidate
+                    .original
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_prefix(':'))?;
+                normalized_remainder
+                    .map(|normalized_remainder| format!("{prefix}:{normalized_remainder}"))
+            });
+
+        candidate
+            .original
+            .get(class_after..)
+            .and_then(|class| self.sort_order.get(class))
+            .or_else(|| {
+                v4_original_remainder
+                    .as_deref()
+                    .and_then(|class| self.sort_order.get(class))
+            })
+            .or_else(|| normalized_remainder.and_then(|class| self.sort_order.get(class)))
+    }
+
+    fn sort_variant_classes<'a>(
+        &self,
+        classes: Vec<SortCandidate<'a>>,
+        mut custom_classes: Vec<&'a str>,
+        class_after: usize,
+    ) -> (Vec<&'a str>, Vec<&'a str>) {
+        let mut tailwind_classes = Vec::with_capacity(classes.len());
+
+        for candidate in classes {
+            match self.variant_class_placement(&candidate, class_after) {
+                Some(class_placement) => {
+                    tailwind_classes.push((candidate.original, class_placement))
+                }
+                None => custom_classes.push(candidate.original),
+            }
+        }
+
+        tailwind_classes.sort_by_key(|&(_class, class_placement)| class_placement);
+
+        let sorted_classes = tailwind_classes
+            .iter()
+            .map(|(class, _index)| *class)
+            .collect();
+
+        (sorted_classes, custom_classes)
+    }
+}
+
+/// Sorts the classes a custom sort order lists by their position in it, and the other
+/// classes with the pattern sorter.
+///
+/// The result is the classes unknown to both sorters in their original order, then
+/// the listed classes, then the classes ordered by the pattern sorter, and `...`
+/// placeholders last.
+pub(crate) struct CustomOrderWithPatternFallback<'a> {
+    pub(crate) sort_order: &'a HashMap<String, usize>,
+    pub(crate) tailwind_prefix: Option<&'a str>,
+    pub(crate) pattern_sorter: &'a HybridSorter,
+}
+
+impl ClassSorter for CustomOrderWithPatternFallback<'_> {
+    fn sort_classes<'a>(&self, classes: &[&'a str]) -> Vec<&'a str> {
+        let mut listed_classes: Vec<(&str, usize)> = vec![];
+        let mut other_classes: Vec<&str> = vec![];
+        let mut placeholders: Vec<&str> = vec![];
+
+        for &class in classes {
+            if is_ellipsis_placeholder(class) {
+                placeholders.push(class);
+                continue;
+            }
+
+            let lookup = normalize_tailwind_prefix(class, self.tailwind_prefix);
+
+            match self
+                .sort_order
+                .get(class)
+                .or_else(|| self.sort_order.get(lookup.as_ref()))
+            {
+                Some(&index) => listed_classes.push((class, index)),
+                None => other_classes.push(class),
+            }
+        }
+
+        listed_classes.sort_by_key(|&(_class, index)| index);
+
+        let (unknown_classes, mut pattern_classes): (Vec<_>, Vec<_>) = other_classes
+            .into_iter()
+            .map(|class| (class, self.pattern_sorter.get_sort_key(class)))
+            .partition(|(_class, key)| key.is_none());
+
+        pattern_classes.sort_by(
+            |(_a_class, a_key), (_z_class, z_key)| match (a_key, z_key) {
+                (Some(a_key), Some(z_key)) => a_key.compare_for_sort(z_key),
+                _ => a_key.cmp(z_key),
+            },
+        );
+
+        unknown_classes
+            .into_iter()
+            .map(|(class, _key)| class)
+            .chain(listed_classes.into_iter().map(|(class, _index)| class))
+            .chain(pattern_classes.into_iter().map(|(class, _key)| class))
+            .chain(placeholders)
+            .collect()
+    }
+
+    fn rank(&self, class: &str) -> Option<Rank> {
+        let lookup = normalize_tailwind_prefix(class, self.tailwind_prefix);
+
+        self.sort_order
+            .get(class)
+            .or_else(|| self.sort_order.get(lookup.as_ref()))
+            .copied()
+            .map(Rank::Index)
+            .or_else(|| self.pattern_sorter.rank(class))
+    }
+}
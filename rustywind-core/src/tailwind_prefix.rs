@@ -34,7 +34,8 @@ pub(crate) fn normalize_tailwind_prefix_value(prefix: &str) -> Option<&str> {
     (!prefix.is_empty()).then_some(prefix)
 }
 
-fn utility_start(class: &str) -> usize {
+/// Byte index where the utility starts, after the last variant separator.
+pub(crate) fn utility_start(class: &str) -> usize {
     let mut start = 0;
     let mut bracket_depth: u32 = 0;
 
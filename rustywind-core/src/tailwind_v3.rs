@@ -0,0 +1,814 @@
+//! Class order tables for Tailwind CSS v3
+//!
+//! Tailwind v3 does not order utilities by the CSS properties they generate. Every
+//! utility belongs to a core plugin and utilities are ordered by the position of
+//! their plugin in [`PLUGIN_ORDER`], then by the order the plugin registers them in
+//! (`p` before `px` before `pt`). Utilities registered together share a position,
+//! so prettier-plugin-tailwindcss v0.4 keeps them in the order they were written.
+//!
+//! Variants work the same as in v4: every variant in [`VARIANT_ORDER`] sets a bit,
+//! and classes are ordered by the resulting mask after all classes without variants.
+//!
+//! The plugin of a utility comes from the v3 specific utilities first (e.g.
+//! `bg-opacity-*`, `space-x-*`, `ring`) and then from the CSS properties the utility
+//! generates in the [`UtilityMap`](crate::utility_map::UtilityMap).
+//!
+//! Source: https://github.com/tailwindlabs/tailwindcss/blob/v3.4.17/src/corePlugins.js
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::pattern_sorter::PatternSorter;
+//! use rustywind_core::tailwind_version::TailwindVersion;
+//!
+//! let sorter = PatternSorter::new().with_tailwind_version(TailwindVersion::V3);
+//!
+//! // bg-opacity-* only exists in v3
+//! assert!(sorter.get_sort_key("bg-opacity-50").is_some());
+//!
+//! // utilities of a plugin are ordered as the plugin registers them
+//! let p4 = sorter.get_sort_key("p-4").unwrap();
+//! let px2 = sorter.get_sort_key("px-2").unwrap();
+//! assert_eq!(p4.cmp(&px2), std::cmp::Ordering::Less);
+//!
+//! // and values of the same utility keep their order when sorting
+//! let p2 = sorter.get_sort_key("p-2").unwrap();
+//! assert_eq!(p4.compare_for_sort(&p2), std::cmp::Ordering::Equal);
+//! ```
+
+use crate::variant_order::ARBITRARY_VARIANT_BIT;
+
+/// The order of the Tailwind v3 core plugins that generate utilities.
+pub const PLUGIN_ORDER: &[&str] = &[
+    "container",
+    "accessibility",
+    "pointerEvents",
+    "visibility",
+    "position",
+    "inset",
+    "isolation",
+    "zIndex",
+    "order",
+    "gridColumn",
+    "gridColumnStart",
+    "gridColumnEnd",
+    "gridRow",
+    "gridRowStart",
+    "gridRowEnd",
+    "float",
+    "clear",
+    "margin",
+    "boxSizing",
+    "lineClamp",
+    "display",
+    "aspectRatio",
+    "size",
+    "height",
+    "maxHeight",
+    "minHeight",
+    "width",
+    "minWidth",
+    "maxWidth",
+    "flex",
+    "flexShrink",
+    "flexGrow",
+    "flexBasis",
+    "tableLayout",
+    "captionSide",
+    "borderCollapse",
+    "borderSpacing",
+    "transformOrigin",
+    "translate",
+    "rotate",
+    "skew",
+    "scale",
+    "transform",
+    "animation",
+    "cursor",
+    "touchAction",
+    "userSelect",
+    "resize",
+    "scrollSnapType",
+    "scrollSnapAlign",
+    "scrollSnapStop",
+    "scrollMargin",
+    "scrollPadding",
+    "listStylePosition",
+    "listStyleType",
+    "listStyleImage",
+    "appearance",
+    "columns",
+    "breakBefore",
+    "breakInside",
+    "breakAfter",
+    "gridAutoColumns",
+    "gridAutoFlow",
+    "gridAutoRows",
+    "gridTemplateColumns",
+    "gridTemplateRows",
+    "flexDirection",
+    "flexWrap",
+    "placeContent",
+    "placeItems",
+    "alignContent",
+    "alignItems",
+    "justifyContent",
+    "justifyItems",
+    "gap",
+    "space",
+    "divideWidth",
+    "divideStyle",
+    "divideColor",
+    "divideOpacity",
+    "placeSelf",
+    "alignSelf",
+    "justifySelf",
+    "overflow",
+    "overscrollBehavior",
+    "scrollBehavior",
+    "textOverflow",
+    "hyphens",
+    "whitespace",
+    "textWrap",
+    "wordBreak",
+    "borderRadius",
+    "borderWidth",
+    "borderStyle",
+    "borderColor",
+    "borderOpacity",
+    "backgroundColor",
+    "backgroundOpacity",
+    "backgroundImage",
+    "gradientColorStops",
+    "boxDecorationBreak",
+    "backgroundSize",
+    "backgroundAttachment",
+    "backgroundClip",
+    "backgroundPosition",
+    "backgroundRepeat",
+    "backgroundOrigin",
+    "fill",
+    "stroke",
+    "strokeWidth",
+    "objectFit",
+    "objectPosition",
+    "padding",
+    "textAlign",
+    "textIndent",
+    "verticalAlign",
+    "fontFamily",
+    "fontSize",
+    "fontWeight",
+    "textTransform",
+    "fontStyle",
+    "fontVariantNumeric",
+    "lineHeight",
+    "letterSpacing",
+    "textColor",
+    "textOpacity",
+    "textDecoration",
+    "textDecorationColor",
+    "textDecorationStyle",
+    "textDecorationThickness",
+    "textUnderlineOffset",
+    "fontSmoothing",
+    "placeholderColor",
+    "placeholderOpacity",
+    "caretColor",
+    "accentColor",
+    "opacity",
+    "backgroundBlendMode",
+    "mixBlendMode",
+    "boxShadow",
+    "boxShadowColor",
+    "outlineStyle",
+    "outlineWidth",
+    "outlineOffset",
+    "outlineColor",
+    "ringWidth",
+    "ringColor",
+    "ringOpacity",
+    "ringOffsetWidth",
+    "ringOffsetColor",
+    "blur",
+    "brightness",
+    "contrast",
+    "dropShadow",
+    "grayscale",
+    "hueRotate",
+    "invert",
+    "saturate",
+    "sepia",
+    "filter",
+    "backdropBlur",
+    "backdropBrightness",
+    "backdropContrast",
+    "backdropGrayscale",
+    "backdropHueRotate",
+    "backdropInvert",
+    "backdropOpacity",
+    "backdropSaturate",
+    "backdropSepia",
+    "backdropFilter",
+    "transitionProperty",
+    "transitionDelay",
+    "transitionDuration",
+    "transitionTimingFunction",
+    "willChange",
+    "content",
+    "forcedColorAdjust",
+];
+
+/// The order of the Tailwind v3 variants.
+///
+/// `group`, `peer`, `supports`, `has`, `aria`, `data`, `min` and `max` stand for every
+/// variant starting with them, e.g. `group-hover` or `aria-checked`.
+pub const VARIANT_ORDER: &[&str] = &[
+    "*",
+    // pseudo elements
+    "first-letter",
+    "first-line",
+    "marker",
+    "selection",
+    "file",
+    "placeholder",
+    "backdrop",
+    "before",
+    "after",
+    // pseudo classes
+    "first",
+    "last",
+    "only",
+    "odd",
+    "even",
+    "first-of-type",
+    "last-of-type",
+    "only-of-type",
+    "visited",
+    "target",
+    "open",
+    "default",
+    "checked",
+    "indeterminate",
+    "placeholder-shown",
+    "autofill",
+    "optional",
+    "required",
+    "valid",
+    "invalid",
+    "in-range",
+    "out-of-range",
+    "read-only",
+    "empty",
+    "focus-within",
+    "hover",
+    "focus",
+    "focus-visible",
+    "active",
+    "enabled",
+    "disabled",
+    "group",
+    "peer",
+    // direction and media
+    "ltr",
+    "rtl",
+    "motion-safe",
+    "motion-reduce",
+    "dark",
+    "print",
+    "sm",
+    "md",
+    "lg",
+    "xl",
+    "2xl",
+    "min",
+    "max",
+    "supports",
+    "has",
+    "aria",
+    "data",
+    "portrait",
+    "landscape",
+    "contrast-more",
+    "contrast-less",
+    "forced-colors",
+];
+
+/// Variants standing for every variant that starts with them and a dash.
+const FUNCTIONAL_VARIANTS: &[&str] = &[
+    "group", "peer", "supports", "has", "aria", "data", "min", "max",
+];
+
+/// Utilities that only exist in v3, or belong to a different plugin than their CSS
+/// properties suggest. A trailing `*` matches by prefix, the longest prefix wins.
+const UTILITY_PLUGINS: &[(&str, &str)] = &[
+    ("container", "container"),
+    ("sr-only", "accessibility"),
+    ("not-sr-only", "accessibility"),
+    ("line-clamp-*", "lineClamp"),
+    ("size-*", "size"),
+    ("flex-grow", "flexGrow"),
+    ("flex-grow-*", "flexGrow"),
+    ("flex-shrink", "flexShrink"),
+    ("flex-shrink-*", "flexShrink"),
+    ("transform", "transform"),
+    ("transform-cpu", "transform"),
+    ("transform-gpu", "transform"),
+    ("transform-none", "transform"),
+    ("space-x-reverse", "space"),
+    ("space-y-reverse", "space"),
+    ("space-x-*", "space"),
+    ("space-y-*", "space"),
+    ("divide-x", "divideWidth"),
+    ("divide-y", "divideWidth"),
+    ("divide-x-reverse", "divideWidth"),
+    ("divide-y-reverse", "divideWidth"),
+    ("divide-opacity-*", "divideOpacity"),
+    ("divide-x-*", "divideWidth"),
+    ("divide-y-*", "divideWidth"),
+    ("truncate", "textOverflow"),
+    ("overflow-ellipsis", "textOverflow"),
+    ("border-opacity-*", "borderOpacity"),
+    ("bg-opacity-*", "backgroundOpacity"),
+    ("bg-gradient-to-*", "backgroundImage"),
+    ("decoration-slice", "boxDecorationBreak"),
+    ("decoration-clone", "boxDecorationBreak"),
+    ("text-opacity-*", "textOpacity"),
+    ("placeholder-opacity-*", "placeholderOpacity"),
+    ("placeholder-*", "placeholderColor"),
+    ("ring", "ringWidth"),
+    ("ring-inset", "ringWidth"),
+    ("ring-opacity-*", "ringOpacity"),
+    ("filter", "filter"),
+    ("filter-none", "filter"),
+    ("backdrop-filter", "backdropFilter"),
+    ("backdrop-filter-none", "backdropFilter"),
+    ("outline-none", "outlineStyle"),
+    ("content-none", "content"),
+];
+
+/// The utilities of the core plugins registering more than one, in the order they
+/// are registered. A utility belongs to the longest entry it equals or continues
+/// with a dash, e.g. `rounded-tl-lg` to `rounded-tl`.
+const PLUGIN_UTILITIES: &[(&str, &[&str])] = &[
+    (
+        "inset",
+        &[
+            "inset", "inset-x", "inset-y", "start", "end", "top", "right", "bottom", "left",
+        ],
+    ),
+    (
+        "margin",
+        &["m", "mx", "my", "ms", "me", "mt", "mr", "mb", "ml"],
+    ),
+    (
+        "scrollMargin",
+        &[
+            "scroll-m",
+            "scroll-mx",
+            "scroll-my",
+            "scroll-ms",
+            "scroll-me",
+            "scroll-mt",
+            "scroll-mr",
+            "scroll-mb",
+            "scroll-ml",
+        ],
+    ),
+    (
+        "scrollPadding",
+        &[
+            "scroll-p",
+            "scroll-px",
+            "scroll-py",
+            "scroll-ps",
+            "scroll-pe",
+            "scroll-pt",
+            "scroll-pr",
+            "scroll-pb",
+            "scroll-pl",
+        ],
+    ),
+    ("translate", &["translate-x", "translate-y"]),
+    ("skew", &["skew-x", "skew-y"]),
+    ("scale", &["scale", "scale-x", "scale-y"]),
+    ("gap", &["gap", "gap-x", "gap-y"]),
+    ("space", &["space-x", "space-y"]),
+    ("divideWidth", &["divide-x", "divide-y"]),
+    ("overflow", &["overflow", "overflow-x", "overflow-y"]),
+    (
+        "overscrollBehavior",
+        &["overscroll", "overscroll-x", "overscroll-y"],
+    ),
+    (
+        "borderRadius",
+        &[
+            "rounded",
+            "rounded-s",
+            "rounded-e",
+            "rounded-t",
+            "rounded-r",
+            "rounded-b",
+            "rounded-l",
+            "rounded-ss",
+            "rounded-se",
+            "rounded-ee",
+            "rounded-es",
+            "rounded-tl",
+            "rounded-tr",
+            "rounded-br",
+            "rounded-bl",
+        ],
+    ),
+    (
+        "borderWidth",
+        &[
+            "border", "border-x", "border-y", "border-s", "border-e", "border-t", "border-r",
+            "border-b", "border-l",
+        ],
+    ),
+    (
+        "borderColor",
+        &[
+            "border", "border-x", "border-y", "border-s", "border-e", "border-t", "border-r",
+            "border-b", "border-l",
+        ],
+    ),
+    (
+        "padding",
+        &["p", "px", "py", "ps", "pe", "pt", "pr", "pb", "pl"],
+    ),
+];
+
+/// Get the index of a plugin in [`PLUGIN_ORDER`].
+pub fn get_plugin_index(plugin: &str) -> Option<usize> {
+    PLUGIN_ORDER.iter().position(|&p| p == plugin)
+}
+
+/// Get the plugin of a utility that only exists in v3 or belongs to a different
+/// plugin than its CSS properties suggest.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::tailwind_v3::get_utility_plugin;
+///
+/// assert_eq!(get_utility_plugin("bg-opacity-50"), Some("backgroundOpacity"));
+/// assert_eq!(get_utility_plugin("space-x-4"), Some("space"));
+/// assert_eq!(get_utility_plugin("p-4"), None);
+/// ```
+pub fn get_utility_plugin(utility: &str) -> Option<&'static str> {
+    if let Some(&(_, plugin)) = UTILITY_PLUGINS.iter().find(|(u, _)| *u == utility) {
+        return Some(plugin);
+    }
+
+    UTILITY_PLUGINS
+        .iter()
+        .filter_map(|&(pattern, plugin)| Some((pattern.strip_suffix('*')?, plugin)))
+        .filter(|(prefix, _)| utility.len() > prefix.len() && utility.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, plugin)| plugin)
+}
+
+/// Get the position of a utility among the utilities its plugin registers.
+///
+/// Returns 0 for the utilities of plugins registering a single utility.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::tailwind_v3::get_utility_index;
+///
+/// assert!(get_utility_index("padding", "p-4") < get_utility_index("padding", "px-2"));
+/// assert!(get_utility_index("margin", "-mx-2") < get_utility_index("margin", "mt-2"));
+/// assert_eq!(get_utility_index("display", "flex"), 0);
+/// ```
+pub fn get_utility_index(plugin: &str, utility: &str) -> usize {
+    let utility = utility.trim_start_matches('-');
+
+    let Some((_, utilities)) = PLUGIN_UTILITIES.iter().find(|(p, _)| *p == plugin) else {
+        return 0;
+    };
+
+    utilities
+        .iter()
+        .enumerate()
+        .filter(|(_, base)| {
+            utility
+                .strip_prefix(**base)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+        .max_by_key(|(_, base)| base.len())
+        .map_or(0, |(index, _)| index)
+}
+
+/// Get the plugin generating a CSS property in v3.
+///
+/// Returns `None` for properties no v3 core plugin generates.
+pub fn get_property_plugin(property: &str) -> Option<&'static str> {
+    let plugin = match property {
+        "pointer-events" => "pointerEvents",
+        "visibility" => "visibility",
+        "position" => "position",
+        "inset" | "inset-inline" | "inset-block" | "inset-inline-start" | "inset-inline-end"
+        | "top" | "right" | "bottom" | "left" => "inset",
+        "isolation" => "isolation",
+        "z-index" => "zIndex",
+        "order" => "order",
+        "grid-column" => "gridColumn",
+        "grid-column-start" => "gridColumnStart",
+        "grid-column-end" => "gridColumnEnd",
+        "grid-row" => "gridRow",
+        "grid-row-start" => "gridRowStart",
+        "grid-row-end" => "gridRowEnd",
+        "float" => "float",
+        "clear" => "clear",
+        "--tw-container-component" => "container",
+        "box-sizing" => "boxSizing",
+        "display" => "display",
+        "aspect-ratio" => "aspectRatio",
+        "height" => "height",
+        "max-height" => "maxHeight",
+        "min-height" => "minHeight",
+        "width" => "width",
+        "min-width" => "minWidth",
+        "max-width" => "maxWidth",
+        "flex" => "flex",
+        "flex-shrink" => "flexShrink",
+        "flex-grow" => "flexGrow",
+        "flex-basis" => "flexBasis",
+        "table-layout" => "tableLayout",
+        "caption-side" => "captionSide",
+        "border-collapse" => "borderCollapse",
+        "border-spacing" => "borderSpacing",
+        "transform-origin" => "transformOrigin",
+        "translate" | "--tw-translate-x" | "--tw-translate-y" => "translate",
+        "rotate" => "rotate",
+        "--tw-skew-x" | "--tw-skew-y" => "skew",
+        "scale" | "--tw-scale-x" | "--tw-scale-y" => "scale",
+        "transform" => "transform",
+        "animation" => "animation",
+        "cursor" => "cursor",
+        "--tw-pan-x" | "--tw-pan-y" | "--tw-pinch-zoom" | "touch-action" => "touchAction",
+        "user-select" => "userSelect",
+        "resize" => "resize",
+        "scroll-snap-type" | "--tw-scroll-snap-strictness" => "scrollSnapType",
+        "scroll-snap-align" => "scrollSnapAlign",
+        "scroll-snap-stop" => "scrollSnapStop",
+        "list-style-position" => "listStylePosition",
+        "list-style-type" => "listStyleType",
+        "list-style-image" => "listStyleImage",
+        "appearance" => "appearance",
+        "columns" => "columns",
+        "break-before" => "breakBefore",
+        "break-inside" => "breakInside",
+        "break-after" => "breakAfter",
+        "grid-auto-columns" => "gridAutoColumns",
+        "grid-auto-flow" => "gridAutoFlow",
+        "grid-auto-rows" => "gridAutoRows",
+        "grid-template-columns" => "gridTemplateColumns",
+        "grid-template-rows" => "gridTemplateRows",
+        "flex-direction" => "flexDirection",
+        "flex-wrap" => "flexWrap",
+        "place-content" => "placeContent",
+        "place-items" => "placeItems",
+        "align-content" => "alignContent",
+        "align-items" => "alignItems",
+        "justify-content" => "justifyContent",
+        "justify-items" => "justifyItems",
+        "gap" | "column-gap" | "row-gap" => "gap",
+        "--tw-space-x-reverse" | "--tw-space-y-reverse" => "space",
+        "divide-x-width" | "divide-y-width" | "--tw-divide-x-reverse" | "--tw-divide-y-reverse" => {
+            "divideWidth"
+        }
+        "divide-style" => "divideStyle",
+        "divide-color" => "divideColor",
+        "place-self" => "placeSelf",
+        "align-self" => "alignSelf",
+        "justify-self" => "justifySelf",
+        "overflow" | "overflow-x" | "overflow-y" => "overflow",
+        "overscroll-behavior" | "overscroll-behavior-x" | "overscroll-behavior-y" => {
+            "overscrollBehavior"
+        }
+        "scroll-behavior" => "scrollBehavior",
+        "text-overflow" => "textOverflow",
+        "hyphens" => "hyphens",
+        "white-space" => "whitespace",
+        "text-wrap" => "textWrap",
+        "word-break" | "overflow-wrap" => "wordBreak",
+        "border-opacity" => "borderOpacity",
+        "background-color" => "backgroundColor",
+        "background-opacity" => "backgroundOpacity",
+        "background-image" | "--tw-gradient-position" => "backgroundImage",
+        "box-decoration-break" => "boxDecorationBreak",
+        "background-size" => "backgroundSize",
+        "background-attachment" => "backgroundAttachment",
+        "background-clip" => "backgroundClip",
+        "background-position" => "backgroundPosition",
+        "background-repeat" => "backgroundRepeat",
+        "background-origin" => "backgroundOrigin",
+        "fill" => "fill",
+        "stroke" => "stroke",
+        "stroke-width" => "strokeWidth",
+        "object-fit" => "objectFit",
+        "object-position" => "objectPosition",
+        "text-align" => "textAlign",
+        "text-indent" => "textIndent",
+        "vertical-align" => "verticalAlign",
+        "font-family" => "fontFamily",
+        "font-size" => "fontSize",
+        "font-weight" => "fontWeight",
+        "text-transform" => "textTransform",
+        "font-style" => "fontStyle",
+        "font-variant-numeric" => "fontVariantNumeric",
+        "line-height" => "lineHeight",
+        "letter-spacing" => "letterSpacing",
+        "color" => "textColor",
+        "text-decoration-line" => "textDecoration",
+        "text-decoration-color" => "textDecorationColor",
+        "text-decoration-style" => "textDecorationStyle",
+        "text-decoration-thickness" => "textDecorationThickness",
+        "text-underline-offset" => "textUnderlineOffset",
+        "-webkit-font-smoothing" => "fontSmoothing",
+        "placeholder-color" => "placeholderColor",
+        "caret-color" => "caretColor",
+        "accent-color" => "accentColor",
+        "opacity" => "opacity",
+        "background-blend-mode" => "backgroundBlendMode",
+        "mix-blend-mode" => "mixBlendMode",
+        "box-shadow" | "--tw-shadow" => "boxShadow",
+        "--tw-shadow-color" => "boxShadowColor",
+        "outline" | "outline-style" => "outlineStyle",
+        "outline-width" => "outlineWidth",
+        "outline-offset" => "outlineOffset",
+        "outline-color" => "outlineColor",
+        "--tw-ring-shadow" | "--tw-ring-offset-shadow" | "--tw-ring-inset" => "ringWidth",
+        "--tw-ring-color" => "ringColor",
+        "--tw-ring-offset-width" => "ringOffsetWidth",
+        "--tw-ring-offset-color" => "ringOffsetColor",
+        "--tw-blur" => "blur",
+        "--tw-brightness" => "brightness",
+        "--tw-contrast" => "contrast",
+        "--tw-drop-shadow" => "dropShadow",
+        "--tw-grayscale" => "grayscale",
+        "--tw-hue-rotate" => "hueRotate",
+        "--tw-invert" => "invert",
+        "--tw-saturate" => "saturate",
+        "--tw-sepia" => "sepia",
+        "filter" => "filter",
+        "--tw-backdrop-blur" => "backdropBlur",
+        "--tw-backdrop-brightness" => "backdropBrightness",
+        "--tw-backdrop-contrast" => "backdropContrast",
+        "--tw-backdrop-grayscale" => "backdropGrayscale",
+        "--tw-backdrop-hue-rotate" => "backdropHueRotate",
+        "--tw-backdrop-invert" => "backdropInvert",
+        "--tw-backdrop-opacity" => "backdropOpacity",
+        "--tw-backdrop-saturate" => "backdropSaturate",
+        "--tw-backdrop-sepia" => "backdropSepia",
+        "backdrop-filter" => "backdropFilter",
+        "transition-property" => "transitionProperty",
+        "transition-delay" => "transitionDelay",
+        "transition-duration" => "transitionDuration",
+        "transition-timing-function" => "transitionTimingFunction",
+        "will-change" => "willChange",
+        "content" => "content",
+        "forced-color-adjust" => "forcedColorAdjust",
+        property if property.starts_with("margin") => "margin",
+        property if property.starts_with("padding") => "padding",
+        property if property.starts_with("scroll-margin") => "scrollMargin",
+        property if property.starts_with("scroll-padding") => "scrollPadding",
+        property if property.starts_with("--tw-gradient-") => "gradientColorStops",
+        property if property.starts_with("border") && property.ends_with("radius") => {
+            "borderRadius"
+        }
+        property if property.starts_with("border") && property.ends_with("width") => "borderWidth",
+        property if property.starts_with("border") && property.ends_with("style") => "borderStyle",
+        property if property.starts_with("border") && property.ends_with("color") => "borderColor",
+        _ => return None,
+    };
+
+    Some(plugin)
+}
+
+/// Get the bit of a variant in [`VARIANT_ORDER`].
+pub fn get_variant_index(variant: &str) -> Option<usize> {
+    let variant = variant.split_once('/').map_or(variant, |(base, _)| base);
+
+    if let Some(index) = VARIANT_ORDER.iter().position(|&v| v == variant) {
+        return Some(index);
+    }
+
+    let functional = FUNCTIONAL_VARIANTS.iter().find(|&&base| {
+        variant
+            .strip_prefix(base)
+            .is_some_and(|rest| rest.len() > 1 && rest.starts_with('-'))
+    })?;
+
+    VARIANT_ORDER.iter().position(|v| v == functional)
+}
+
+/// Get the variant at the given bit of [`VARIANT_ORDER`].
+pub fn variant_at(index: usize) -> Option<&'static str> {
+    VARIANT_ORDER.get(index).copied()
+}
+
+/// Calculate the variant mask of a class from its variants.
+///
+/// Arbitrary variants set the arbitrary variant bit, so they sort after every known
+/// variant. Returns `None` if a variant is unknown, Tailwind v3 generates no CSS for
+/// such classes.
+pub fn calculate_variant_order(variants: &[&str]) -> Option<u128> {
+    variants.iter().try_fold(0u128, |order, variant| {
+        if variant.starts_with('[') {
+            return Some(order | ARBITRARY_VARIANT_BIT);
+        }
+
+        get_variant_index(variant).map(|index| order | (1u128 << index))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    #[test]
+    fn test_tables_fit_variant_bits() {
+        assert!(VARIANT_ORDER.len() < ARBITRARY_VARIANT_BIT.trailing_zeros() as usize);
+    }
+
+    #[test]
+    fn test_utility_plugins_exist() {
+        for (utility, plugin) in UTILITY_PLUGINS {
+            assert!(get_plugin_index(plugin).is_some(), "{utility}: {plugin}");
+        }
+    }
+
+    #[test_case("placeholder-opacity-50", Some("placeholderOpacity") ; "longest prefix wins")]
+    #[test_case("placeholder-red-500", Some("placeholderColor") ; "prefix")]
+    #[test_case("ring", Some("ringWidth") ; "exact")]
+    #[test_case("divide-x", Some("divideWidth") ; "exact divide")]
+    #[test_case("space-x", None ; "prefix needs a value")]
+    fn test_get_utility_plugin(utility: &str, plugin: Option<&str>) {
+        assert_eq!(get_utility_plugin(utility), plugin);
+    }
+
+    #[test]
+    fn test_plugin_utilities_exist() {
+        for (plugin, _) in PLUGIN_UTILITIES {
+            assert!(get_plugin_index(plugin).is_some(), "{plugin}");
+        }
+    }
+
+    #[test_case("padding", "p-4", 0)]
+    #[test_case("padding", "pt-2", 5)]
+    #[test_case("margin", "-mx-2", 1)]
+    #[test_case("inset", "inset-x-0", 1)]
+    #[test_case("inset", "top-0", 5)]
+    #[test_case("borderRadius", "rounded-lg", 0 ; "rounded-lg is not rounded-l")]
+    #[test_case("borderRadius", "rounded-tl-lg", 11 ; "longest entry wins")]
+    #[test_case("borderColor", "border-t-red-500", 5)]
+    #[test_case("display", "flex", 0 ; "single utility plugin")]
+    fn test_get_utility_index(plugin: &str, utility: &str, index: usize) {
+        assert_eq!(get_utility_index(plugin, utility), index);
+    }
+
+    #[test_case("margin-top", Some("margin"))]
+    #[test_case("border-top-left-radius", Some("borderRadius"))]
+    #[test_case("border-inline-start-color", Some("borderColor"))]
+    #[test_case("color", Some("textColor"))]
+    #[test_case("--tw-gradient-from", Some("gradientColorStops"))]
+    #[test_case("mask-image", None)]
+    fn test_get_property_plugin(property: &str, plugin: Option<&str>) {
+        assert_eq!(get_property_plugin(property), plugin);
+    }
+
+    #[test_case("hover", Some(35))]
+    #[test_case("group-hover", get_variant_index("group"))]
+    #[test_case("peer-focus/name", get_variant_index("peer"))]
+    #[test_case("aria-checked", get_variant_index("aria"))]
+    #[test_case("group", Some(41))]
+    #[test_case("group-", None)]
+    #[test_case("not-first", None)]
+    fn test_get_variant_index(variant: &str, index: Option<usize>) {
+        assert_eq!(get_variant_index(variant), index);
+    }
+
+    #[test]
+    fn test_calculate_variant_order() {
+        assert_eq!(calculate_variant_order(&[]), Some(0));
+        assert_eq!(
+            calculate_variant_order(&["hover", "md"]),
+            Some(
+                (1 << get_variant_index("hover").unwrap())
+                    | (1 << get_variant_index("md").unwrap())
+            )
+        );
+        assert_eq!(
+            calculate_variant_order(&["[&>*]"]),
+            Some(ARBITRARY_VARIANT_BIT)
+        );
+        assert_eq!(calculate_variant_order(&["unknown"]), None);
+    }
+}
@@ -0,0 +1,20 @@
+/// The Tailwind CSS version whose class order the pattern sorter follows.
+///
+/// Tailwind v4 orders utilities by the CSS properties they generate, Tailwind v3
+/// (with prettier-plugin-tailwindcss v0.4 and earlier) orders them by the core plugin
+/// that generates them. See [`tailwind_v3`](crate::tailwind_v3) for the v3 tables.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TailwindVersion {
+    V3,
+    #[default]
+    V4,
+}
+
+impl TailwindVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TailwindVersion::V3 => "3",
+            TailwindVersion::V4 => "4",
+        }
+    }
+}
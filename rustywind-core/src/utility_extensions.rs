@@ -0,0 +1,374 @@
+//! Runtime extensions to the built-in utility and property tables
+//!
+//! The [`UtilityMap`](crate::utility_map::UtilityMap), the declaration counts and the
+//! [`PROPERTY_ORDER`] are compiled into RustyWind. [`UtilityExtensions`] layers
+//! additional entries on top of them, so new Tailwind utilities, plugin utilities or
+//! local ordering fixes can be used without waiting for a release.
+//!
+//! Entries in the extensions always take precedence over the built-in tables.
+//!
+//! # Examples
+//!
+//! ```
+//! use rustywind_core::pattern_sorter::PatternSorter;
+//! use rustywind_core::utility_extensions::{PropertyPosition, UtilityExtensions};
+//! use std::sync::Arc;
+//!
+//! let mut extensions = UtilityExtensions::new();
+//! extensions
+//!     .place_property("--tw-prose", PropertyPosition::After("display".to_string()))
+//!     .unwrap();
+//! extensions.add_utility("prose", ["--tw-prose"]);
+//! extensions.add_utility("scrollbar-*", ["overflow"]);
+//!
+//! let sorter = PatternSorter::new().with_extensions(Some(Arc::new(extensions)));
+//! assert!(sorter.get_sort_key("prose").is_some());
+//! assert!(sorter.get_sort_key("scrollbar-thin").is_some());
+//! ```
+
+use ahash::AHashMap as HashMap;
+use eyre::{Result, eyre};
+
+use crate::plugins::Plugin;
+use crate::property_order::PROPERTY_ORDER;
+use crate::utility_map::{UTILITY_MAP, get_declaration_count};
+
+/// Where a property is placed in the property order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyPosition {
+    /// Right before the given property
+    Before(String),
+    /// Right after the given property
+    After(String),
+}
+
+/// Utilities, declaration counts, property order entries and plugins layered on top
+/// of the built-in tables.
+#[derive(Debug, Clone)]
+pub struct UtilityExtensions {
+    /// Exact utility matches
+    exact: HashMap<String, Vec<String>>,
+    /// Utility prefixes from `prefix-*` entries, longest prefix first
+    patterns: Vec<(String, Vec<String>)>,
+    declaration_counts: HashMap<String, usize>,
+    plugins: Vec<Plugin>,
+    /// The full property order, starting out as [`PROPERTY_ORDER`]
+    property_order: Vec<String>,
+    property_indices: HashMap<String, usize>,
+}
+
+impl UtilityExtensions {
+    /// Create empty extensions, using the built-in tables unchanged.
+    pub fn new() -> Self {
+        let property_order: Vec<String> = PROPERTY_ORDER.iter().map(|p| p.to_string()).collect();
+        let property_indices = index_properties(&property_order);
+
+        Self {
+            exact: HashMap::new(),
+            patterns: Vec::new(),
+            declaration_counts: HashMap::new(),
+            plugins: Vec::new(),
+            property_order,
+            property_indices,
+        }
+    }
+
+    /// Map a utility to the CSS properties it generates, replacing any built-in mapping.
+    ///
+    /// A utility ending in `-*` matches every utility starting with the text before
+    /// the `*`, e.g. `scrollbar-*` matches `scrollbar-thin`.
+    pub fn add_utility<I, S>(&mut self, utility: &str, properties: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let properties = properties.into_iter().map(Into::into).collect();
+
+        match utility.strip_suffix('*') {
+            Some(prefix) => {
+                self.patterns.retain(|(existing, _)| existing != prefix);
+                self.patterns.push((prefix.to_string(), properties));
+                self.patterns
+                    .sort_by(|(a, _), (z, _)| z.len().cmp(&a.len()).then_with(|| a.cmp(z)));
+            }
+            None => {
+                self.exact.insert(utility.to_string(), properties);
+            }
+        }
+
+        self
+    }
+
+    /// Set the number of CSS declarations a utility generates, used to order
+    /// utilities that share the same properties.
+    ///
+    /// Like [`add_utility`](Self::add_utility), a trailing `-*` matches by prefix.
+    pub fn set_declaration_count(&mut self, utility: &str, count: usize) -> &mut Self {
+        self.declaration_counts.insert(utility.to_string(), count);
+        self
+    }
+
+    /// Add the utilities, variants and properties of an official Tailwind plugin.
+    ///
+    /// Entries added before the plugin are overridden by it, entries added after it
+    /// override the plugin's entries.
+    pub fn add_plugin(&mut self, plugin: Plugin) -> &mut Self {
+        if !self.has_plugin(plugin) {
+            self.plugins.push(plugin);
+            plugin.extend(self);
+        }
+
+        self
+    }
+
+    /// Whether the plugin was added with [`add_plugin`](Self::add_plugin).
+    pub fn has_plugin(&self, plugin: Plugin) -> bool {
+        self.plugins.contains(&plugin)
+    }
+
+    /// The plugins added with [`add_plugin`](Self::add_plugin), in the order they were added.
+    pub fn plugins(&self) -> &[Plugin] {
+        &self.plugins
+    }
+
+    /// Add a property to the property order, or move an existing one, relative to
+    /// another property.
+    ///
+    /// Returns an error if the anchor property is not in the property order.
+    pub fn place_property(&mut self, property: &str, position: PropertyPosition) -> Result<()> {
+        let anchor = match &position {
+            PropertyPosition::Before(anchor) | PropertyPosition::After(anchor) => anchor,
+        };
+
+        if anchor == property {
+            return Err(eyre!(
+                "property `{property}` can't be placed relative to itself"
+            ));
+        }
+
+        if !self.property_indices.contains_key(anchor) {
+            return Err(eyre!(
+                "can't place property `{property}`, `{anchor}` is not in the property order"
+            ));
+        }
+
+        self.property_order.retain(|existing| existing != property);
+
+        let anchor_index = self
+            .property_order
+            .iter()
+            .position(|existing| existing == anchor)
+            .expect("anchor property should still be in the property order");
+
+        let index = match position {
+            PropertyPosition::Before(_) => anchor_index,
+            PropertyPosition::After(_) => anchor_index + 1,
+        };
+
+        self.property_order.insert(index, property.to_string());
+        self.property_indices = index_properties(&self.property_order);
+
+        Ok(())
+    }
+
+    /// Whether no utilities, declaration counts or property order entries were added.
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty()
+            && self.patterns.is_empty()
+            && self.declaration_counts.is_empty()
+            && self.plugins.is_empty()
+            && self.property_order.len() == PROPERTY_ORDER.len()
+            && self
+                .property_order
+                .iter()
+                .zip(PROPERTY_ORDER)
+                .all(|(a, z)| a == z)
+    }
+
+    /// The full property order, including added and moved properties.
+    pub fn property_order(&self) -> &[String] {
+        &self.property_order
+    }
+
+    /// Get the index of a CSS property in the extended property order.
+    pub fn get_property_index(&self, property: &str) -> Option<usize> {
+        self.property_indices.get(property).copied()
+    }
+
+    /// Get the indices of the properties a utility generates, looking up the
+    /// extensions first and then the built-in utility map.
+    ///
+    /// Returns `None` if the utility is unknown or none of its properties are in the
+    /// property order.
+    pub fn get_property_indices(&self, utility: &str) -> Option<Vec<usize>> {
+        let indices: Vec<usize> = match self.get_properties(utility) {
+            Some(properties) => properties
+                .iter()
+                .filter_map(|property| self.get_property_index(property))
+                .collect(),
+            None => UTILITY_MAP
+                .get_properties(utility)?
+                .iter()
+                .filter_map(|property| self.get_property_index(property))
+                .collect(),
+        };
+
+        (!indices.is_empty()).then_some(indices)
+    }
+
+    /// Get the properties added for a utility, without looking at the built-in utility map.
+    pub fn get_properties(&self, utility: &str) -> Option<&[String]> {
+        if let Some(properties) = self.exact.get(utility) {
+            return Some(properties);
+        }
+
+        self.patterns
+            .iter()
+            .find(|(prefix, _)| utility.len() > prefix.len() && utility.starts_with(prefix))
+            .map(|(_, properties)| properties.as_slice())
+    }
+
+    /// Get the number of declarations a utility generates, looking up the extensions
+    /// first and then the built-in declaration counts.
+    pub fn get_declaration_count(&self, utility: &str) -> usize {
+        let base_utility = utility.split(':').next_back().unwrap_or(utility);
+
+        if let Some(&count) = self.declaration_counts.get(base_utility) {
+            return count;
+        }
+
+        let pattern_count = self
+            .declaration_counts
+            .iter()
+            .filter_map(|(pattern, &count)| Some((pattern.strip_suffix('*')?, count)))
+            .filter(|(prefix, _)| base_utility.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len());
+
+        match pattern_count {
+            Some((_, count)) => count,
+            None => get_declaration_count(utility),
+        }
+    }
+}
+
+impl Default for UtilityExtensions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn index_properties(property_order: &[String]) -> HashMap<String, usize> {
+    property_order
+        .iter()
+        .enumerate()
+        .map(|(index, property)| (property.clone(), index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property_order::get_property_index;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_empty_extensions_match_built_in_tables() {
+        let extensions = UtilityExtensions::new();
+
+        assert!(extensions.is_empty());
+        assert_eq!(
+            extensions.get_property_index("margin"),
+            get_property_index("margin")
+        );
+        assert_eq!(extensions.get_declaration_count("ring-2"), 3);
+        assert_eq!(
+            extensions.get_property_indices("p-4"),
+            get_property_index("padding").map(|index| vec![index])
+        );
+    }
+
+    #[test]
+    fn test_utilities_override_built_in_map() {
+        let mut extensions = UtilityExtensions::new();
+        extensions.add_utility("flex", ["margin"]);
+        extensions.add_utility("scrollbar-*", ["padding"]);
+
+        assert!(!extensions.is_empty());
+        assert_eq!(
+            extensions.get_property_indices("flex"),
+            get_property_index("margin").map(|index| vec![index])
+        );
+        assert_eq!(
+            extensions.get_properties("scrollbar-thin"),
+            Some(&["padding".to_string()][..])
+        );
+        assert_eq!(extensions.get_properties("scrollbar-"), None);
+        assert_eq!(extensions.get_properties("unknown"), None);
+    }
+
+    #[test]
+    fn test_longest_pattern_wins() {
+        let mut extensions = UtilityExtensions::new();
+        extensions.add_utility("a-*", ["margin"]);
+        extensions.add_utility("a-b-*", ["padding"]);
+
+        assert_eq!(
+            extensions.get_properties("a-b-c"),
+            Some(&["padding".to_string()][..])
+        );
+        assert_eq!(
+            extensions.get_properties("a-c"),
+            Some(&["margin".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn test_declaration_counts() {
+        let mut extensions = UtilityExtensions::new();
+        extensions.set_declaration_count("prose", 12);
+        extensions.set_declaration_count("scrollbar-*", 2);
+
+        assert_eq!(extensions.get_declaration_count("prose"), 12);
+        assert_eq!(extensions.get_declaration_count("md:prose"), 12);
+        assert_eq!(extensions.get_declaration_count("scrollbar-thin"), 2);
+        assert_eq!(extensions.get_declaration_count("p-4"), 1);
+    }
+
+    #[test]
+    fn test_place_property() {
+        let mut extensions = UtilityExtensions::new();
+
+        extensions
+            .place_property("--tw-prose", PropertyPosition::After("display".to_string()))
+            .unwrap();
+        let display = extensions.get_property_index("display").unwrap();
+        assert_eq!(
+            extensions.get_property_index("--tw-prose"),
+            Some(display + 1)
+        );
+
+        // moving an existing property keeps the order length the same
+        extensions
+            .place_property("padding", PropertyPosition::Before("margin".to_string()))
+            .unwrap();
+        let margin = extensions.get_property_index("margin").unwrap();
+        assert_eq!(extensions.get_property_index("padding"), Some(margin - 1));
+        assert_eq!(extensions.property_order().len(), PROPERTY_ORDER.len() + 1);
+    }
+
+    #[test]
+    fn test_place_property_errors() {
+        let mut extensions = UtilityExtensions::new();
+
+        assert!(
+            extensions
+                .place_property("--x", PropertyPosition::After("not-a-property".to_string()))
+                .is_err()
+        );
+        assert!(
+            extensions
+                .place_property("margin", PropertyPosition::After("margin".to_string()))
+                .is_err()
+        );
+    }
+}
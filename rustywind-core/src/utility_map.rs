@@ -922,6 +922,14 @@ impl UtilityMap {
         self.match_pattern(utility)
     }
 
+    /// Iterate over the names of all static (exact-match) utilities.
+    ///
+    /// Parameterized utilities like `m-4` are matched by pattern and are not
+    /// included. The iteration order is unspecified.
+    pub fn exact_utilities(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.exact.keys().copied()
+    }
+
     /// Match a utility against known patterns to determine its properties.
     fn match_pattern(&self, utility: &str) -> Option<&'static [&'static str]> {
         // parse utility into base and value
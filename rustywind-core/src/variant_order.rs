@@ -14,6 +14,15 @@
 //! special handling. They are compared recursively: first by their base, then by
 //! their modifier. This matches Tailwind's behavior where `peer-hover` comes
 //! before `peer-focus` because `hover` comes before `focus`.
+//!
+//! ## Plugin Variants
+//!
+//! The free functions of this module only know the core variants. Variants of
+//! plugins, like `prose-headings` or `@md`, are only ordered through a
+//! [`VariantOrder`] whose utility extensions enable the plugin.
+
+use crate::plugins::{Plugin, plugin_variant_index, plugin_variant_sort_key, variant_plugin};
+use crate::utility_extensions::UtilityExtensions;
 
 /// The canonical order of variants from Tailwind CSS.
 ///
@@ -125,6 +134,8 @@ pub struct VariantInfo {
     pub base: String,
     /// Optional modifier for compound variants (e.g., "hover" in "peer-hover")
     pub modifier: Option<Box<VariantInfo>>,
+    /// The enabled plugin the base variant comes from (e.g., typography for "prose-a")
+    plugin: Option<Plugin>,
 }
 
 impl VariantInfo {
@@ -133,6 +144,7 @@ impl VariantInfo {
         Self {
             base: base.to_string(),
             modifier: None,
+            plugin: None,
         }
     }
 
@@ -141,6 +153,7 @@ impl VariantInfo {
         Self {
             base: base.to_string(),
             modifier: Some(Box::new(modifier)),
+            plugin: None,
         }
     }
 
@@ -151,17 +164,12 @@ impl VariantInfo {
     /// - "peer-hover" -> VariantInfo { base: "peer", modifier: Some("hover") }
     /// - "not-focus" -> VariantInfo { base: "not", modifier: Some("focus") }
     pub fn parse(variant: &str) -> Self {
-        // check for compound variants (peer-*, group-*, not-*)
-        if (variant.starts_with("peer-")
-            || variant.starts_with("group-")
-            || variant.starts_with("not-"))
-            && let Some(dash_pos) = variant.find('-')
-        {
-            let base = &variant[..dash_pos];
-            let modifier_str = &variant[dash_pos + 1..];
-            return Self::compound(base, Self::parse(modifier_str));
-        }
-        Self::simple(variant)
+        VariantOrder::default().parse(variant)
+    }
+
+    /// The index of the base variant in the canonical order.
+    pub(crate) fn index(&self) -> Option<usize> {
+        get_variant_index(&self.base).or_else(|| plugin_variant_index(self.plugin?))
     }
 
     /// Compare two variant infos according to Tailwind's rules.
@@ -184,8 +192,8 @@ impl VariantInfo {
         // - peer-hover: < peer-focus: (hover comes before focus)
         {
             // compound variants or modifiers: use indices
-            let self_idx = get_variant_index(&self.base);
-            let other_idx = get_variant_index(&other.base);
+            let self_idx = self.index();
+            let other_idx = other.index();
 
             match (self_idx, other_idx) {
                 (Some(a), Some(b)) => {
@@ -196,9 +204,7 @@ impl VariantInfo {
                                 (Some(m1), Some(m2)) => m1.cmp_variants_internal(m2, false), // NOT top level
                                 (Some(_), None) => Ordering::Greater, // Compound after simple
                                 (None, Some(_)) => Ordering::Less,    // Simple before compound
-                                (None, None) => {
-                                    compare_dynamic_variant_bases(&self.base, &other.base)
-                                }
+                                (None, None) => compare_dynamic_variant_bases(self, other),
                             }
                         }
                         other => other,
@@ -212,7 +218,17 @@ impl VariantInfo {
     }
 }
 
-fn compare_dynamic_variant_bases(a: &str, b: &str) -> std::cmp::Ordering {
+impl std::fmt::Display for VariantInfo {
+    /// Formats the variant as written, e.g. `peer-hover`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.modifier {
+            Some(modifier) => write!(f, "{}-{modifier}", self.base),
+            None => write!(f, "{}", self.base),
+        }
+    }
+}
+
+fn compare_dynamic_variant_bases(a: &VariantInfo, b: &VariantInfo) -> std::cmp::Ordering {
     use std::cmp::Ordering;
 
     match (dynamic_variant_sort_key(a), dynamic_variant_sort_key(b)) {
@@ -220,16 +236,22 @@ fn compare_dynamic_variant_bases(a: &str, b: &str) -> std::cmp::Ordering {
             a_kind.cmp(&b_kind).then_with(|| a_value.cmp(b_value))
         }
         _ => {
-            if a == b {
+            if a.base == b.base {
                 Ordering::Equal
             } else {
-                a.cmp(b)
+                a.base.cmp(&b.base)
             }
         }
     }
 }
 
-fn dynamic_variant_sort_key(variant: &str) -> Option<(u8, &str)> {
+fn dynamic_variant_sort_key(info: &VariantInfo) -> Option<(u8, &str)> {
+    let variant = info.base.as_str();
+
+    if let Some(plugin) = info.plugin {
+        return plugin_variant_sort_key(plugin, variant);
+    }
+
     if let Some(value) = variant.strip_prefix("nth-last-of-type-") {
         return Some(arbitrary_value_sort_key(value));
     }
@@ -296,7 +318,8 @@ fn bracket_inner(value: &str) -> Option<&str> {
 /// Get the index of a variant in the canonical order.
 ///
 /// Returns `Some(index)` if the variant is found, or `None` if it's not in the list.
-/// Lower indices mean the variant should appear earlier in the sorted output.
+/// Lower indices mean the variant should appear earlier in the sorted output. Plugin
+/// variants are only known to [`VariantOrder::variant_index`].
 ///
 /// In Tailwind's bitwise sorting system, each variant gets a bit position based on
 /// its index. Classes without variants have a variant order of 0 and always appear first.
@@ -380,6 +403,16 @@ pub fn get_variant_index(variant: &str) -> Option<usize> {
     None
 }
 
+/// Get the name of the variant at the given index of the canonical order, including
+/// the variant slots of compiled in plugins, e.g. `prose-*`.
+pub fn variant_at(index: usize) -> Option<&'static str> {
+    VARIANT_ORDER.get(index).copied().or_else(|| {
+        crate::plugins::PLUGIN_VARIANT_SLOTS
+            .get(index.checked_sub(VARIANT_ORDER.len())?)
+            .copied()
+    })
+}
+
 /// Parse a list of variant strings into structured variant infos.
 ///
 /// This function converts raw variant strings into `VariantInfo` structures that
@@ -394,7 +427,7 @@ pub fn get_variant_index(variant: &str) -> Option<usize> {
 /// assert_eq!(variants.len(), 2);
 /// ```
 pub fn parse_variants(variants: &[&str]) -> Vec<VariantInfo> {
-    variants.iter().map(|v| VariantInfo::parse(v)).collect()
+    VariantOrder::default().parse_variants(variants)
 }
 
 /// Compare two lists of variants according to Tailwind's rules.
@@ -434,7 +467,7 @@ pub fn compare_variant_lists(a: &[VariantInfo], b: &[VariantInfo]) -> std::cmp::
 fn compare_variant_base_order(a: &VariantInfo, b: &VariantInfo) -> std::cmp::Ordering {
     use std::cmp::Ordering;
 
-    match (get_variant_index(&a.base), get_variant_index(&b.base)) {
+    match (a.index(), b.index()) {
         (Some(a_idx), Some(b_idx)) => a_idx.cmp(&b_idx),
         (Some(_), None) => Ordering::Less,
         (None, Some(_)) => Ordering::Greater,
@@ -479,53 +512,129 @@ fn compare_variant_base_order(a: &VariantInfo, b: &VariantInfo) -> std::cmp::Ord
 pub(crate) const ARBITRARY_VARIANT_BIT: u128 = 1u128 << 120;
 
 pub fn calculate_variant_order(variants: &[&str]) -> u128 {
-    if variants.is_empty() {
-        return 0;
+    VariantOrder::default().calculate_variant_order(variants)
+}
+
+/// Orders variants by the core variant order and the variants of the plugins
+/// enabled in a set of utility extensions.
+///
+/// The default order only knows the core variants.
+///
+/// # Examples
+///
+/// ```
+/// use rustywind_core::variant_order::VariantOrder;
+///
+/// assert_eq!(VariantOrder::default().variant_index("prose-a"), None);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VariantOrder<'a> {
+    extensions: Option<&'a UtilityExtensions>,
+}
+
+impl<'a> VariantOrder<'a> {
+    /// Create a variant order that knows the variants of the plugins enabled in
+    /// `extensions`.
+    pub fn new(extensions: Option<&'a UtilityExtensions>) -> Self {
+        Self { extensions }
     }
 
-    let mut order = 0u128;
-    let mut has_arbitrary = false;
+    /// The plugin of a variant, if the plugin is enabled.
+    fn enabled_plugin(&self, variant: &str) -> Option<Plugin> {
+        let plugin = variant_plugin(variant)?;
+        let extensions = self.extensions?;
 
-    for variant in variants {
-        if let Some(idx) = get_variant_index(variant) {
-            // known variant - set bit at its index
-            if idx < 120 {
-                order |= 1u128 << idx;
-            }
-        } else if variant.starts_with('[') {
-            // arbitrary variant (e.g., [&.htmx-request], [&>*], [@supports...])
-            has_arbitrary = true;
-        } else if variant.contains('-') {
-            // handle compound variants like "peer-hover", "group-focus", or "peer-focus-within"
-            // CRITICAL: For compound variants, use ONLY the base part (peer, group) for sorting
-            // The modifier (hover, focus) is used for tiebreaking elsewhere, not in bitwise order
-            // this makes peer-hover sort at peer's position, not hover's position
-            if let Some(dash_pos) = variant.find('-') {
-                let first_part = &variant[..dash_pos];
-
-                // only add the first part (base variant) to the order
-                // this ensures peer-hover sorts near peer, not near hover
-                if let Some(idx) = get_variant_index(first_part)
-                    && idx < 120
-                {
+        extensions.has_plugin(plugin).then_some(plugin)
+    }
+
+    /// Get the index of a variant in the canonical order, see [`get_variant_index`].
+    pub fn variant_index(&self, variant: &str) -> Option<usize> {
+        get_variant_index(variant).or_else(|| plugin_variant_index(self.enabled_plugin(variant)?))
+    }
+
+    /// The named variants of the plugins enabled in the extensions.
+    pub fn plugin_variants(&self) -> Vec<String> {
+        self.extensions
+            .iter()
+            .flat_map(|extensions| extensions.plugins())
+            .flat_map(|plugin| plugin.variants())
+            .collect()
+    }
+
+    /// Parse a variant string into structured form, see [`VariantInfo::parse`].
+    pub fn parse(&self, variant: &str) -> VariantInfo {
+        // check for compound variants (peer-*, group-*, not-*)
+        if (variant.starts_with("peer-")
+            || variant.starts_with("group-")
+            || variant.starts_with("not-"))
+            && let Some(dash_pos) = variant.find('-')
+        {
+            let base = &variant[..dash_pos];
+            let modifier_str = &variant[dash_pos + 1..];
+            return VariantInfo::compound(base, self.parse(modifier_str));
+        }
+
+        VariantInfo {
+            plugin: self.enabled_plugin(variant),
+            ..VariantInfo::simple(variant)
+        }
+    }
+
+    /// Parse a list of variant strings, see [`parse_variants`].
+    pub fn parse_variants(&self, variants: &[&str]) -> Vec<VariantInfo> {
+        variants.iter().map(|v| self.parse(v)).collect()
+    }
+
+    /// Calculate the variant order as a bitwise flag, see [`calculate_variant_order`].
+    pub fn calculate_variant_order(&self, variants: &[&str]) -> u128 {
+        if variants.is_empty() {
+            return 0;
+        }
+
+        let mut order = 0u128;
+        let mut has_arbitrary = false;
+
+        for variant in variants {
+            if let Some(idx) = self.variant_index(variant) {
+                // known variant - set bit at its index
+                if idx < 120 {
                     order |= 1u128 << idx;
-                } else {
-                    has_arbitrary = true;
                 }
+            } else if variant.starts_with('[') {
+                // arbitrary variant (e.g., [&.htmx-request], [&>*], [@supports...])
+                has_arbitrary = true;
+            } else if variant.contains('-') {
+                // handle compound variants like "peer-hover", "group-focus", or "peer-focus-within"
+                // CRITICAL: For compound variants, use ONLY the base part (peer, group) for sorting
+                // The modifier (hover, focus) is used for tiebreaking elsewhere, not in bitwise order
+                // this makes peer-hover sort at peer's position, not hover's position
+                if let Some(dash_pos) = variant.find('-') {
+                    let first_part = &variant[..dash_pos];
+
+                    // only add the first part (base variant) to the order
+                    // this ensures peer-hover sorts near peer, not near hover
+                    if let Some(idx) = self.variant_index(first_part)
+                        && idx < 120
+                    {
+                        order |= 1u128 << idx;
+                    } else {
+                        has_arbitrary = true;
+                    }
+                }
+            } else {
+                // unknown variant - treat as arbitrary
+                has_arbitrary = true;
             }
-        } else {
-            // unknown variant - treat as arbitrary
-            has_arbitrary = true;
         }
-    }
 
-    // set a high bit for any class with arbitrary variants
-    // this ensures: hover:block sorts before [&.a]:block
-    if has_arbitrary {
-        order |= ARBITRARY_VARIANT_BIT;
-    }
+        // set a high bit for any class with arbitrary variants
+        // this ensures: hover:block sorts before [&.a]:block
+        if has_arbitrary {
+            order |= ARBITRARY_VARIANT_BIT;
+        }
 
-    order
+        order
+    }
 }
 
 #[cfg(test)]
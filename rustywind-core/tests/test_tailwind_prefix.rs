@@ -1,6 +1,6 @@
 use rustywind_core::RustyWind;
 use rustywind_core::hybrid_sorter::HybridSorter;
-use rustywind_core::sorter::{FinderRegex, Sorter};
+use rustywind_core::sorter::Sorter;
 use rustywind_core::tailwind_prefix::normalize_tailwind_prefix;
 
 fn normalize_all(classes: Vec<&str>, prefix: &str) -> Vec<String> {
@@ -87,13 +87,7 @@ fn prefixed_sort_key_uses_normalized_class() {
 
 #[test]
 fn rustywind_flag_preserves_original_prefixed_classes_in_output() {
-    let app = RustyWind {
-        regex: FinderRegex::DefaultRegex,
-        sorter: Sorter::PatternSorter,
-        allow_duplicates: false,
-        class_wrapping: Default::default(),
-        tailwind_prefix: Some("tw".to_string()),
-    };
+    let app = RustyWind::default().with_tailwind_prefix(Some("tw".to_string()));
 
     let input = r#"<div class="tw:p-4 tw:bg-white tw:md:text-xl tw:hover:-mr-4"></div>"#;
     let sorted = app.sort_file_contents(input);
@@ -107,17 +101,13 @@ fn rustywind_flag_preserves_original_prefixed_classes_in_output() {
 
 #[test]
 fn custom_sorter_uses_normalized_prefixed_fallback_after_exact_lookup() {
-    let app = RustyWind {
-        regex: FinderRegex::DefaultRegex,
-        sorter: Sorter::new(
+    let app = RustyWind::default()
+        .with_sorter(Sorter::new(
             [("bg-white".to_string(), 0), ("p-4".to_string(), 1)]
                 .into_iter()
                 .collect(),
-        ),
-        allow_duplicates: false,
-        class_wrapping: Default::default(),
-        tailwind_prefix: Some("tw".to_string()),
-    };
+        ))
+        .with_tailwind_prefix(Some("tw".to_string()));
 
     assert_eq!(app.sort_classes("tw-p-4 tw-bg-white"), "tw-bg-white tw-p-4");
     assert_eq!(app.sort_classes("tw:p-4 tw:bg-white"), "tw:bg-white tw:p-4");
@@ -125,17 +115,13 @@ fn custom_sorter_uses_normalized_prefixed_fallback_after_exact_lookup() {
 
 #[test]
 fn custom_sorter_variant_fallback_keeps_v3_prefixed_exact_order() {
-    let app = RustyWind {
-        regex: FinderRegex::DefaultRegex,
-        sorter: Sorter::new(
+    let app = RustyWind::default()
+        .with_sorter(Sorter::new(
             [("tw-bg-white".to_string(), 0), ("tw-p-4".to_string(), 1)]
                 .into_iter()
                 .collect(),
-        ),
-        allow_duplicates: false,
-        class_wrapping: Default::default(),
-        tailwind_prefix: Some("tw".to_string()),
-    };
+        ))
+        .with_tailwind_prefix(Some("tw".to_string()));
 
     assert_eq!(
         app.sort_classes("md:tw-p-4 md:tw-bg-white"),
@@ -145,17 +131,13 @@ fn custom_sorter_variant_fallback_keeps_v3_prefixed_exact_order() {
 
 #[test]
 fn custom_sorter_variant_fallback_keeps_v4_prefixed_exact_order() {
-    let app = RustyWind {
-        regex: FinderRegex::DefaultRegex,
-        sorter: Sorter::new(
+    let app = RustyWind::default()
+        .with_sorter(Sorter::new(
             [("tw:bg-white".to_string(), 0), ("tw:p-4".to_string(), 1)]
                 .into_iter()
                 .collect(),
-        ),
-        allow_duplicates: false,
-        class_wrapping: Default::default(),
-        tailwind_prefix: Some("tw".to_string()),
-    };
+        ))
+        .with_tailwind_prefix(Some("tw".to_string()));
 
     assert_eq!(
         app.sort_classes("tw:md:p-4 tw:md:bg-white"),